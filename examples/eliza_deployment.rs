@@ -74,7 +74,7 @@ async fn main() -> Result<(), Error> {
 
         match deployer.get_network_info(&app_id).await {
             Ok(network_info) => {
-                if network_info.is_online && !network_info.public_urls.app.is_empty() {
+                if network_info.is_ready() {
                     println!("🌐 Network is ready!");
                     println!("🔗 Access your ELIZA instance at:");
                     println!("   - App URL: {}", network_info.public_urls.app);
@@ -104,9 +104,7 @@ async fn main() -> Result<(), Error> {
                 stats.sysinfo.total_memory as f64 / 1024.0 / 1024.0 / 1024.0
             );
 
-            // Calculate CPU usage based on load average
-            let cpu_usage = stats.sysinfo.loadavg_one * 100.0 / stats.sysinfo.num_cpus as f32;
-            println!("   - CPU Usage: {:.2}%", cpu_usage);
+            println!("   - CPU Usage: {:.2}%", stats.sysinfo.cpu_load_percent());
 
             // Display disk info if available
             if !stats.sysinfo.disks.is_empty() {
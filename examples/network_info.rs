@@ -1,6 +1,5 @@
 use phala_tee_deploy_rs::{DeploymentConfig, Error, Result, TeeClient};
 use serde_json::json;
-use std::collections::HashMap;
 use std::env;
 use std::time::Duration;
 
@@ -23,15 +22,12 @@ async fn main() -> Result<()> {
     println!("Using API endpoint: {}", api_url);
 
     // Initialize client with minimal configuration
-    let client = TeeClient::new(DeploymentConfig {
-        api_key,
-        api_url,
-        docker_compose: String::new(),
-        env_vars: HashMap::new(),
-        teepod_id: 0,
-        image: String::new(),
-        vm_config: None,
-    })?;
+    let client = TeeClient::new(
+        DeploymentConfig::builder()
+            .with_api_key(api_key)
+            .with_api_url(api_url)
+            .build()?,
+    )?;
 
     // ===== STEP 1: DISCOVER TEEPOD =====
     println!("\n🔍 Discovering available TEEPods...");
@@ -109,8 +105,8 @@ services:
         .deploy_with_config_do_encrypt(
             vm_config,
             &env_vars,
-            &pubkey_response.app_env_encrypt_pubkey,
-            &pubkey_response.app_id_salt,
+            &pubkey_response.public_key()?,
+            &pubkey_response.salt()?,
         )
         .await?;
 
@@ -173,7 +169,7 @@ services:
             );
             println!("   Public: {}", if info.is_public { "Yes" } else { "No" });
 
-            if let Some(error) = info.error {
+            if let Some(error) = &info.error {
                 println!("⚠️ Error: {}", error);
             }
 
@@ -183,8 +179,8 @@ services:
             println!("\n🌐 Public URLs:");
 
             // Check if app URL is available before displaying
-            if !info.public_urls.app.is_empty() {
-                println!("   Application URL: {}", info.public_urls.app);
+            if let Some(app_url) = info.app_url() {
+                println!("   Application URL: {}", app_url);
                 println!(
                     "\n✅ Notice that the app_id '{}' is included in the URL",
                     app_id
@@ -193,17 +189,17 @@ services:
                 println!("   is the correct identifier for your deployment.");
 
                 println!("\n✅ You can access your application at:");
-                println!("   {}", info.public_urls.app);
+                println!("   {}", app_url);
                 println!("\n   Try opening this URL in your browser or run:");
-                println!("   curl {}", info.public_urls.app);
+                println!("   curl {}", app_url);
             } else {
                 println!("   Application URL not yet available");
                 println!("   The deployment may still be initializing.");
                 println!("   Try running this example again in a few minutes.");
             }
 
-            if !info.public_urls.instance.is_empty() {
-                println!("   Instance URL: {}", info.public_urls.instance);
+            if let Some(instance_url) = info.instance_url() {
+                println!("   Instance URL: {}", instance_url);
             }
 
             println!("\n🔍 Key Takeaways:");
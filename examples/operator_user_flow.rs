@@ -1,6 +1,5 @@
 use phala_tee_deploy_rs::{DeploymentConfig, Error, TeeClient};
 use serde_json::json;
-use std::collections::HashMap;
 use std::env;
 
 /// This example demonstrates a security-focused deployment pattern with separation of concerns:
@@ -16,16 +15,15 @@ async fn main() -> Result<(), Error> {
     println!("🔷 OPERATOR: Setting up deployment environment");
 
     // Initialize client with minimal configuration
-    let client = TeeClient::new(DeploymentConfig {
-        api_key: env::var("PHALA_CLOUD_API_KEY").expect("API key required"),
-        api_url: env::var("PHALA_CLOUD_API_ENDPOINT")
-            .unwrap_or_else(|_| "https://cloud-api.phala.network/api/v1".to_string()),
-        docker_compose: String::new(),
-        env_vars: HashMap::new(),
-        teepod_id: 0,
-        image: String::new(),
-        vm_config: None,
-    })?;
+    let client = TeeClient::new(
+        DeploymentConfig::builder()
+            .with_api_key(env::var("PHALA_CLOUD_API_KEY").expect("API key required"))
+            .with_api_url(
+                env::var("PHALA_CLOUD_API_ENDPOINT")
+                    .unwrap_or_else(|_| "https://cloud-api.phala.network/api/v1".to_string()),
+            )
+            .build()?,
+    )?;
 
     // 1. Get available infrastructure
     println!("🔷 OPERATOR: Discovering available TEEPods");
@@ -57,8 +55,8 @@ services:
     // 3. Get encryption key
     println!("🔷 OPERATOR: Obtaining encryption key from Phala Cloud");
     let pubkey_response = client.get_pubkey_for_config(&vm_config).await?;
-    let pubkey = pubkey_response.app_env_encrypt_pubkey;
-    let salt = pubkey_response.app_id_salt;
+    let pubkey = pubkey_response.public_key()?;
+    let salt = pubkey_response.salt()?;
     let app_id = pubkey_response.app_id.clone();
 
     // Construct the full application identifier with the required "app_" prefix
@@ -68,7 +66,10 @@ services:
     println!("🔷 OPERATOR: Sending public key to user via secure channel\n");
 
     // ============ USER ACTIONS ============
-    println!("🔶 USER: Received public key: {}...", &pubkey[0..16]);
+    println!(
+        "🔶 USER: Received public key: {}...",
+        &pubkey.as_str()[0..16]
+    );
 
     // 1. User defines sensitive environment variables
     println!("🔶 USER: Preparing sensitive environment variables");
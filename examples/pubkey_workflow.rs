@@ -1,7 +1,6 @@
 use dotenv::dotenv;
 use phala_tee_deploy_rs::{DeploymentConfig, Encryptor, Error, TeeClient};
 use serde_json::json;
-use std::collections::HashMap;
 use std::env;
 
 /// This example demonstrates the precise 3-step workflow:
@@ -14,16 +13,13 @@ async fn main() -> Result<(), Error> {
     dotenv().ok();
 
     // Set up the API client configuration
-    let config = DeploymentConfig {
-        api_key: env::var("PHALA_API_KEY").expect("PHALA_API_KEY must be set"),
-        api_url: env::var("PHALA_API_ENDPOINT")
-            .unwrap_or_else(|_| "https://cloud-api.phala.network/api/v1".to_string()),
-        docker_compose: String::new(),
-        env_vars: HashMap::new(),
-        teepod_id: 0,
-        image: String::new(),
-        vm_config: None,
-    };
+    let config = DeploymentConfig::builder()
+        .with_api_key(env::var("PHALA_API_KEY").expect("PHALA_API_KEY must be set"))
+        .with_api_url(
+            env::var("PHALA_API_ENDPOINT")
+                .unwrap_or_else(|_| "https://cloud-api.phala.network/api/v1".to_string()),
+        )
+        .build()?;
 
     let client = TeeClient::new(config)?;
 
@@ -55,8 +51,8 @@ services:
     println!("Step 2: Retrieving public key for encryption");
     let pubkey_response = client.get_pubkey_for_config(&vm_config).await?;
 
-    let public_key = pubkey_response.app_env_encrypt_pubkey;
-    let salt = pubkey_response.app_id_salt;
+    let public_key = pubkey_response.public_key()?;
+    let salt = pubkey_response.salt()?;
     let app_id = pubkey_response.app_id.clone();
 
     // Construct the full application identifier with the required "app_" prefix
@@ -82,7 +78,7 @@ services:
         ("API_KEY".to_string(), "user-api-key-456".to_string()),
     ];
     // Encrypt the environment variables
-    let encrypted_env = Encryptor::encrypt_env_vars(&user_secrets, &public_key)?;
+    let encrypted_env = Encryptor::encrypt_env_vars(&user_secrets, public_key.as_str())?;
 
     // STEP 4: Call /cvms/from_cvm_configuration with the VM config and encrypted env
     println!("Step 4: Deploying with the VM configuration and encrypted environment");
@@ -1,6 +1,4 @@
-use phala_tee_deploy_rs::{Encryptor, Error, Result, TeeDeployerBuilder};
-use serde_json::{json, Value};
-use std::collections::HashMap;
+use phala_tee_deploy_rs::{encrypt_env_for_pubkey, Result, TeeDeployerBuilder};
 use std::env;
 
 /// This example demonstrates a secure deployment workflow with separation of concerns:
@@ -58,14 +56,14 @@ services:
         Some(10),   // 10 GB disk
     )?;
 
-    let vm_config_json = serde_json::to_value(&vm_config).unwrap();
+    let vm_config_json: serde_json::Value = vm_config.into();
 
     // Get the public key for this VM configuration
     println!("🔑 Requesting encryption public key...");
     let pubkey_response = deployer.get_pubkey_for_config(&vm_config_json).await?;
 
-    let public_key = pubkey_response.app_env_encrypt_pubkey;
-    let salt = pubkey_response.app_id_salt;
+    let public_key = pubkey_response.public_key()?;
+    let salt = pubkey_response.salt()?;
     let app_id = pubkey_response.app_id.clone();
 
     println!("✅ Public key obtained: {}", public_key);
@@ -91,7 +89,7 @@ services:
 
     // User encrypts their environment variables with the public key
     println!("🔐 Encrypting environment variables...");
-    let encrypted_env = Encryptor::encrypt_env_vars(&user_env_vars, &public_key)?;
+    let encrypted_env = encrypt_env_for_pubkey(&user_env_vars, public_key.as_str())?;
     println!("✅ Environment variables encrypted successfully");
 
     // At this point, the user would securely send the encrypted env vars back to the operator
@@ -1,6 +1,6 @@
-use phala_tee_deploy_rs::{Error, Result, SystemStatsResponse, TeeDeployer};
+use phala_tee_deploy_rs::{Result, TeeDeployer};
 use serde_json::json;
-use std::{collections::HashMap, env, time::Duration};
+use std::{env, time::Duration};
 
 /// This example demonstrates how to deploy an application and retrieve its system statistics
 /// in the Phala TEE Cloud.
@@ -81,8 +81,8 @@ services:
         .deploy_with_config_do_encrypt(
             vm_config,
             &env_vars,
-            &pubkey_response.app_env_encrypt_pubkey,
-            &pubkey_response.app_id_salt,
+            &pubkey_response.public_key()?,
+            &pubkey_response.salt()?,
         )
         .await?;
 
@@ -137,13 +137,13 @@ services:
 
     match system_stats {
         Some(stats) => {
-            display_system_stats(&stats);
+            println!("\n{}", stats);
         }
         None => {
             // Make one final attempt
             println!("   Making one final attempt to get system stats...");
             let stats = deployer.get_system_stats(&full_app_id).await?;
-            display_system_stats(&stats);
+            println!("\n{}", stats);
         }
     }
 
@@ -153,107 +153,3 @@ services:
 
     Ok(())
 }
-
-/// Display system statistics in a readable format
-fn display_system_stats(stats: &SystemStatsResponse) {
-    // Status information
-    println!("\n=== System Status ===");
-    println!(
-        "Online: {}",
-        if stats.is_online {
-            "🟢 Yes"
-        } else {
-            "🔴 No"
-        }
-    );
-    println!(
-        "Publicly accessible: {}",
-        if stats.is_public { "Yes" } else { "No" }
-    );
-
-    if let Some(error) = &stats.error {
-        println!("⚠️ Error: {}", error);
-    }
-
-    // Operating system information
-    println!("\n=== OS Information ===");
-    println!("OS: {} {}", stats.sysinfo.os_name, stats.sysinfo.os_version);
-    println!("Kernel version: {}", stats.sysinfo.kernel_version);
-
-    // CPU information
-    println!("\n=== CPU Information ===");
-    println!("CPU model: {}", stats.sysinfo.cpu_model);
-    println!("Number of CPUs: {}", stats.sysinfo.num_cpus);
-
-    // Format memory values in a human-readable way (MB or GB)
-    println!("\n=== Memory Usage ===");
-    println!(
-        "Total memory: {} MB",
-        stats.sysinfo.total_memory / 1024 / 1024
-    );
-    println!(
-        "Used memory: {} MB ({:.1}%)",
-        stats.sysinfo.used_memory / 1024 / 1024,
-        (stats.sysinfo.used_memory as f64 / stats.sysinfo.total_memory as f64) * 100.0
-    );
-    println!(
-        "Free memory: {} MB",
-        stats.sysinfo.free_memory / 1024 / 1024
-    );
-
-    // Swap information
-    if stats.sysinfo.total_swap > 0 {
-        println!("\n=== Swap Usage ===");
-        println!("Total swap: {} MB", stats.sysinfo.total_swap / 1024 / 1024);
-        println!(
-            "Used swap: {} MB ({:.1}%)",
-            stats.sysinfo.used_swap / 1024 / 1024,
-            (stats.sysinfo.used_swap as f64 / stats.sysinfo.total_swap as f64) * 100.0
-        );
-        println!("Free swap: {} MB", stats.sysinfo.free_swap / 1024 / 1024);
-    }
-
-    // Load averages
-    println!("\n=== System Load ===");
-    println!(
-        "Load averages: {:.2} (1m), {:.2} (5m), {:.2} (15m)",
-        stats.sysinfo.loadavg_one, stats.sysinfo.loadavg_five, stats.sysinfo.loadavg_fifteen
-    );
-
-    // Uptime
-    let uptime_days = stats.sysinfo.uptime / (60 * 60 * 24);
-    let uptime_hours = (stats.sysinfo.uptime / (60 * 60)) % 24;
-    let uptime_minutes = (stats.sysinfo.uptime / 60) % 60;
-    let uptime_seconds = stats.sysinfo.uptime % 60;
-
-    println!("\n=== Uptime ===");
-    println!(
-        "System uptime: {}d {}h {}m {}s",
-        uptime_days, uptime_hours, uptime_minutes, uptime_seconds
-    );
-
-    // Disk information
-    println!("\n=== Disk Information ===");
-    if stats.sysinfo.disks.is_empty() {
-        println!("No disk information available");
-    } else {
-        for (i, disk) in stats.sysinfo.disks.iter().enumerate() {
-            println!("Disk {}: {}", i + 1, disk.name);
-
-            if let Some(mount) = &disk.mount_point {
-                println!("  Mount point: {}", mount);
-            }
-
-            let total_gb = disk.total_size as f64 / 1024.0 / 1024.0 / 1024.0;
-            let free_gb = disk.free_size as f64 / 1024.0 / 1024.0 / 1024.0;
-            let used_gb = total_gb - free_gb;
-            let used_percent = (used_gb / total_gb) * 100.0;
-
-            println!("  Total size: {:.2} GB", total_gb);
-            println!("  Used: {:.2} GB ({:.1}%)", used_gb, used_percent);
-            println!("  Free: {:.2} GB", free_gb);
-        }
-    }
-
-    println!("\n=== End of System Stats ===");
-}
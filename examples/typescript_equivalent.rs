@@ -1,6 +1,5 @@
 use phala_tee_deploy_rs::{DeploymentConfig, Error, Result, TeeClient};
 use serde_json::json;
-use std::collections::HashMap;
 use std::env;
 
 /// This example demonstrates a step-by-step deployment process that mirrors
@@ -11,16 +10,15 @@ async fn main() -> Result<()> {
     dotenv::dotenv().ok();
 
     // Create a minimal client with just the API credentials
-    let client = TeeClient::new(DeploymentConfig {
-        api_key: env::var("PHALA_CLOUD_API_KEY").expect("API key required"),
-        api_url: env::var("PHALA_CLOUD_API_ENDPOINT")
-            .unwrap_or_else(|_| "https://cloud-api.phala.network/api/v1".to_string()),
-        docker_compose: String::new(),
-        env_vars: HashMap::new(),
-        teepod_id: 0,
-        image: String::new(),
-        vm_config: None,
-    })?;
+    let client = TeeClient::new(
+        DeploymentConfig::builder()
+            .with_api_key(env::var("PHALA_CLOUD_API_KEY").expect("API key required"))
+            .with_api_url(
+                env::var("PHALA_CLOUD_API_ENDPOINT")
+                    .unwrap_or_else(|_| "https://cloud-api.phala.network/api/v1".to_string()),
+            )
+            .build()?,
+    )?;
 
     // ===== PHASE 1: SELECT TEEPOD =====
     println!("1. Discovering available TEEPods...");
@@ -86,8 +84,8 @@ services:
     let pubkey_response = client.get_pubkey_for_config(&vm_config).await?;
 
     // Access the strongly typed response
-    let pubkey = &pubkey_response.app_env_encrypt_pubkey;
-    let salt = &pubkey_response.app_id_salt;
+    let pubkey = pubkey_response.public_key()?;
+    let salt = pubkey_response.salt()?;
     let app_id = pubkey_response.app_id.clone();
 
     // Construct the full application identifier with the required "app_" prefix
@@ -107,7 +105,7 @@ services:
     // ===== PHASE 5: DEPLOY =====
     println!("5. Deploying to TEE environment...");
     let deployment = client
-        .deploy_with_config_do_encrypt(vm_config, &env_vars, pubkey, salt)
+        .deploy_with_config_do_encrypt(vm_config, &env_vars, &pubkey, &salt)
         .await?;
 
     // ===== RESULT =====
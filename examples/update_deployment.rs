@@ -26,16 +26,15 @@ async fn main() -> Result<()> {
     println!("Using application identifier: {}", prefixed_app_id);
 
     // Initialize client with minimal configuration
-    let client = TeeClient::new(DeploymentConfig {
-        api_key: env::var("PHALA_CLOUD_API_KEY").expect("API key required"),
-        api_url: env::var("PHALA_CLOUD_API_ENDPOINT")
-            .unwrap_or_else(|_| "https://cloud-api.phala.network/api/v1".to_string()),
-        docker_compose: String::new(),
-        env_vars: HashMap::new(),
-        teepod_id: 0,
-        image: String::new(),
-        vm_config: None,
-    })?;
+    let client = TeeClient::new(
+        DeploymentConfig::builder()
+            .with_api_key(env::var("PHALA_CLOUD_API_KEY").expect("API key required"))
+            .with_api_url(
+                env::var("PHALA_CLOUD_API_ENDPOINT")
+                    .unwrap_or_else(|_| "https://cloud-api.phala.network/api/v1".to_string()),
+            )
+            .build()?,
+    )?;
 
     // ===== PHASE 1: RETRIEVE CURRENT CONFIGURATION =====
     println!("1. Retrieving current deployment configuration...");
@@ -76,12 +75,8 @@ async fn main() -> Result<()> {
     println!("\n✅ Deployment updated successfully!");
     println!("   New configuration applied to: {}", prefixed_app_id);
 
-    // Access the strongly typed response if available, otherwise show raw JSON
-    if let Some(status) = update_response.get("status") {
-        println!("   Update status: {}", status);
-    }
-
-    if let Some(message) = update_response.get("message") {
+    println!("   Update status: {}", update_response.status);
+    if let Some(message) = &update_response.message {
         println!("   Message: {}", message);
     }
 
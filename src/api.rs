@@ -0,0 +1,92 @@
+use async_trait::async_trait;
+
+use crate::{
+    client::TeeClient,
+    error::Error,
+    types::{
+        AppIdSalt, AppPublicKey, DeploymentResponse, NetworkInfoResponse, PubkeyResponse,
+        SystemStatsResponse, TeePodDiscoveryResponse,
+    },
+};
+
+/// Object-safe abstraction over the core Phala Cloud API operations.
+///
+/// `TeeClient` implements this trait, so code that only needs these operations
+/// can accept a `&dyn DeploymentApi` (or `Box<dyn DeploymentApi>`) and have a
+/// hand-written fake injected in tests instead of talking to the real API.
+///
+/// # Examples
+///
+/// ```no_run
+/// use phala_tee_deploy_rs::{DeploymentApi, Result};
+///
+/// async fn first_teepod(api: &dyn DeploymentApi) -> Result<u64> {
+///     let teepods = api.get_available_teepods().await?;
+///     Ok(teepods.nodes[0].teepod_id)
+/// }
+/// ```
+#[async_trait]
+pub trait DeploymentApi: Send + Sync {
+    /// Retrieves the TEEPods available for deployment.
+    async fn get_available_teepods(&self) -> Result<TeePodDiscoveryResponse, Error>;
+
+    /// Retrieves the encryption public key for a VM configuration.
+    async fn get_pubkey_for_config(
+        &self,
+        vm_config: &serde_json::Value,
+    ) -> Result<PubkeyResponse, Error>;
+
+    /// Deploys a VM configuration with pre-encrypted environment variables.
+    async fn deploy_with_config_encrypted_env(
+        &self,
+        vm_config: serde_json::Value,
+        encrypted_env: String,
+        app_env_encrypt_pubkey: &AppPublicKey,
+        app_id_salt: &AppIdSalt,
+    ) -> Result<DeploymentResponse, Error>;
+
+    /// Retrieves network information for a deployed application.
+    async fn get_network_info(&self, app_id: &str) -> Result<NetworkInfoResponse, Error>;
+
+    /// Retrieves system statistics for a deployed application.
+    async fn get_system_stats(&self, app_id: &str) -> Result<SystemStatsResponse, Error>;
+}
+
+#[async_trait]
+impl DeploymentApi for TeeClient {
+    async fn get_available_teepods(&self) -> Result<TeePodDiscoveryResponse, Error> {
+        TeeClient::get_available_teepods(self).await
+    }
+
+    async fn get_pubkey_for_config(
+        &self,
+        vm_config: &serde_json::Value,
+    ) -> Result<PubkeyResponse, Error> {
+        TeeClient::get_pubkey_for_config(self, vm_config).await
+    }
+
+    async fn deploy_with_config_encrypted_env(
+        &self,
+        vm_config: serde_json::Value,
+        encrypted_env: String,
+        app_env_encrypt_pubkey: &AppPublicKey,
+        app_id_salt: &AppIdSalt,
+    ) -> Result<DeploymentResponse, Error> {
+        TeeClient::deploy_with_config_encrypted_env(
+            self,
+            vm_config,
+            encrypted_env,
+            app_env_encrypt_pubkey,
+            app_id_salt,
+        )
+        .await
+    }
+
+    async fn get_network_info(&self, app_id: &str) -> Result<NetworkInfoResponse, Error> {
+        TeeClient::get_network_info(self, app_id).await
+    }
+
+    async fn get_system_stats(&self, app_id: &str) -> Result<SystemStatsResponse, Error> {
+        TeeClient::get_system_stats(self, app_id).await
+    }
+}
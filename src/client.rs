@@ -1,19 +1,32 @@
-use reqwest::Client;
+use flate2::{write::GzEncoder, Compression};
+use reqwest::{Body, Client, RequestBuilder, Response};
+use serde::{de::DeserializeOwned, Serialize, Serializer};
 use serde_json::json;
-use std::collections::HashMap;
-use std::time::Duration;
+use std::collections::{BTreeMap, HashMap};
+use std::io::Write;
+use std::sync::Arc;
 
 use crate::{
-    config::DeploymentConfig,
+    config::{DeploymentConfig, RequestOptions},
     crypto::Encryptor,
     error::Error,
     types::{
-        AttestationResponse, ComposeResponse, CvmInfo, CvmStateResponse, DeploymentResponse,
-        NetworkInfoResponse, SystemStatsResponse, VmConfig,
+        ApiCapabilities, AppIdSalt, AppPublicKey, AttestationResponse, ComposeResponse, CvmInfo,
+        CvmStateResponse, DeploymentResponse, DeploymentSummary, LogsResponse, NetworkInfoResponse,
+        SettingsUpdate, SystemStatsResponse, UpdateResponse, VmConfig,
     },
     PubkeyResponse, TeePodDiscoveryResponse,
 };
 
+/// Callback reporting upload progress as `(bytes_sent, total_bytes)`.
+pub type UploadProgress = Arc<dyn Fn(u64, u64) + Send + Sync>;
+
+/// Size of the chunks request bodies are streamed in when upload progress is reported.
+const UPLOAD_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Largest total size in bytes of environment variable keys and values accepted for encryption.
+const MAX_ENV_PLAINTEXT_BYTES: usize = 32 * 1024 * 1024;
+
 /// Client for interacting with the Phala TEE Cloud API.
 ///
 /// `TeeClient` provides low-level access to the Phala Cloud API for deploying
@@ -32,6 +45,34 @@ use crate::{
 pub struct TeeClient {
     client: Client,
     config: DeploymentConfig,
+    fixed_crypto: Option<FixedCrypto>,
+    upload_progress: Option<UploadProgress>,
+}
+
+/// Serializes a JSON value with object keys sorted at every level, whether or
+/// not serde_json's `preserve_order` feature is enabled by another crate.
+struct SortedJson<'a>(&'a serde_json::Value);
+
+impl Serialize for SortedJson<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.0 {
+            serde_json::Value::Object(map) => serializer.collect_map(
+                map.iter()
+                    .map(|(key, value)| (key, SortedJson(value)))
+                    .collect::<BTreeMap<_, _>>(),
+            ),
+            serde_json::Value::Array(items) => serializer.collect_seq(items.iter().map(SortedJson)),
+            other => other.serialize(serializer),
+        }
+    }
+}
+
+/// Fixed encryption components used to make deploy request bodies deterministic.
+#[derive(Clone, Copy)]
+struct FixedCrypto {
+    ephemeral_pubkey: [u8; 32],
+    shared_secret: [u8; 32],
+    iv: [u8; 12],
 }
 
 impl TeeClient {
@@ -49,12 +90,240 @@ impl TeeClient {
     ///
     /// Returns an error if the HTTP client cannot be created
     pub fn new(config: DeploymentConfig) -> Result<Self, Error> {
+        if config.danger_accept_invalid_certs {
+            tracing::warn!(
+                "TLS certificate verification is disabled for {}; use this only for local development",
+                config.base_url()
+            );
+        }
+
         let client = Client::builder()
-            .timeout(Duration::from_secs(30))
+            .timeout(config.request_timeout)
+            .connect_timeout(config.connect_timeout)
+            .danger_accept_invalid_certs(config.danger_accept_invalid_certs)
             .build()
             .map_err(Error::HttpClient)?;
 
-        Ok(Self { client, config })
+        Ok(Self {
+            client,
+            config,
+            fixed_crypto: None,
+            upload_progress: None,
+        })
+    }
+
+    /// Makes every environment variable encryption performed by this client
+    /// deterministic by using fixed cryptographic components.
+    ///
+    /// This enables byte-exact assertions on deploy request bodies, e.g. when
+    /// comparing against vectors produced by the TypeScript client. It is only
+    /// available in tests or with the `test-util` feature.
+    ///
+    /// IMPORTANT: Never use this outside of tests; reusing an ephemeral key and
+    /// IV eliminates the security guarantees of the encryption scheme.
+    ///
+    /// # Parameters
+    ///
+    /// * `ephemeral_pubkey` - Fixed 32-byte ephemeral public key
+    /// * `shared_secret` - Fixed 32-byte shared secret used as the AES key
+    /// * `iv` - Fixed 12-byte initialization vector
+    ///
+    /// # Returns
+    ///
+    /// The client instance for method chaining
+    #[cfg(any(test, feature = "test-util"))]
+    pub fn with_fixed_crypto(
+        mut self,
+        ephemeral_pubkey: [u8; 32],
+        shared_secret: [u8; 32],
+        iv: [u8; 12],
+    ) -> Self {
+        self.fixed_crypto = Some(FixedCrypto {
+            ephemeral_pubkey,
+            shared_secret,
+            iv,
+        });
+        self
+    }
+
+    /// Reports the progress of request body uploads, such as large deploy
+    /// payloads with embedded ELIZA character files.
+    ///
+    /// Bodies are streamed in chunks and `callback` is invoked with the bytes
+    /// sent so far and the total body size as each chunk is handed to the
+    /// connection. With compression enabled, sizes refer to the compressed body.
+    ///
+    /// # Parameters
+    ///
+    /// * `callback` - Called with `(bytes_sent, total_bytes)`
+    ///
+    /// # Returns
+    ///
+    /// The client instance for method chaining
+    pub fn with_upload_progress(mut self, callback: UploadProgress) -> Self {
+        self.upload_progress = Some(callback);
+        self
+    }
+
+    /// Encrypts environment variables, honouring fixed components when configured.
+    ///
+    /// Encryption is CPU-bound, so it runs on the blocking thread pool to keep
+    /// large payloads such as `CHARACTER_DATA` from stalling the async runtime.
+    /// Payloads above `MAX_ENV_PLAINTEXT_BYTES` are rejected upfront.
+    async fn encrypt_env(
+        &self,
+        env_vars: &[(String, String)],
+        pubkey: &str,
+    ) -> Result<String, Error> {
+        let total: usize = env_vars.iter().map(|(k, v)| k.len() + v.len()).sum();
+        if total > MAX_ENV_PLAINTEXT_BYTES {
+            return Err(Error::Configuration(format!(
+                "Environment variables total {} bytes, above the {} byte limit",
+                total, MAX_ENV_PLAINTEXT_BYTES
+            )));
+        }
+
+        let env_vars = env_vars.to_vec();
+        let pubkey = pubkey.to_string();
+        let fixed_crypto = self.fixed_crypto;
+        tokio::task::spawn_blocking(move || match fixed_crypto {
+            Some(fixed) => Encryptor::encrypt_env_vars_with_fixed_components(
+                &env_vars,
+                &pubkey,
+                fixed.ephemeral_pubkey,
+                fixed.shared_secret,
+                fixed.iv,
+            ),
+            None => Encryptor::encrypt_env_vars(&env_vars, &pubkey),
+        })
+        .await
+        .map_err(|e| Error::Encryption(format!("Encryption task failed: {}", e)))?
+    }
+
+    /// Attaches `body` as the JSON request body with its keys sorted,
+    /// gzip-compressing it when `compress_requests` is enabled in the
+    /// configuration and streaming it when upload progress is reported.
+    fn with_json_body<T: Serialize + ?Sized>(
+        &self,
+        request: RequestBuilder,
+        body: &T,
+    ) -> Result<RequestBuilder, Error> {
+        let body = serde_json::to_value(body)
+            .map_err(|e| Error::Serialization(format!("Failed to serialize request: {}", e)))?;
+        let body = SortedJson(&body);
+        if !self.config.compress_requests && self.upload_progress.is_none() {
+            return Ok(request.json(&body));
+        }
+
+        let json = serde_json::to_vec(&body)
+            .map_err(|e| Error::Serialization(format!("Failed to serialize request: {}", e)))?;
+        let (request, bytes) = if self.config.compress_requests {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            let compressed = encoder
+                .write_all(&json)
+                .and_then(|_| encoder.finish())
+                .map_err(|e| Error::Serialization(format!("Failed to compress request: {}", e)))?;
+            (request.header("Content-Encoding", "gzip"), compressed)
+        } else {
+            (request, json)
+        };
+
+        Ok(match &self.upload_progress {
+            Some(progress) => request
+                .header(reqwest::header::CONTENT_LENGTH, bytes.len())
+                .body(Self::progress_body(bytes, progress.clone())),
+            None => request.body(bytes),
+        })
+    }
+
+    /// Wraps `bytes` in a streaming body that reports each chunk to `progress`.
+    fn progress_body(bytes: Vec<u8>, progress: UploadProgress) -> Body {
+        let total = bytes.len() as u64;
+        let chunks: Vec<Vec<u8>> = bytes
+            .chunks(UPLOAD_CHUNK_SIZE)
+            .map(<[u8]>::to_vec)
+            .collect();
+        let mut sent = 0;
+        Body::wrap_stream(futures_util::stream::iter(chunks.into_iter().map(
+            move |chunk| {
+                sent += chunk.len() as u64;
+                progress(sent, total);
+                Ok::<_, std::io::Error>(chunk)
+            },
+        )))
+    }
+
+    /// Converts an unsuccessful response into an `Error::Api`, stating clearly
+    /// when the API rejected the API key. HTML pages from upstream proxies
+    /// become a concise `Error::UpstreamHtml` instead.
+    async fn api_error(response: Response) -> Error {
+        let status = response.status();
+        let is_html = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.starts_with("text/html"));
+        let body = match response.text().await {
+            Ok(body) => body,
+            Err(e) => return Error::from(e),
+        };
+        let trimmed = body.trim_start();
+        if is_html || trimmed.starts_with("<!DOCTYPE") || trimmed.starts_with("<html") {
+            return Error::UpstreamHtml {
+                status_code: status.as_u16(),
+                message: format!(
+                    "{}: non-JSON HTML error from upstream proxy",
+                    status.canonical_reason().unwrap_or("Unknown status")
+                ),
+                body,
+            };
+        }
+        let message = match status {
+            reqwest::StatusCode::UNAUTHORIZED => format!(
+                "API key was rejected; it may be invalid or expired: {}",
+                body
+            ),
+            reqwest::StatusCode::FORBIDDEN => {
+                format!("API key lacks permission for this endpoint: {}", body)
+            }
+            _ => body,
+        };
+        Error::Api {
+            status_code: status.as_u16(),
+            message,
+        }
+    }
+
+    /// Applies per-call `options` on top of the client defaults.
+    fn with_options(request: RequestBuilder, options: &RequestOptions) -> RequestBuilder {
+        match options.timeout {
+            Some(timeout) => request.timeout(timeout),
+            None => request,
+        }
+    }
+
+    /// Parses a successful response body as JSON, rejecting fields the target
+    /// type does not model when `strict` is enabled in the configuration.
+    async fn parse_json<T: DeserializeOwned>(&self, response: Response) -> Result<T, Error> {
+        if !self.config.strict {
+            return response.json().await.map_err(Error::from);
+        }
+
+        let body = response.text().await?;
+        let mut unknown = Vec::new();
+        let value =
+            serde_ignored::deserialize(&mut serde_json::Deserializer::from_str(&body), |path| {
+                unknown.push(path.to_string())
+            })
+            .map_err(|e| Error::Serialization(format!("Failed to parse response: {}", e)))?;
+
+        if !unknown.is_empty() {
+            return Err(Error::Serialization(format!(
+                "Unexpected field(s) in API response: {}",
+                unknown.join(", ")
+            )));
+        }
+        Ok(value)
     }
 
     /// Deploys a container to the TEE environment using the client's configuration.
@@ -73,13 +342,94 @@ impl TeeClient {
     /// * Environment variables cannot be encrypted
     /// * The API returns an error response
     pub async fn deploy(&self) -> Result<DeploymentResponse, Error> {
+        self.deploy_with_options(&RequestOptions::default()).await
+    }
+
+    /// Deploys using the client's configuration, with per-call request options.
+    ///
+    /// Behaves like `deploy`; `options` apply to both the pubkey and the
+    /// deployment request, e.g. a longer timeout for a large compose file.
+    ///
+    /// # Parameters
+    ///
+    /// * `options` - Overrides for the client defaults
+    ///
+    /// # Returns
+    ///
+    /// A `DeploymentResponse` containing the deployment details if successful
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as `deploy`
+    pub async fn deploy_with_options(
+        &self,
+        options: &RequestOptions,
+    ) -> Result<DeploymentResponse, Error> {
         // Get or create VM configuration
-        let vm_config = self.config.vm_config.clone().unwrap_or_else(|| VmConfig {
+        let vm_config = self
+            .config
+            .vm_config
+            .clone()
+            .unwrap_or_else(|| self.default_vm_config(&self.config.docker_compose));
+
+        let env_vars: Vec<_> = self
+            .config
+            .env_vars
+            .iter()
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect();
+
+        self.deploy_vm(vm_config, &env_vars, options).await
+    }
+
+    /// Deploys a compose file with per-call inputs, using the client's
+    /// configuration only for defaults.
+    ///
+    /// Unlike `deploy`, the compose file and environment variables of the
+    /// configuration are ignored, so one client can deploy many different
+    /// applications. Without `vm_config`, a VM configuration is built from
+    /// the configured TEEPod and image as in `deploy`.
+    ///
+    /// # Parameters
+    ///
+    /// * `docker_compose` - Docker Compose configuration as a YAML string; replaces
+    ///   the compose file of `vm_config`
+    /// * `env_vars` - Environment variables to encrypt for the deployment
+    /// * `vm_config` - Optional VM configuration to deploy with
+    ///
+    /// # Returns
+    ///
+    /// A `DeploymentResponse` containing the deployment details if successful
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as `deploy`
+    pub async fn deploy_with(
+        &self,
+        docker_compose: &str,
+        env_vars: &[(String, String)],
+        vm_config: Option<VmConfig>,
+    ) -> Result<DeploymentResponse, Error> {
+        let vm_config = match vm_config {
+            Some(mut vm_config) => {
+                vm_config.compose_manifest.docker_compose_file = docker_compose.to_string();
+                vm_config
+            }
+            None => self.default_vm_config(docker_compose),
+        };
+        self.deploy_vm(vm_config, env_vars, &RequestOptions::default())
+            .await
+    }
+
+    /// Builds the VM configuration `deploy` uses when none is configured.
+    pub(crate) fn default_vm_config(&self, docker_compose: &str) -> VmConfig {
+        VmConfig {
             name: format!("tee-deploy-{}", uuid::Uuid::new_v4()),
             compose_manifest: crate::types::ComposeManifest {
                 name: "tee-deployment".to_string(),
                 features: vec!["kms".to_string(), "tproxy-net".to_string()],
-                docker_compose_file: self.config.docker_compose.clone(),
+                docker_compose_file: docker_compose.to_string(),
+                runner: crate::types::Runner::default(),
             },
             vcpu: 2,
             memory: 8192,
@@ -98,25 +448,33 @@ impl TeeClient {
                 },
                 listed: false,
             },
-        });
+            labels: HashMap::new(),
+            kernel_cmdline_append: None,
+            extra: serde_json::Map::new(),
+        }
+    }
+
+    /// Fetches the encryption key for `vm_config`, encrypts `env_vars` and deploys.
+    async fn deploy_vm(
+        &self,
+        vm_config: VmConfig,
+        env_vars: &[(String, String)],
+        options: &RequestOptions,
+    ) -> Result<DeploymentResponse, Error> {
+        if let Some(cmdline) = &vm_config.kernel_cmdline_append {
+            crate::types::check_kernel_cmdline_append(cmdline)?;
+        }
 
         // Get encryption public key
-        let pubkey_response = self.get_pubkey(&vm_config).await?;
+        let pubkey_response = self.get_pubkey(&vm_config, options).await?;
 
         // Encrypt environment variables
-        let env_vars: Vec<_> = self
-            .config
-            .env_vars
-            .iter()
-            .map(|(k, v)| (k.clone(), v.clone()))
-            .collect();
-
-        let encrypted_env =
-            Encryptor::encrypt_env_vars(&env_vars, &pubkey_response.app_env_encrypt_pubkey)?;
+        let encrypted_env = self
+            .encrypt_env(env_vars, &pubkey_response.app_env_encrypt_pubkey)
+            .await?;
 
         // Create a mutable request body from vm_config
-        let mut request_body = serde_json::to_value(&vm_config)
-            .unwrap()
+        let mut request_body = serde_json::Value::from(vm_config)
             .as_object()
             .cloned()
             .unwrap_or_default();
@@ -132,29 +490,22 @@ impl TeeClient {
         );
 
         // Create deployment
-        let response = self
+        let request = self
             .client
             .post(format!(
                 "{}/cvms/from_cvm_configuration",
-                self.config.api_url
+                self.config.base_url()
             ))
             .header("Content-Type", "application/json")
-            .header("x-api-key", &self.config.api_key)
-            .json(&request_body)
-            .send()
-            .await?;
+            .header("x-api-key", &self.config.api_key);
+        let request = Self::with_options(request, options);
+        let response = self.with_json_body(request, &request_body)?.send().await?;
 
         if !response.status().is_success() {
-            return Err(Error::Api {
-                status_code: response.status().as_u16(),
-                message: response.text().await?,
-            });
+            return Err(Self::api_error(response).await);
         }
 
-        response
-            .json::<DeploymentResponse>()
-            .await
-            .map_err(Error::HttpClient)
+        self.parse_json(response).await
     }
 
     /// Retrieves the encryption public key for a given VM configuration.
@@ -165,6 +516,7 @@ impl TeeClient {
     /// # Parameters
     ///
     /// * `vm_config` - The VM configuration to get a public key for
+    /// * `options` - Overrides for the client defaults
     ///
     /// # Returns
     ///
@@ -173,30 +525,27 @@ impl TeeClient {
     /// # Errors
     ///
     /// Returns an error if the API request fails or returns an error
-    async fn get_pubkey(&self, vm_config: &VmConfig) -> Result<PubkeyResponse, Error> {
-        let response = self
+    async fn get_pubkey(
+        &self,
+        vm_config: &VmConfig,
+        options: &RequestOptions,
+    ) -> Result<PubkeyResponse, Error> {
+        let request = self
             .client
             .post(format!(
                 "{}/cvms/pubkey/from_cvm_configuration",
-                self.config.api_url
+                self.config.base_url()
             ))
             .header("Content-Type", "application/json")
-            .header("x-api-key", &self.config.api_key)
-            .json(&vm_config)
-            .send()
-            .await?;
+            .header("x-api-key", &self.config.api_key);
+        let request = Self::with_options(request, options);
+        let response = self.with_json_body(request, &vm_config)?.send().await?;
 
         if !response.status().is_success() {
-            return Err(Error::Api {
-                status_code: response.status().as_u16(),
-                message: response.text().await?,
-            });
+            return Err(Self::api_error(response).await);
         }
 
-        response
-            .json::<PubkeyResponse>()
-            .await
-            .map_err(Error::HttpClient)
+        self.parse_json(response).await
     }
 
     /// Retrieves the current Docker Compose configuration for an application.
@@ -215,23 +564,21 @@ impl TeeClient {
     pub async fn get_compose(&self, app_id: &str) -> Result<ComposeResponse, Error> {
         let response = self
             .client
-            .get(format!("{}/cvms/{}/compose", self.config.api_url, app_id))
+            .get(format!(
+                "{}/cvms/{}/compose",
+                self.config.base_url(),
+                app_id
+            ))
             .header("Content-Type", "application/json")
             .header("x-api-key", &self.config.api_key)
             .send()
             .await?;
 
         if !response.status().is_success() {
-            return Err(Error::Api {
-                status_code: response.status().as_u16(),
-                message: response.text().await?,
-            });
+            return Err(Self::api_error(response).await);
         }
 
-        response
-            .json::<ComposeResponse>()
-            .await
-            .map_err(Error::HttpClient)
+        self.parse_json(response).await
     }
 
     /// Updates the Docker Compose configuration for an existing application.
@@ -248,7 +595,7 @@ impl TeeClient {
     ///
     /// # Returns
     ///
-    /// A JSON value containing the update operation result
+    /// An `UpdateResponse` with the update status and the raw response body
     ///
     /// # Errors
     ///
@@ -262,35 +609,217 @@ impl TeeClient {
         compose_file: serde_json::Value,
         env_vars: Option<HashMap<String, String>>,
         env_pubkey: String,
-    ) -> Result<serde_json::Value, Error> {
+    ) -> Result<UpdateResponse, Error> {
+        self.update_compose_with_removals(app_id, compose_file, env_vars, &[], env_pubkey)
+            .await
+    }
+
+    /// Updates the Docker Compose configuration and drops environment variables.
+    ///
+    /// The platform has no native removal: the encrypted environment sent with
+    /// an update replaces the previous one. Removed keys are therefore filtered
+    /// out of `env_vars` before encryption and dropped from the compose file's
+    /// `allowed_envs`. Because encrypted values cannot be read back, `env_vars`
+    /// must hold the complete set of variables to keep whenever keys are removed.
+    ///
+    /// # Parameters
+    ///
+    /// * `app_id` - The ID of the application to update
+    /// * `compose_file` - The new Docker Compose configuration
+    /// * `env_vars` - The environment variables to keep or set
+    /// * `env_removals` - Keys of environment variables to remove
+    /// * `env_pubkey` - The public key for encrypting environment variables
+    ///
+    /// # Returns
+    ///
+    /// An `UpdateResponse` with the update status and the raw response body
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// * Keys are removed without supplying the remaining `env_vars`
+    /// * The API request fails
+    /// * The application is not found
+    /// * Environment variables cannot be encrypted
+    pub async fn update_compose_with_removals(
+        &self,
+        app_id: &str,
+        mut compose_file: serde_json::Value,
+        env_vars: Option<HashMap<String, String>>,
+        env_removals: &[String],
+        env_pubkey: String,
+    ) -> Result<UpdateResponse, Error> {
+        if !env_removals.is_empty() {
+            if env_vars.is_none() {
+                return Err(Error::Configuration(
+                    "Removing environment variables requires the remaining variables in env_vars"
+                        .to_string(),
+                ));
+            }
+            if let Some(allowed) = compose_file
+                .get_mut("allowed_envs")
+                .and_then(|v| v.as_array_mut())
+            {
+                allowed.retain(|key| {
+                    !key.as_str()
+                        .is_some_and(|key| env_removals.iter().any(|removed| removed == key))
+                });
+            }
+        }
+
         let mut body = json!({
             "compose_manifest": compose_file
         });
 
         // Encrypt environment variables if provided
         if let Some(vars) = env_vars {
-            let env_vars: Vec<_> = vars.iter().map(|(k, v)| (k.clone(), v.clone())).collect();
-            let encrypted_env = Encryptor::encrypt_env_vars(&env_vars, &env_pubkey)?;
+            let env_vars: Vec<_> = vars
+                .iter()
+                .filter(|(k, _)| !env_removals.contains(k))
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect();
+            let encrypted_env = self.encrypt_env(&env_vars, &env_pubkey).await?;
             body["encrypted_env"] = json!(encrypted_env);
         }
 
-        let response = self
+        let request = self
             .client
-            .put(format!("{}/cvms/{}/compose", self.config.api_url, app_id))
+            .put(format!(
+                "{}/cvms/{}/compose",
+                self.config.base_url(),
+                app_id
+            ))
             .header("Content-Type", "application/json")
-            .header("x-api-key", &self.config.api_key)
-            .json(&body)
-            .send()
-            .await?;
+            .header("x-api-key", &self.config.api_key);
+        let response = self.with_json_body(request, &body)?.send().await?;
+
+        if !response.status().is_success() {
+            return Err(Self::api_error(response).await);
+        }
+
+        self.parse_json(response).await
+    }
+
+    /// Changes the advanced features of an existing deployment.
+    ///
+    /// Sends only the settings set in `settings`, e.g. to disable public logs
+    /// after the initial launch. Settings fixed at creation (`kms`, `tproxy`)
+    /// cannot be expressed and are never sent.
+    ///
+    /// # Parameters
+    ///
+    /// * `app_id` - The ID of the application to update
+    /// * `settings` - The settings to change
+    ///
+    /// # Returns
+    ///
+    /// An `UpdateResponse` with the update status and the raw response body
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// * `settings` changes nothing
+    /// * The API request fails
+    /// * The application is not found
+    pub async fn update_settings(
+        &self,
+        app_id: &str,
+        settings: &SettingsUpdate,
+    ) -> Result<UpdateResponse, Error> {
+        if settings.is_empty() {
+            return Err(Error::Configuration(
+                "Settings update changes nothing; set at least one setting".to_string(),
+            ));
+        }
+
+        let body = json!({ "advanced_features": settings });
+        let request = self
+            .client
+            .patch(format!("{}/cvms/{}", self.config.base_url(), app_id))
+            .header("Content-Type", "application/json")
+            .header("x-api-key", &self.config.api_key);
+        let response = self.with_json_body(request, &body)?.send().await?;
+
+        if !response.status().is_success() {
+            return Err(Self::api_error(response).await);
+        }
+
+        self.parse_json(response).await
+    }
+
+    /// Checks that the API endpoint is reachable and the API key is accepted.
+    ///
+    /// Issues a lightweight authenticated `GET /auth/me` request, which is cheap
+    /// enough to run before starting a longer deployment workflow.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` if the API accepted the credentials
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// * The network request fails (timeout, connection issues, etc.)
+    /// * The API rejects the API key (401) or its permissions (403), with a
+    ///   message saying so
+    /// * The API returns any other error response
+    pub async fn ping(&self) -> Result<(), Error> {
+        self.ping_with_options(&RequestOptions::default()).await
+    }
+
+    /// Checks the API key like `ping`, with per-call request options.
+    /// `GET /api/v1/auth/me`
+    pub async fn ping_with_options(&self, options: &RequestOptions) -> Result<(), Error> {
+        let request = self
+            .client
+            .get(format!("{}/auth/me", self.config.base_url()))
+            .header("x-api-key", &self.config.api_key);
+        let response = Self::with_options(request, options).send().await?;
 
         if !response.status().is_success() {
-            return Err(Error::Api {
-                status_code: response.status().as_u16(),
-                message: response.text().await?,
-            });
+            return Err(Self::api_error(response).await);
         }
 
-        response.json().await.map_err(Error::HttpClient)
+        Ok(())
+    }
+
+    /// Queries which optional endpoints the API version serves.
+    ///
+    /// Check the result before calling endpoints that older API versions lack
+    /// (delete, logs, resize), so callers can fail fast with a clear message
+    /// instead of a raw 404.
+    ///
+    /// `GET /api/v1/version`
+    ///
+    /// # Returns
+    ///
+    /// The API version and its available features
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the API request fails or the response cannot be parsed
+    pub async fn api_capabilities(&self) -> Result<ApiCapabilities, Error> {
+        self.api_capabilities_with_options(&RequestOptions::default())
+            .await
+    }
+
+    /// Queries the API capabilities with per-call request options.
+    /// `GET /api/v1/version`
+    pub async fn api_capabilities_with_options(
+        &self,
+        options: &RequestOptions,
+    ) -> Result<ApiCapabilities, Error> {
+        let request = self
+            .client
+            .get(format!("{}/version", self.config.base_url()))
+            .header("x-api-key", &self.config.api_key);
+        let response = Self::with_options(request, options).send().await?;
+
+        if !response.status().is_success() {
+            return Err(Self::api_error(response).await);
+        }
+
+        self.parse_json(response).await
     }
 
     /// Retrieves a list of available TEEPods from the Phala Cloud API.
@@ -311,7 +840,7 @@ impl TeeClient {
     pub async fn get_available_teepods(&self) -> Result<TeePodDiscoveryResponse, Error> {
         let response = self
             .client
-            .get(format!("{}/teepods/available", self.config.api_url))
+            .get(format!("{}/teepods/available", self.config.base_url()))
             .header("Content-Type", "application/json")
             .header("x-api-key", &self.config.api_key)
             .timeout(std::time::Duration::from_secs(15))
@@ -319,13 +848,10 @@ impl TeeClient {
             .await?;
 
         if !response.status().is_success() {
-            return Err(Error::Api {
-                status_code: response.status().as_u16(),
-                message: response.text().await?,
-            });
+            return Err(Self::api_error(response).await);
         }
 
-        response.json().await.map_err(Error::HttpClient)
+        self.parse_json(response).await
     }
 
     /// Retrieves the encryption public key for a custom VM configuration.
@@ -345,26 +871,21 @@ impl TeeClient {
         &self,
         vm_config: &serde_json::Value,
     ) -> Result<PubkeyResponse, Error> {
-        let response = self
+        let request = self
             .client
             .post(format!(
                 "{}/cvms/pubkey/from_cvm_configuration",
-                self.config.api_url
+                self.config.base_url()
             ))
             .header("Content-Type", "application/json")
-            .header("x-api-key", &self.config.api_key)
-            .json(&vm_config)
-            .send()
-            .await?;
+            .header("x-api-key", &self.config.api_key);
+        let response = self.with_json_body(request, &vm_config)?.send().await?;
 
         if !response.status().is_success() {
-            return Err(Error::Api {
-                status_code: response.status().as_u16(),
-                message: response.text().await?,
-            });
+            return Err(Self::api_error(response).await);
         }
 
-        response.json().await.map_err(Error::HttpClient)
+        self.parse_json(response).await
     }
 
     /// Deploys a container with a custom VM configuration and encrypts environment variables.
@@ -386,6 +907,7 @@ impl TeeClient {
     /// # Errors
     ///
     /// Returns an error if:
+    /// * The environment variables exceed 32 MiB in total
     /// * Environment variable encryption fails
     /// * The API request fails
     /// * The API returns an error response
@@ -393,11 +915,13 @@ impl TeeClient {
         &self,
         vm_config: serde_json::Value,
         env_vars: &[(String, String)],
-        app_env_encrypt_pubkey: &str,
-        app_id_salt: &str,
+        app_env_encrypt_pubkey: &AppPublicKey,
+        app_id_salt: &AppIdSalt,
     ) -> Result<DeploymentResponse, Error> {
         // Encrypt environment variables
-        let encrypted_env = Encryptor::encrypt_env_vars(env_vars, app_env_encrypt_pubkey)?;
+        let encrypted_env = self
+            .encrypt_env(env_vars, app_env_encrypt_pubkey.as_str())
+            .await?;
 
         self.deploy_with_config_encrypted_env(
             vm_config,
@@ -413,6 +937,10 @@ impl TeeClient {
     /// This method is the final step in the deployment process, sending the VM configuration
     /// and encrypted environment variables to the API.
     ///
+    /// Object keys in the request body are sorted alphabetically at every
+    /// level, so identical inputs always produce byte-identical bodies
+    /// regardless of the key order of `vm_config`.
+    ///
     /// # Parameters
     ///
     /// * `vm_config` - The VM configuration as a JSON value
@@ -431,9 +959,57 @@ impl TeeClient {
         &self,
         vm_config: serde_json::Value,
         encrypted_env: String,
-        app_env_encrypt_pubkey: &str,
-        app_id_salt: &str,
+        app_env_encrypt_pubkey: &AppPublicKey,
+        app_id_salt: &AppIdSalt,
     ) -> Result<DeploymentResponse, Error> {
+        let response = self
+            .send_encrypted_deploy(
+                vm_config,
+                encrypted_env,
+                app_env_encrypt_pubkey,
+                app_id_salt,
+            )
+            .await?;
+        self.parse_json(response).await
+    }
+
+    /// Same as [`deploy_with_config_encrypted_env`](Self::deploy_with_config_encrypted_env),
+    /// but returns the response body as untyped JSON.
+    ///
+    /// Useful for debugging when `DeploymentResponse` drops or reshapes
+    /// fields the API returned; every field in the body is preserved.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the API request fails, returns an error, or the
+    /// body is not valid JSON
+    pub async fn deploy_with_config_encrypted_env_raw(
+        &self,
+        vm_config: serde_json::Value,
+        encrypted_env: String,
+        app_env_encrypt_pubkey: &AppPublicKey,
+        app_id_salt: &AppIdSalt,
+    ) -> Result<serde_json::Value, Error> {
+        let response = self
+            .send_encrypted_deploy(
+                vm_config,
+                encrypted_env,
+                app_env_encrypt_pubkey,
+                app_id_salt,
+            )
+            .await?;
+        self.parse_json(response).await
+    }
+
+    /// Sends the deployment request and returns the successful response
+    /// without parsing its body.
+    async fn send_encrypted_deploy(
+        &self,
+        vm_config: serde_json::Value,
+        encrypted_env: String,
+        app_env_encrypt_pubkey: &AppPublicKey,
+        app_id_salt: &AppIdSalt,
+    ) -> Result<reqwest::Response, Error> {
         // Create a mutable request body
         let mut request_body = vm_config.as_object().cloned().unwrap_or_default();
 
@@ -452,29 +1028,22 @@ impl TeeClient {
         );
 
         // Create deployment
-        let response = self
+        let request = self
             .client
             .post(format!(
                 "{}/cvms/from_cvm_configuration",
-                self.config.api_url
+                self.config.base_url()
             ))
             .header("Content-Type", "application/json")
-            .header("x-api-key", &self.config.api_key)
-            .json(&request_body)
-            .send()
-            .await?;
+            .header("x-api-key", &self.config.api_key);
+        let request_body = serde_json::Value::Object(request_body);
+        let response = self.with_json_body(request, &request_body)?.send().await?;
 
         if !response.status().is_success() {
-            return Err(Error::Api {
-                status_code: response.status().as_u16(),
-                message: response.text().await?,
-            });
+            return Err(Self::api_error(response).await);
         }
 
-        response
-            .json::<DeploymentResponse>()
-            .await
-            .map_err(Error::HttpClient)
+        Ok(response)
     }
 
     /// Provisions a new ELIZA chatbot deployment.
@@ -517,20 +1086,15 @@ impl TeeClient {
             "image": image
         });
 
-        let response = self
+        let request = self
             .client
-            .post(format!("{}/cvms/provision/eliza", self.config.api_url))
+            .post(format!("{}/cvms/provision/eliza", self.config.base_url()))
             .header("Content-Type", "application/json")
-            .header("x-api-key", &self.config.api_key)
-            .json(&request_body)
-            .send()
-            .await?;
+            .header("x-api-key", &self.config.api_key);
+        let response = self.with_json_body(request, &request_body)?.send().await?;
 
         if !response.status().is_success() {
-            return Err(Error::Api {
-                status_code: response.status().as_u16(),
-                message: response.text().await?,
-            });
+            return Err(Self::api_error(response).await);
         }
 
         // Get the response as JSON Value to extract necessary fields
@@ -593,20 +1157,15 @@ impl TeeClient {
             "encrypted_env": encrypted_env
         });
 
-        let create_response = self
+        let request = self
             .client
-            .post(format!("{}/cvms", self.config.api_url))
+            .post(format!("{}/cvms", self.config.base_url()))
             .header("Content-Type", "application/json")
-            .header("x-api-key", &self.config.api_key)
-            .json(&create_body)
-            .send()
-            .await?;
+            .header("x-api-key", &self.config.api_key);
+        let create_response = self.with_json_body(request, &create_body)?.send().await?;
 
         if !create_response.status().is_success() {
-            return Err(Error::Api {
-                status_code: create_response.status().as_u16(),
-                message: create_response.text().await?,
-            });
+            return Err(Self::api_error(create_response).await);
         }
 
         // Parse final response into DeploymentResponse
@@ -640,6 +1199,7 @@ impl TeeClient {
                     Ok(DeploymentResponse {
                         id,
                         status: "pending".to_string(),
+                        app_id: Some(app_id.to_string()),
                         details: Some(details),
                     })
                 } else {
@@ -674,23 +1234,21 @@ impl TeeClient {
     pub async fn get_network_info(&self, app_id: &str) -> Result<NetworkInfoResponse, Error> {
         let response = self
             .client
-            .get(format!("{}/cvms/{}/network", self.config.api_url, app_id))
+            .get(format!(
+                "{}/cvms/{}/network",
+                self.config.base_url(),
+                app_id
+            ))
             .header("Content-Type", "application/json")
             .header("x-api-key", &self.config.api_key)
             .send()
             .await?;
 
         if !response.status().is_success() {
-            return Err(Error::Api {
-                status_code: response.status().as_u16(),
-                message: response.text().await?,
-            });
+            return Err(Self::api_error(response).await);
         }
 
-        response
-            .json::<NetworkInfoResponse>()
-            .await
-            .map_err(Error::HttpClient)
+        self.parse_json(response).await
     }
 
     /// Retrieves system statistics for a deployed application.
@@ -713,74 +1271,109 @@ impl TeeClient {
     /// * The application is not found
     /// * The system statistics cannot be retrieved
     pub async fn get_system_stats(&self, app_id: &str) -> Result<SystemStatsResponse, Error> {
-        let response = self
+        self.get_system_stats_with_options(app_id, &RequestOptions::default())
+            .await
+    }
+
+    /// Retrieves system statistics with per-call request options.
+    ///
+    /// # Parameters
+    ///
+    /// * `app_id` - The ID of the application to get system statistics for
+    /// * `options` - Overrides for the client defaults
+    ///
+    /// # Returns
+    ///
+    /// A `SystemStatsResponse` containing system information if successful
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as `get_system_stats`
+    pub async fn get_system_stats_with_options(
+        &self,
+        app_id: &str,
+        options: &RequestOptions,
+    ) -> Result<SystemStatsResponse, Error> {
+        let request = self
             .client
-            .get(format!("{}/cvms/{}/stats", self.config.api_url, app_id))
+            .get(format!("{}/cvms/{}/stats", self.config.base_url(), app_id))
             .header("Content-Type", "application/json")
-            .header("x-api-key", &self.config.api_key)
-            .send()
-            .await?;
+            .header("x-api-key", &self.config.api_key);
+        let response = Self::with_options(request, options).send().await?;
 
         if !response.status().is_success() {
-            return Err(Error::Api {
-                status_code: response.status().as_u16(),
-                message: response.text().await?,
-            });
+            return Err(Self::api_error(response).await);
         }
 
-        response
-            .json::<SystemStatsResponse>()
-            .await
-            .map_err(Error::HttpClient)
+        self.parse_json(response).await
     }
 
     // ─────────────────────────────────────────────────────────────────────
     // CVM lifecycle
     // ─────────────────────────────────────────────────────────────────────
 
+    /// List all CVMs owned by the API key.
+    /// `GET /api/v1/cvms`
+    pub async fn list_cvms(&self) -> Result<Vec<CvmInfo>, Error> {
+        let response = self
+            .client
+            .get(format!("{}/cvms", self.config.base_url()))
+            .header("Content-Type", "application/json")
+            .header("x-api-key", &self.config.api_key)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(Self::api_error(response).await);
+        }
+
+        self.parse_json(response).await
+    }
+
     /// Get CVM details including status.
     /// `GET /api/v1/cvms/{cvm_id}`
     pub async fn get_cvm(&self, cvm_id: &str) -> Result<CvmInfo, Error> {
         let response = self
             .client
-            .get(format!("{}/cvms/{}", self.config.api_url, cvm_id))
+            .get(format!("{}/cvms/{}", self.config.base_url(), cvm_id))
             .header("Content-Type", "application/json")
             .header("x-api-key", &self.config.api_key)
             .send()
             .await?;
 
         if !response.status().is_success() {
-            return Err(Error::Api {
-                status_code: response.status().as_u16(),
-                message: response.text().await?,
-            });
+            return Err(Self::api_error(response).await);
         }
 
-        response.json::<CvmInfo>().await.map_err(Error::HttpClient)
+        self.parse_json(response).await
     }
 
     /// Get CVM state (running, stopped, etc.).
     /// `GET /api/v1/cvms/{cvm_id}/state`
     pub async fn get_state(&self, cvm_id: &str) -> Result<CvmStateResponse, Error> {
-        let response = self
+        self.get_state_with_options(cvm_id, &RequestOptions::default())
+            .await
+    }
+
+    /// Get CVM state with per-call request options.
+    /// `GET /api/v1/cvms/{cvm_id}/state`
+    pub async fn get_state_with_options(
+        &self,
+        cvm_id: &str,
+        options: &RequestOptions,
+    ) -> Result<CvmStateResponse, Error> {
+        let request = self
             .client
-            .get(format!("{}/cvms/{}/state", self.config.api_url, cvm_id))
+            .get(format!("{}/cvms/{}/state", self.config.base_url(), cvm_id))
             .header("Content-Type", "application/json")
-            .header("x-api-key", &self.config.api_key)
-            .send()
-            .await?;
+            .header("x-api-key", &self.config.api_key);
+        let response = Self::with_options(request, options).send().await?;
 
         if !response.status().is_success() {
-            return Err(Error::Api {
-                status_code: response.status().as_u16(),
-                message: response.text().await?,
-            });
+            return Err(Self::api_error(response).await);
         }
 
-        response
-            .json::<CvmStateResponse>()
-            .await
-            .map_err(Error::HttpClient)
+        self.parse_json(response).await
     }
 
     /// Start a stopped CVM.
@@ -788,20 +1381,17 @@ impl TeeClient {
     pub async fn start_cvm(&self, cvm_id: &str) -> Result<CvmInfo, Error> {
         let response = self
             .client
-            .post(format!("{}/cvms/{}/start", self.config.api_url, cvm_id))
+            .post(format!("{}/cvms/{}/start", self.config.base_url(), cvm_id))
             .header("Content-Type", "application/json")
             .header("x-api-key", &self.config.api_key)
             .send()
             .await?;
 
         if !response.status().is_success() {
-            return Err(Error::Api {
-                status_code: response.status().as_u16(),
-                message: response.text().await?,
-            });
+            return Err(Self::api_error(response).await);
         }
 
-        response.json::<CvmInfo>().await.map_err(Error::HttpClient)
+        self.parse_json(response).await
     }
 
     /// Graceful shutdown (SIGTERM, then SIGKILL after timeout).
@@ -811,7 +1401,8 @@ impl TeeClient {
             .client
             .post(format!(
                 "{}/cvms/{}/shutdown",
-                self.config.api_url, cvm_id
+                self.config.base_url(),
+                cvm_id
             ))
             .header("Content-Type", "application/json")
             .header("x-api-key", &self.config.api_key)
@@ -819,13 +1410,10 @@ impl TeeClient {
             .await?;
 
         if !response.status().is_success() {
-            return Err(Error::Api {
-                status_code: response.status().as_u16(),
-                message: response.text().await?,
-            });
+            return Err(Self::api_error(response).await);
         }
 
-        response.json::<CvmInfo>().await.map_err(Error::HttpClient)
+        self.parse_json(response).await
     }
 
     /// Force stop (immediate, like power loss).
@@ -833,20 +1421,17 @@ impl TeeClient {
     pub async fn stop_cvm(&self, cvm_id: &str) -> Result<CvmInfo, Error> {
         let response = self
             .client
-            .post(format!("{}/cvms/{}/stop", self.config.api_url, cvm_id))
+            .post(format!("{}/cvms/{}/stop", self.config.base_url(), cvm_id))
             .header("Content-Type", "application/json")
             .header("x-api-key", &self.config.api_key)
             .send()
             .await?;
 
         if !response.status().is_success() {
-            return Err(Error::Api {
-                status_code: response.status().as_u16(),
-                message: response.text().await?,
-            });
+            return Err(Self::api_error(response).await);
         }
 
-        response.json::<CvmInfo>().await.map_err(Error::HttpClient)
+        self.parse_json(response).await
     }
 
     /// Permanently delete a stopped CVM (irreversible).
@@ -854,17 +1439,14 @@ impl TeeClient {
     pub async fn delete_cvm(&self, cvm_id: &str) -> Result<(), Error> {
         let response = self
             .client
-            .delete(format!("{}/cvms/{}", self.config.api_url, cvm_id))
+            .delete(format!("{}/cvms/{}", self.config.base_url(), cvm_id))
             .header("Content-Type", "application/json")
             .header("x-api-key", &self.config.api_key)
             .send()
             .await?;
 
         if !response.status().is_success() {
-            return Err(Error::Api {
-                status_code: response.status().as_u16(),
-                message: response.text().await?,
-            });
+            return Err(Self::api_error(response).await);
         }
 
         Ok(())
@@ -877,7 +1459,8 @@ impl TeeClient {
             .client
             .get(format!(
                 "{}/cvms/{}/attestation",
-                self.config.api_url, cvm_id
+                self.config.base_url(),
+                cvm_id
             ))
             .header("Content-Type", "application/json")
             .header("x-api-key", &self.config.api_key)
@@ -885,15 +1468,164 @@ impl TeeClient {
             .await?;
 
         if !response.status().is_success() {
-            return Err(Error::Api {
-                status_code: response.status().as_u16(),
-                message: response.text().await?,
-            });
+            return Err(Self::api_error(response).await);
         }
 
-        response
-            .json::<AttestationResponse>()
-            .await
-            .map_err(Error::HttpClient)
+        self.parse_json(response).await
+    }
+
+    /// Get a page of application logs, optionally only those after `since`.
+    /// `GET /api/v1/cvms/{cvm_id}/logs?since={cursor}`
+    pub async fn get_logs(&self, cvm_id: &str, since: Option<&str>) -> Result<LogsResponse, Error> {
+        let mut request = self
+            .client
+            .get(format!("{}/cvms/{}/logs", self.config.base_url(), cvm_id))
+            .header("Content-Type", "application/json")
+            .header("x-api-key", &self.config.api_key);
+        if let Some(cursor) = since {
+            request = request.query(&[("since", cursor)]);
+        }
+        let response = request.send().await?;
+
+        if !response.status().is_success() {
+            return Err(Self::api_error(response).await);
+        }
+
+        self.parse_json(response).await
+    }
+
+    // ─────────────────────────────────────────────────────────────────────
+    // Public directory
+    // ─────────────────────────────────────────────────────────────────────
+
+    /// Searches the public directory of listed deployments by name.
+    ///
+    /// Only deployments created with `listed` enabled appear in the directory.
+    /// Entries are additionally filtered client-side: names must contain
+    /// `query` (case-insensitive), and any entry reporting `listed: false` is
+    /// dropped, so private deployments are never returned. An empty query
+    /// returns every listed deployment.
+    ///
+    /// `GET /api/v1/cvms/listed?search={query}`
+    ///
+    /// # Parameters
+    ///
+    /// * `query` - Text to match against deployment names
+    ///
+    /// # Returns
+    ///
+    /// The matching listed deployments
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the API request fails
+    pub async fn search_listed_deployments(
+        &self,
+        query: &str,
+    ) -> Result<Vec<DeploymentSummary>, Error> {
+        let response = self
+            .client
+            .get(format!("{}/cvms/listed", self.config.base_url()))
+            .query(&[("search", query)])
+            .header("Content-Type", "application/json")
+            .header("x-api-key", &self.config.api_key)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(Self::api_error(response).await);
+        }
+
+        let query = query.to_lowercase();
+        let deployments: Vec<DeploymentSummary> = self.parse_json(response).await?;
+        Ok(deployments
+            .into_iter()
+            .filter(|d| d.listed != Some(false) && d.name.to_lowercase().contains(&query))
+            .collect())
+    }
+
+    // ─────────────────────────────────────────────────────────────────────
+    // Untyped access
+    // ─────────────────────────────────────────────────────────────────────
+
+    /// Sends an authenticated `GET` to an endpoint this crate does not model yet.
+    ///
+    /// An escape hatch pending typed support: `path` is appended to the API URL
+    /// (e.g. `"/cvms/app_1/composefile"`) and the response is returned as raw
+    /// JSON. Errors are handled as for the typed methods.
+    ///
+    /// # Parameters
+    ///
+    /// * `path` - Endpoint path relative to the API URL
+    ///
+    /// # Returns
+    ///
+    /// The parsed JSON response body
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the API request fails, returns an error response,
+    /// or the response is not JSON
+    pub async fn get_raw(&self, path: &str) -> Result<serde_json::Value, Error> {
+        let response = self
+            .client
+            .get(self.raw_url(path))
+            .header("Content-Type", "application/json")
+            .header("x-api-key", &self.config.api_key)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(Self::api_error(response).await);
+        }
+
+        self.parse_json(response).await
+    }
+
+    /// Sends an authenticated `POST` with a JSON body to an endpoint this crate
+    /// does not model yet.
+    ///
+    /// The counterpart of `get_raw`; the body is compressed like any other
+    /// request body when compression is enabled.
+    ///
+    /// # Parameters
+    ///
+    /// * `path` - Endpoint path relative to the API URL
+    /// * `body` - JSON request body
+    ///
+    /// # Returns
+    ///
+    /// The parsed JSON response body
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the API request fails, returns an error response,
+    /// or the response is not JSON
+    pub async fn post_raw(
+        &self,
+        path: &str,
+        body: serde_json::Value,
+    ) -> Result<serde_json::Value, Error> {
+        let request = self
+            .client
+            .post(self.raw_url(path))
+            .header("Content-Type", "application/json")
+            .header("x-api-key", &self.config.api_key);
+        let response = self.with_json_body(request, &body)?.send().await?;
+
+        if !response.status().is_success() {
+            return Err(Self::api_error(response).await);
+        }
+
+        self.parse_json(response).await
+    }
+
+    /// Joins a caller-supplied path onto the API URL, with or without a leading slash.
+    fn raw_url(&self, path: &str) -> String {
+        format!(
+            "{}/{}",
+            self.config.base_url().trim_end_matches('/'),
+            path.trim_start_matches('/')
+        )
     }
 }
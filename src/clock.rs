@@ -0,0 +1,60 @@
+use async_trait::async_trait;
+use std::time::{Duration, Instant};
+
+/// Source of time for polling, backoff, and caching.
+///
+/// `TeeDeployer` reads the current time and sleeps through this trait rather
+/// than calling `tokio::time` directly, so tests can inject a fake clock and
+/// step through timeouts and backoff schedules without real delays.
+///
+/// # Examples
+///
+/// ```
+/// use async_trait::async_trait;
+/// use phala_tee_deploy_rs::Clock;
+/// use std::sync::Mutex;
+/// use std::time::{Duration, Instant};
+///
+/// /// A clock that only moves when something sleeps on it.
+/// struct FakeClock {
+///     start: Instant,
+///     elapsed: Mutex<Duration>,
+/// }
+///
+/// #[async_trait]
+/// impl Clock for FakeClock {
+///     fn now(&self) -> Instant {
+///         self.start + *self.elapsed.lock().unwrap()
+///     }
+///
+///     async fn sleep(&self, duration: Duration) {
+///         *self.elapsed.lock().unwrap() += duration;
+///     }
+/// }
+/// ```
+#[async_trait]
+pub trait Clock: Send + Sync {
+    /// Returns the current instant.
+    fn now(&self) -> Instant;
+
+    /// Waits until `duration` has elapsed.
+    async fn sleep(&self, duration: Duration);
+}
+
+/// The default clock, backed by the Tokio timer.
+///
+/// Follows Tokio's paused test time, so `tokio::time::pause` also works for
+/// deterministic tests without a custom clock.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TokioClock;
+
+#[async_trait]
+impl Clock for TokioClock {
+    fn now(&self) -> Instant {
+        tokio::time::Instant::now().into_std()
+    }
+
+    async fn sleep(&self, duration: Duration) {
+        tokio::time::sleep(duration).await;
+    }
+}
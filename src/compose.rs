@@ -0,0 +1,1481 @@
+use crate::error::Error;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+
+/// Specification for a single service in a Docker Compose file.
+///
+/// Only the fields commonly needed for TEE deployments are modelled. Empty
+/// collections and unset options are omitted from the generated YAML.
+#[derive(Debug, Clone, Default)]
+pub struct ServiceSpec {
+    /// Docker image to run (e.g. "nginx:latest")
+    pub image: String,
+
+    /// Port mappings (e.g. "80:80")
+    pub ports: Vec<String>,
+
+    /// Environment variables for the container
+    pub env: HashMap<String, String>,
+
+    /// Volume mappings (e.g. "data:/var/lib/data")
+    pub volumes: Vec<String>,
+
+    /// Optional command override for the container
+    pub command: Option<Vec<String>>,
+
+    /// Names of services that must start before this one
+    pub depends_on: Vec<String>,
+
+    /// Services this one waits for, with the condition each must reach first
+    /// (e.g. `ServiceHealthy` for a database)
+    pub depends_on_conditions: BTreeMap<String, DependencyCondition>,
+
+    /// Optional container healthcheck, required by dependents waiting for
+    /// `DependencyCondition::ServiceHealthy`
+    pub healthcheck: Option<ServiceHealthcheck>,
+
+    /// Optional resource limits, emitted as `deploy.resources.limits`
+    pub resources: Option<ServiceResources>,
+
+    /// Names of top-level secrets mounted into the container
+    pub secrets: Vec<String>,
+
+    /// Names of top-level configs mounted into the container
+    pub configs: Vec<String>,
+
+    /// Names of top-level networks the service joins
+    pub networks: Vec<String>,
+
+    /// Optional size of `/dev/shm` (e.g. "256m" or "1gb"), needed by databases
+    pub shm_size: Option<String>,
+
+    /// Resource limits by name (e.g. "nofile"), emitted as `ulimits`
+    pub ulimits: BTreeMap<String, ServiceUlimit>,
+
+    /// Namespaced kernel parameters (e.g. "net.core.somaxconn"), emitted as `sysctls`
+    pub sysctls: BTreeMap<String, String>,
+}
+
+/// Per-service resource limits for a multi-container deployment.
+///
+/// Unset limits are omitted, leaving the service unconstrained in that dimension.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ServiceResources {
+    /// Maximum number of CPUs the service may use (e.g. 0.5)
+    pub cpus: Option<f64>,
+
+    /// Maximum memory in MB the service may use
+    pub memory_mb: Option<u64>,
+}
+
+/// Condition a dependency must reach before a dependent service starts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DependencyCondition {
+    /// The dependency has started
+    ServiceStarted,
+
+    /// The dependency's healthcheck passes; it must declare a `healthcheck`
+    ServiceHealthy,
+
+    /// The dependency ran to completion with exit code 0
+    ServiceCompletedSuccessfully,
+}
+
+/// Container healthcheck, emitted as the service's `healthcheck` section.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ServiceHealthcheck {
+    /// Check command, e.g. `["CMD", "pg_isready", "-U", "postgres"]`
+    pub test: Vec<String>,
+
+    /// Time between checks (e.g. "10s")
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub interval: Option<String>,
+
+    /// Time after which a single check counts as failed (e.g. "5s")
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<String>,
+
+    /// Consecutive failures before the container is unhealthy
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retries: Option<u32>,
+
+    /// Grace period before failures count (e.g. "30s")
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_period: Option<String>,
+}
+
+/// Soft and hard values of a container `ulimit`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct ServiceUlimit {
+    /// Limit enforced by the kernel, which the process may raise up to `hard`
+    pub soft: u64,
+
+    /// Ceiling for the soft limit
+    pub hard: u64,
+}
+
+/// Builder for Docker Compose YAML without hand-written strings.
+///
+/// Services are serialized through `serde_yaml`, so values are always quoted
+/// and escaped correctly.
+///
+/// # Examples
+///
+/// ```
+/// use phala_tee_deploy_rs::{ComposeBuilder, ServiceSpec};
+///
+/// let yaml = ComposeBuilder::new()
+///     .add_service(
+///         "web",
+///         ServiceSpec {
+///             image: "nginx:latest".to_string(),
+///             ports: vec!["80:80".to_string()],
+///             ..Default::default()
+///         },
+///     )
+///     .build_yaml()
+///     .unwrap();
+///
+/// assert!(yaml.contains("nginx:latest"));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ComposeBuilder {
+    services: BTreeMap<String, ServiceSpec>,
+    secrets: BTreeMap<String, String>,
+    configs: BTreeMap<String, String>,
+    networks: BTreeMap<String, String>,
+    vm_allocation: Option<(u32, u32)>,
+}
+
+#[derive(Serialize)]
+struct ComposeFile<'a> {
+    services: BTreeMap<&'a str, ComposeService<'a>>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    secrets: BTreeMap<&'a str, ComposeFileSource<'a>>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    configs: BTreeMap<&'a str, ComposeFileSource<'a>>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    networks: BTreeMap<&'a str, ComposeNetwork<'a>>,
+}
+
+#[derive(Serialize)]
+struct ComposeFileSource<'a> {
+    file: &'a str,
+}
+
+#[derive(Serialize)]
+struct ComposeNetwork<'a> {
+    driver: &'a str,
+}
+
+#[derive(Serialize)]
+struct ComposeService<'a> {
+    image: &'a str,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    ports: &'a Vec<String>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    environment: BTreeMap<&'a str, &'a str>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    volumes: &'a Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    command: &'a Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    depends_on: Option<ComposeDependsOn<'a>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    healthcheck: &'a Option<ServiceHealthcheck>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    deploy: Option<ComposeDeploy>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    secrets: &'a Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    configs: &'a Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    networks: &'a Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    shm_size: &'a Option<String>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    ulimits: &'a BTreeMap<String, ServiceUlimit>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    sysctls: &'a BTreeMap<String, String>,
+}
+
+/// `depends_on` in short list form, or in long form once any dependency has
+/// a condition.
+#[derive(Serialize)]
+#[serde(untagged)]
+enum ComposeDependsOn<'a> {
+    List(&'a Vec<String>),
+    Conditions(BTreeMap<&'a str, ComposeDependency>),
+}
+
+#[derive(Serialize)]
+struct ComposeDependency {
+    condition: DependencyCondition,
+}
+
+#[derive(Serialize)]
+struct ComposeDeploy {
+    resources: ComposeResources,
+}
+
+#[derive(Serialize)]
+struct ComposeResources {
+    limits: ComposeLimits,
+}
+
+#[derive(Serialize)]
+struct ComposeLimits {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cpus: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    memory: Option<String>,
+}
+
+impl ComposeBuilder {
+    /// Creates a new builder with no services.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a service, replacing any existing service with the same name.
+    ///
+    /// # Parameters
+    ///
+    /// * `name` - Name of the service in the compose file
+    /// * `spec` - The service specification
+    ///
+    /// # Returns
+    ///
+    /// The builder instance for method chaining
+    pub fn add_service(mut self, name: impl Into<String>, spec: ServiceSpec) -> Self {
+        self.services.insert(name.into(), spec);
+        self
+    }
+
+    /// Declares a top-level secret backed by a file, replacing any secret with
+    /// the same name.
+    ///
+    /// Services mount it by listing `name` in `ServiceSpec::secrets`; the
+    /// container sees it at `/run/secrets/<name>`.
+    ///
+    /// # Parameters
+    ///
+    /// * `name` - Name of the secret in the compose file
+    /// * `file` - Path of the file holding the secret (e.g. "./certs/client.key")
+    ///
+    /// # Returns
+    ///
+    /// The builder instance for method chaining
+    pub fn add_secret(mut self, name: impl Into<String>, file: impl Into<String>) -> Self {
+        self.secrets.insert(name.into(), file.into());
+        self
+    }
+
+    /// Declares a top-level config backed by a file, replacing any config with
+    /// the same name.
+    ///
+    /// Services mount it by listing `name` in `ServiceSpec::configs`.
+    ///
+    /// # Parameters
+    ///
+    /// * `name` - Name of the config in the compose file
+    /// * `file` - Path of the file holding the config
+    ///
+    /// # Returns
+    ///
+    /// The builder instance for method chaining
+    pub fn add_config(mut self, name: impl Into<String>, file: impl Into<String>) -> Self {
+        self.configs.insert(name.into(), file.into());
+        self
+    }
+
+    /// Declares a top-level network, replacing any network with the same name.
+    ///
+    /// Services join it by listing `name` in `ServiceSpec::networks`.
+    ///
+    /// # Parameters
+    ///
+    /// * `name` - Name of the network in the compose file
+    /// * `driver` - Network driver (e.g. "bridge")
+    ///
+    /// # Returns
+    ///
+    /// The builder instance for method chaining
+    pub fn add_network(mut self, name: impl Into<String>, driver: impl Into<String>) -> Self {
+        self.networks.insert(name.into(), driver.into());
+        self
+    }
+
+    /// Sets the VM allocation that per-service resource limits must fit within.
+    ///
+    /// When set, `build_yaml` rejects compose files whose summed service CPU or
+    /// memory limits exceed the VM's resources.
+    ///
+    /// # Parameters
+    ///
+    /// * `vcpu` - Number of virtual CPU cores of the VM
+    /// * `memory_mb` - Memory of the VM in MB
+    ///
+    /// # Returns
+    ///
+    /// The builder instance for method chaining
+    pub fn with_vm_allocation(mut self, vcpu: u32, memory_mb: u32) -> Self {
+        self.vm_allocation = Some((vcpu, memory_mb));
+        self
+    }
+
+    /// Renders the configured services as Docker Compose YAML.
+    ///
+    /// # Returns
+    ///
+    /// The compose file content as a YAML string
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// * No services have been added
+    /// * A service has an empty image
+    /// * A service depends on a service that is not defined
+    /// * A service waits for a dependency to be healthy that has no
+    ///   healthcheck, or a healthcheck has no test command
+    /// * A service mounts a secret or config, or joins a network, that is not declared
+    /// * A resource limit is not positive, or the summed limits exceed the VM allocation
+    /// * A `shm_size`, ulimit or sysctl is malformed
+    /// * Two services publish the same host port
+    /// * YAML serialization fails
+    pub fn build_yaml(&self) -> Result<String, Error> {
+        if self.services.is_empty() {
+            return Err(Error::Configuration(
+                "Compose file must define at least one service".to_string(),
+            ));
+        }
+
+        let mut services = BTreeMap::new();
+        for (name, spec) in &self.services {
+            if spec.image.is_empty() {
+                return Err(Error::Configuration(format!(
+                    "Service '{}' has no image",
+                    name
+                )));
+            }
+            if let Some(missing) = spec
+                .depends_on
+                .iter()
+                .chain(spec.depends_on_conditions.keys())
+                .find(|dep| !self.services.contains_key(*dep))
+            {
+                return Err(Error::Configuration(format!(
+                    "Service '{}' depends on undefined service '{}'",
+                    name, missing
+                )));
+            }
+            if let Some((dependency, _)) =
+                spec.depends_on_conditions.iter().find(|(dep, condition)| {
+                    **condition == DependencyCondition::ServiceHealthy
+                        && self.services[*dep].healthcheck.is_none()
+                })
+            {
+                return Err(Error::Configuration(format!(
+                    "Service '{}' waits for '{}' to be healthy, but '{}' has no healthcheck",
+                    name, dependency, dependency
+                )));
+            }
+            if spec
+                .healthcheck
+                .as_ref()
+                .is_some_and(|check| check.test.is_empty())
+            {
+                return Err(Error::Configuration(format!(
+                    "Service '{}' has a healthcheck without a test command",
+                    name
+                )));
+            }
+            for (kind, refs, declared) in [
+                ("secret", &spec.secrets, &self.secrets),
+                ("config", &spec.configs, &self.configs),
+            ] {
+                if let Some(missing) = refs.iter().find(|r| !declared.contains_key(*r)) {
+                    return Err(Error::Configuration(format!(
+                        "Service '{}' references undeclared {} '{}'",
+                        name, kind, missing
+                    )));
+                }
+            }
+
+            Self::validate_tunables(name, spec)?;
+
+            services.insert(
+                name.as_str(),
+                ComposeService {
+                    image: &spec.image,
+                    ports: &spec.ports,
+                    environment: spec
+                        .env
+                        .iter()
+                        .map(|(k, v)| (k.as_str(), v.as_str()))
+                        .collect(),
+                    volumes: &spec.volumes,
+                    command: &spec.command,
+                    depends_on: Self::depends_on_section(spec),
+                    healthcheck: &spec.healthcheck,
+                    deploy: Self::deploy_section(name, spec.resources)?,
+                    secrets: &spec.secrets,
+                    configs: &spec.configs,
+                    networks: &spec.networks,
+                    shm_size: &spec.shm_size,
+                    ulimits: &spec.ulimits,
+                    sysctls: &spec.sysctls,
+                },
+            );
+        }
+
+        if let Some((vcpu, memory_mb)) = self.vm_allocation {
+            let resources = || self.services.values().filter_map(|spec| spec.resources);
+            let total_cpus: f64 = resources().filter_map(|r| r.cpus).sum();
+            let total_memory: u64 = resources().filter_map(|r| r.memory_mb).sum();
+            if total_cpus > f64::from(vcpu) {
+                return Err(Error::Configuration(format!(
+                    "Service CPU limits total {} but the VM has {} vCPUs",
+                    total_cpus, vcpu
+                )));
+            }
+            if total_memory > u64::from(memory_mb) {
+                return Err(Error::Configuration(format!(
+                    "Service memory limits total {}MB but the VM has {}MB",
+                    total_memory, memory_mb
+                )));
+            }
+        }
+
+        fn file_sources(
+            sources: &BTreeMap<String, String>,
+        ) -> BTreeMap<&str, ComposeFileSource<'_>> {
+            sources
+                .iter()
+                .map(|(name, file)| (name.as_str(), ComposeFileSource { file }))
+                .collect()
+        }
+        let compose = ComposeFile {
+            services,
+            secrets: file_sources(&self.secrets),
+            configs: file_sources(&self.configs),
+            networks: self
+                .networks
+                .iter()
+                .map(|(name, driver)| (name.as_str(), ComposeNetwork { driver }))
+                .collect(),
+        };
+        let yaml = serde_yaml::to_string(&compose)
+            .map_err(|e| Error::Serialization(format!("Failed to render compose YAML: {}", e)))?;
+        check_port_conflicts(&yaml)?;
+        check_network_references(&yaml)?;
+        Ok(yaml)
+    }
+
+    /// Renders `depends_on` as a plain list unless a dependency has a
+    /// condition; in long form, unconditioned dependencies wait for
+    /// `service_started`, matching the short form.
+    fn depends_on_section(spec: &ServiceSpec) -> Option<ComposeDependsOn<'_>> {
+        if spec.depends_on_conditions.is_empty() {
+            return (!spec.depends_on.is_empty())
+                .then_some(ComposeDependsOn::List(&spec.depends_on));
+        }
+        let mut dependencies: BTreeMap<&str, ComposeDependency> = spec
+            .depends_on
+            .iter()
+            .map(|dep| {
+                (
+                    dep.as_str(),
+                    ComposeDependency {
+                        condition: DependencyCondition::ServiceStarted,
+                    },
+                )
+            })
+            .collect();
+        dependencies.extend(spec.depends_on_conditions.iter().map(|(dep, condition)| {
+            (
+                dep.as_str(),
+                ComposeDependency {
+                    condition: *condition,
+                },
+            )
+        }));
+        Some(ComposeDependsOn::Conditions(dependencies))
+    }
+
+    /// Validates a service's runtime tunables: `shm_size` must be a byte size
+    /// such as "256m", ulimits need `soft <= hard`, and sysctls need dotted
+    /// names with non-empty values.
+    fn validate_tunables(name: &str, spec: &ServiceSpec) -> Result<(), Error> {
+        if let Some(size) = &spec.shm_size {
+            if !is_byte_size(size) {
+                return Err(Error::Configuration(format!(
+                    "Service '{}' has invalid shm_size '{}'; expected a size like \"256m\" or \"1gb\"",
+                    name, size
+                )));
+            }
+        }
+        for (ulimit, limit) in &spec.ulimits {
+            if ulimit.is_empty() {
+                return Err(Error::Configuration(format!(
+                    "Service '{}' has a ulimit with an empty name",
+                    name
+                )));
+            }
+            if limit.soft > limit.hard {
+                return Err(Error::Configuration(format!(
+                    "Service '{}' has invalid ulimit '{}': soft limit {} exceeds hard limit {}",
+                    name, ulimit, limit.soft, limit.hard
+                )));
+            }
+        }
+        let is_sysctl_name = |key: &str| {
+            key.contains('.')
+                && key.split('.').all(|part| {
+                    !part.is_empty()
+                        && part
+                            .chars()
+                            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+                })
+        };
+        for (key, value) in &spec.sysctls {
+            if !is_sysctl_name(key) || value.trim().is_empty() {
+                return Err(Error::Configuration(format!(
+                    "Service '{}' has invalid sysctl '{}={}'",
+                    name, key, value
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Converts a service's resource limits into a compose `deploy` section.
+    fn deploy_section(
+        name: &str,
+        resources: Option<ServiceResources>,
+    ) -> Result<Option<ComposeDeploy>, Error> {
+        let Some(resources) = resources else {
+            return Ok(None);
+        };
+        if resources
+            .cpus
+            .is_some_and(|cpus| !(cpus > 0.0 && cpus.is_finite()))
+            || resources.memory_mb == Some(0)
+        {
+            return Err(Error::Configuration(format!(
+                "Service '{}' has a non-positive resource limit",
+                name
+            )));
+        }
+        if resources.cpus.is_none() && resources.memory_mb.is_none() {
+            return Ok(None);
+        }
+
+        Ok(Some(ComposeDeploy {
+            resources: ComposeResources {
+                limits: ComposeLimits {
+                    cpus: resources.cpus.map(|cpus| cpus.to_string()),
+                    memory: resources.memory_mb.map(|mb| format!("{}M", mb)),
+                },
+            },
+        }))
+    }
+}
+
+/// Checks for a compose byte size: digits with an optional `b`, `k`, `m` or
+/// `g` unit, optionally followed by `b` (e.g. "64000000", "256m", "1gb").
+fn is_byte_size(size: &str) -> bool {
+    let size = size.trim().to_ascii_lowercase();
+    let digits = size.bytes().take_while(u8::is_ascii_digit).count();
+    digits > 0
+        && matches!(
+            &size[digits..],
+            "" | "b" | "k" | "kb" | "m" | "mb" | "g" | "gb"
+        )
+}
+
+/// A variable referenced through `${NAME}` or `$NAME` interpolation in a compose file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct InterpolatedVar {
+    /// Variable name
+    pub name: String,
+
+    /// Whether the reference supplies a fallback (`${NAME:-default}` or `${NAME-default}`)
+    pub has_default: bool,
+}
+
+/// Scans compose YAML for variable interpolations, in order of first appearance.
+///
+/// `$$` escapes are skipped. Each variable name is returned once; a variable is
+/// considered defaulted only if every reference to it provides a default.
+pub(crate) fn interpolated_variables(yaml: &str) -> Vec<InterpolatedVar> {
+    let mut vars: Vec<InterpolatedVar> = Vec::new();
+    let mut record = |name: &str, has_default: bool| {
+        if name.is_empty() {
+            return;
+        }
+        match vars.iter_mut().find(|var| var.name == name) {
+            Some(var) => var.has_default &= has_default,
+            None => vars.push(InterpolatedVar {
+                name: name.to_string(),
+                has_default,
+            }),
+        }
+    };
+
+    let is_name_char = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    let mut rest = yaml;
+    while let Some(pos) = rest.find('$') {
+        rest = &rest[pos + 1..];
+        if let Some(after) = rest.strip_prefix('$') {
+            rest = after;
+        } else if let Some(braced) = rest.strip_prefix('{') {
+            let Some(end) = braced.find('}') else {
+                break;
+            };
+            let expr = &braced[..end];
+            let name_len = expr.find(|c| !is_name_char(c)).unwrap_or(expr.len());
+            let modifier = &expr[name_len..];
+            record(
+                &expr[..name_len],
+                modifier.starts_with(":-") || modifier.starts_with('-'),
+            );
+            rest = &braced[end + 1..];
+        } else {
+            let name_len = rest.find(|c| !is_name_char(c)).unwrap_or(rest.len());
+            record(&rest[..name_len], false);
+            rest = &rest[name_len..];
+        }
+    }
+    vars
+}
+
+/// A host port range published by a service.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct PortBinding {
+    service: String,
+    host_ip: Option<String>,
+    protocol: String,
+    start: u16,
+    end: u16,
+}
+
+impl PortBinding {
+    /// Returns `true` if both bindings claim a common host port.
+    ///
+    /// A binding without a specific host IP listens on every interface, so it
+    /// conflicts with any binding of the same port and protocol.
+    fn conflicts_with(&self, other: &PortBinding) -> bool {
+        let ips_overlap = match (&self.host_ip, &other.host_ip) {
+            (Some(a), Some(b)) => a == b,
+            _ => true,
+        };
+        ips_overlap
+            && self.protocol == other.protocol
+            && self.start <= other.end
+            && other.start <= self.end
+    }
+
+    fn describe_ports(&self) -> String {
+        let ports = if self.start == self.end {
+            self.start.to_string()
+        } else {
+            format!("{}-{}", self.start, self.end)
+        };
+        match &self.host_ip {
+            Some(ip) => format!("{}:{}/{}", ip, ports, self.protocol),
+            None => format!("{}/{}", ports, self.protocol),
+        }
+    }
+}
+
+/// Parses a published port or port range such as `80` or `8000-8010`.
+fn parse_port_range(raw: &str) -> Option<(u16, u16)> {
+    let (start, end) = raw.split_once('-').unwrap_or((raw, raw));
+    let (start, end) = (start.trim().parse().ok()?, end.trim().parse().ok()?);
+    (start <= end).then_some((start, end))
+}
+
+/// Extracts the host-side binding from a short-syntax port mapping such as
+/// `80:80`, `127.0.0.1:8080:80/udp` or `[::1]:6001:6001`.
+///
+/// Container-only ports (`80`) and ephemeral host ports (`127.0.0.1::80`)
+/// publish no fixed host port and yield `None`, as do interpolated values.
+fn parse_short_port(service: &str, mapping: &str) -> Option<PortBinding> {
+    let (mapping, protocol) = mapping.split_once('/').unwrap_or((mapping, "tcp"));
+    let (host, _container) = mapping.rsplit_once(':')?;
+    let (host_ip, published) = match host.rsplit_once(':') {
+        Some((ip, published)) => (Some(ip.trim_matches(|c| c == '[' || c == ']')), published),
+        None => (None, host),
+    };
+    let (start, end) = parse_port_range(published)?;
+    Some(PortBinding {
+        service: service.to_string(),
+        host_ip: host_ip.filter(|ip| !is_wildcard_ip(ip)).map(str::to_string),
+        protocol: protocol.to_ascii_lowercase(),
+        start,
+        end,
+    })
+}
+
+/// Extracts the host-side binding from a long-syntax port mapping with
+/// `published`, `protocol` and `host_ip` keys.
+fn parse_long_port(service: &str, mapping: &serde_yaml::Mapping) -> Option<PortBinding> {
+    let published = match mapping.get("published")? {
+        serde_yaml::Value::Number(n) => n.to_string(),
+        serde_yaml::Value::String(s) => s.clone(),
+        _ => return None,
+    };
+    let (start, end) = parse_port_range(&published)?;
+    let field = |key: &str| mapping.get(key).and_then(|v| v.as_str());
+    Some(PortBinding {
+        service: service.to_string(),
+        host_ip: field("host_ip")
+            .filter(|ip| !is_wildcard_ip(ip))
+            .map(str::to_string),
+        protocol: field("protocol").unwrap_or("tcp").to_ascii_lowercase(),
+        start,
+        end,
+    })
+}
+
+fn is_wildcard_ip(ip: &str) -> bool {
+    matches!(ip, "" | "0.0.0.0" | "::")
+}
+
+/// Rejects compose YAML in which services publish overlapping host ports.
+///
+/// Both short (`80:80/tcp`, ranges, host IPs) and long port syntax are
+/// understood. Ports that cannot be resolved statically, such as `${PORT}`,
+/// are skipped, as is YAML that does not parse; the API reports those.
+///
+/// # Errors
+///
+/// Returns `Error::Configuration` listing every conflicting pair of services
+pub(crate) fn check_port_conflicts(yaml: &str) -> Result<(), Error> {
+    let Ok(compose) = serde_yaml::from_str::<serde_yaml::Value>(yaml) else {
+        return Ok(());
+    };
+    let Some(services) = compose.get("services").and_then(|s| s.as_mapping()) else {
+        return Ok(());
+    };
+
+    let mut bindings = Vec::new();
+    for (name, service) in services {
+        let Some(name) = name.as_str() else {
+            continue;
+        };
+        let Some(ports) = service.get("ports").and_then(|p| p.as_sequence()) else {
+            continue;
+        };
+        bindings.extend(ports.iter().filter_map(|port| match port {
+            serde_yaml::Value::String(mapping) => parse_short_port(name, mapping),
+            serde_yaml::Value::Mapping(mapping) => parse_long_port(name, mapping),
+            _ => None,
+        }));
+    }
+
+    let conflicts: Vec<String> = bindings
+        .iter()
+        .enumerate()
+        .flat_map(|(i, a)| bindings[i + 1..].iter().map(move |b| (a, b)))
+        .filter(|(a, b)| a.conflicts_with(b))
+        .map(|(a, b)| {
+            format!(
+                "'{}' ({}) and '{}' ({})",
+                a.service,
+                a.describe_ports(),
+                b.service,
+                b.describe_ports()
+            )
+        })
+        .collect();
+
+    if conflicts.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::Configuration(format!(
+            "Services publish conflicting host ports: {}",
+            conflicts.join(", ")
+        )))
+    }
+}
+
+/// Runs the local compose checks performed before deploying or updating.
+///
+/// Catches problems the platform would only report obscurely: conflicting
+/// host ports, undeclared networks and local build contexts.
+///
+/// # Errors
+///
+/// Returns `Error::Configuration` describing the first failed check
+pub(crate) fn validate_compose(yaml: &str) -> Result<(), Error> {
+    check_port_conflicts(yaml)?;
+    check_network_references(yaml)?;
+    check_prebuilt_images(yaml)
+}
+
+/// Rejects services with a `build:` section; the TEE platform cannot build
+/// images from a local context and only runs pre-built ones. YAML that does
+/// not parse is skipped; the API reports it.
+///
+/// # Errors
+///
+/// Returns `Error::Configuration` listing the services that use `build:`
+pub(crate) fn check_prebuilt_images(yaml: &str) -> Result<(), Error> {
+    let Ok(compose) = serde_yaml::from_str::<serde_yaml::Value>(yaml) else {
+        return Ok(());
+    };
+    let Some(services) = compose.get("services").and_then(|s| s.as_mapping()) else {
+        return Ok(());
+    };
+
+    let building: Vec<&str> = services
+        .iter()
+        .filter(|(_, service)| service.get("build").is_some())
+        .filter_map(|(name, _)| name.as_str())
+        .collect();
+    if building.is_empty() {
+        return Ok(());
+    }
+    Err(Error::Configuration(format!(
+        "Service(s) {} use a `build:` section, but only pre-built images are supported. \
+         Build and push the image to a registry, then reference it with `image:`",
+        building
+            .iter()
+            .map(|name| format!("'{}'", name))
+            .collect::<Vec<_>>()
+            .join(", ")
+    )))
+}
+
+/// Network every service joins implicitly, which needs no declaration.
+const DEFAULT_NETWORK: &str = "default";
+
+/// Checks that every network a service joins is declared under the top-level
+/// `networks` key.
+///
+/// Both the list form and the map form of a service's `networks` are
+/// understood; the implicit `default` network needs no declaration. YAML that
+/// does not parse is skipped; the API reports it.
+///
+/// # Errors
+///
+/// Returns `Error::Configuration` naming the first service that joins an
+/// undeclared network
+pub(crate) fn check_network_references(yaml: &str) -> Result<(), Error> {
+    let Ok(compose) = serde_yaml::from_str::<serde_yaml::Value>(yaml) else {
+        return Ok(());
+    };
+    let Some(services) = compose.get("services").and_then(|s| s.as_mapping()) else {
+        return Ok(());
+    };
+    let declared = compose.get("networks").and_then(|n| n.as_mapping());
+
+    for (name, service) in services {
+        let joined: Vec<&str> = match service.get("networks") {
+            Some(serde_yaml::Value::Sequence(networks)) => {
+                networks.iter().filter_map(|n| n.as_str()).collect()
+            }
+            Some(serde_yaml::Value::Mapping(networks)) => {
+                networks.keys().filter_map(|n| n.as_str()).collect()
+            }
+            _ => continue,
+        };
+        let is_declared = |network: &str| {
+            network == DEFAULT_NETWORK || declared.is_some_and(|d| d.contains_key(network))
+        };
+        if let Some(missing) = joined.into_iter().find(|n| !is_declared(n)) {
+            return Err(Error::Configuration(format!(
+                "Service '{}' references undeclared network '{}'",
+                name.as_str().unwrap_or_default(),
+                missing
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Returns the environment variable names declared by the services of a
+/// compose file, in order of first appearance.
+///
+/// Both the list form (`- KEY=value` or `- KEY`) and the map form of
+/// `environment` are understood. YAML that does not parse yields no keys.
+pub(crate) fn environment_keys(yaml: &str) -> Vec<String> {
+    let Ok(compose) = serde_yaml::from_str::<serde_yaml::Value>(yaml) else {
+        return Vec::new();
+    };
+    let Some(services) = compose.get("services").and_then(|s| s.as_mapping()) else {
+        return Vec::new();
+    };
+
+    let mut keys: Vec<String> = Vec::new();
+    for service in services.values() {
+        let names: Vec<&str> = match service.get("environment") {
+            Some(serde_yaml::Value::Sequence(entries)) => entries
+                .iter()
+                .filter_map(|entry| entry.as_str())
+                .map(|entry| entry.split_once('=').map_or(entry, |(key, _)| key))
+                .collect(),
+            Some(serde_yaml::Value::Mapping(entries)) => {
+                entries.keys().filter_map(|key| key.as_str()).collect()
+            }
+            _ => continue,
+        };
+        for name in names.into_iter().map(str::trim) {
+            if !name.is_empty() && !keys.iter().any(|key| key == name) {
+                keys.push(name.to_string());
+            }
+        }
+    }
+    keys
+}
+
+/// Removes the obsolete top-level `version` key from compose YAML.
+///
+/// The Compose Spec ignores `version`, and newer tooling warns about it. Only
+/// unindented `version:` lines are removed, so service-level keys and the rest
+/// of the file, including comments, are left untouched. Returns `None` if the
+/// file has no top-level `version`.
+pub(crate) fn strip_compose_version(yaml: &str) -> Option<String> {
+    let is_version = |line: &str| line.starts_with("version:");
+    if !yaml.lines().any(is_version) {
+        return None;
+    }
+    Some(
+        yaml.split_inclusive('\n')
+            .filter(|line| !is_version(line))
+            .collect(),
+    )
+}
+
+/// Resolves YAML anchors, aliases and `<<` merge keys into concrete values.
+///
+/// The file is round-tripped through `serde_yaml`, so comments and quoting
+/// style are not preserved. Returns `None` if the file uses no anchors or
+/// aliases, leaving it untouched.
+///
+/// # Errors
+///
+/// Returns an error if the file is not valid YAML or a merge key is malformed
+pub(crate) fn expand_yaml_anchors(yaml: &str) -> Result<Option<String>, Error> {
+    if !has_yaml_anchors(yaml) {
+        return Ok(None);
+    }
+    let mut value: serde_yaml::Value = serde_yaml::from_str(yaml)
+        .map_err(|e| Error::Configuration(format!("Invalid compose YAML: {}", e)))?;
+    value
+        .apply_merge()
+        .map_err(|e| Error::Configuration(format!("Invalid merge key in compose YAML: {}", e)))?;
+    serde_yaml::to_string(&value)
+        .map(Some)
+        .map_err(|e| Error::Serialization(format!("Failed to render compose YAML: {}", e)))
+}
+
+/// Detects anchors (`&name`), aliases (`*name`) and merge keys (`<<:`) in
+/// value position, i.e. right after a key or list dash.
+fn has_yaml_anchors(yaml: &str) -> bool {
+    let is_marker = |token: &str| {
+        let mut chars = token.chars();
+        matches!(chars.next(), Some('&' | '*'))
+            && chars
+                .next()
+                .is_some_and(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    };
+    yaml.lines().any(|line| {
+        let line = line.trim_start();
+        if line.starts_with('#') {
+            return false;
+        }
+        if line.starts_with("<<:") {
+            return true;
+        }
+        let mut previous: Option<&str> = None;
+        line.split_whitespace().any(|token| {
+            let in_value_position = previous.is_none_or(|prev| prev == "-" || prev.ends_with(':'));
+            previous = Some(token);
+            in_value_position && is_marker(token)
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_two_service_build_round_trip() {
+        let mut env = HashMap::new();
+        env.insert("DATABASE_URL".to_string(), "postgres://db:5432".to_string());
+
+        let yaml = ComposeBuilder::new()
+            .add_service(
+                "web",
+                ServiceSpec {
+                    image: "nginx:latest".to_string(),
+                    ports: vec!["80:80".to_string()],
+                    env,
+                    command: Some(vec!["nginx".to_string(), "-g".to_string()]),
+                    depends_on: vec!["db".to_string()],
+                    ..Default::default()
+                },
+            )
+            .add_service(
+                "db",
+                ServiceSpec {
+                    image: "postgres:16".to_string(),
+                    volumes: vec!["pgdata:/var/lib/postgresql/data".to_string()],
+                    ..Default::default()
+                },
+            )
+            .build_yaml()
+            .unwrap();
+
+        let parsed: serde_yaml::Value = serde_yaml::from_str(&yaml).unwrap();
+        let web = &parsed["services"]["web"];
+        assert_eq!(web["image"], "nginx:latest");
+        assert_eq!(web["ports"][0], "80:80");
+        assert_eq!(web["environment"]["DATABASE_URL"], "postgres://db:5432");
+        assert_eq!(web["command"][1], "-g");
+        assert_eq!(web["depends_on"][0], "db");
+        assert!(web.get("volumes").is_none());
+
+        let db = &parsed["services"]["db"];
+        assert_eq!(db["image"], "postgres:16");
+        assert_eq!(db["volumes"][0], "pgdata:/var/lib/postgresql/data");
+        assert!(db.get("ports").is_none());
+    }
+
+    #[test]
+    fn test_depends_on_healthy_dependency() {
+        let yaml = ComposeBuilder::new()
+            .add_service(
+                "app",
+                ServiceSpec {
+                    image: "app:1".to_string(),
+                    depends_on: vec!["cache".to_string()],
+                    depends_on_conditions: BTreeMap::from([(
+                        "db".to_string(),
+                        DependencyCondition::ServiceHealthy,
+                    )]),
+                    ..Default::default()
+                },
+            )
+            .add_service(
+                "db",
+                ServiceSpec {
+                    image: "postgres:16".to_string(),
+                    healthcheck: Some(ServiceHealthcheck {
+                        test: vec!["CMD".to_string(), "pg_isready".to_string()],
+                        interval: Some("5s".to_string()),
+                        retries: Some(5),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                },
+            )
+            .add_service(
+                "cache",
+                ServiceSpec {
+                    image: "redis:7".to_string(),
+                    ..Default::default()
+                },
+            )
+            .build_yaml()
+            .unwrap();
+
+        let parsed: serde_yaml::Value = serde_yaml::from_str(&yaml).unwrap();
+        let app = &parsed["services"]["app"];
+        assert_eq!(app["depends_on"]["db"]["condition"], "service_healthy");
+        assert_eq!(app["depends_on"]["cache"]["condition"], "service_started");
+        let healthcheck = &parsed["services"]["db"]["healthcheck"];
+        assert_eq!(healthcheck["test"][1], "pg_isready");
+        assert_eq!(healthcheck["interval"], "5s");
+        assert_eq!(healthcheck["retries"], 5);
+        assert!(healthcheck.get("timeout").is_none());
+    }
+
+    #[test]
+    fn test_depends_on_healthy_requires_healthcheck() {
+        let builder = ComposeBuilder::new()
+            .add_service(
+                "app",
+                ServiceSpec {
+                    image: "app:1".to_string(),
+                    depends_on_conditions: BTreeMap::from([(
+                        "db".to_string(),
+                        DependencyCondition::ServiceHealthy,
+                    )]),
+                    ..Default::default()
+                },
+            )
+            .add_service(
+                "db",
+                ServiceSpec {
+                    image: "postgres:16".to_string(),
+                    ..Default::default()
+                },
+            );
+
+        match builder.build_yaml() {
+            Err(Error::Configuration(message)) => assert_eq!(
+                message,
+                "Service 'app' waits for 'db' to be healthy, but 'db' has no healthcheck"
+            ),
+            other => panic!("expected missing healthcheck error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_expand_yaml_anchors() {
+        let yaml = "\
+x-common: &common
+  restart: always
+  environment:
+    LOG_LEVEL: info
+services:
+  web:
+    <<: *common
+    image: nginx
+  worker:
+    image: worker
+    environment: *env
+";
+        // An alias to an undefined anchor is invalid YAML
+        assert!(expand_yaml_anchors(yaml).is_err());
+
+        let yaml = yaml.replace(
+            "environment:\n    LOG_LEVEL",
+            "environment: &env\n    LOG_LEVEL",
+        );
+        let expanded = expand_yaml_anchors(&yaml).unwrap().unwrap();
+        assert!(!expanded.contains('&') && !expanded.contains('*') && !expanded.contains("<<"));
+
+        let parsed: serde_yaml::Value = serde_yaml::from_str(&expanded).unwrap();
+        let web = &parsed["services"]["web"];
+        assert_eq!(web["restart"], "always");
+        assert_eq!(web["image"], "nginx");
+        assert_eq!(
+            parsed["services"]["worker"]["environment"]["LOG_LEVEL"],
+            "info"
+        );
+
+        let plain = "services:\n  app:\n    image: nginx\n    command: sh -c 'a && b *.txt'\n";
+        assert_eq!(expand_yaml_anchors(plain).unwrap(), None);
+    }
+
+    #[test]
+    fn test_build_rejects_invalid_services() {
+        assert!(ComposeBuilder::new().build_yaml().is_err());
+
+        let missing_dependency = ComposeBuilder::new().add_service(
+            "web",
+            ServiceSpec {
+                image: "nginx:latest".to_string(),
+                depends_on: vec!["db".to_string()],
+                ..Default::default()
+            },
+        );
+        assert!(matches!(
+            missing_dependency.build_yaml(),
+            Err(Error::Configuration(_))
+        ));
+    }
+
+    #[test]
+    fn test_per_service_resource_limits() {
+        let builder = ComposeBuilder::new()
+            .add_service(
+                "node",
+                ServiceSpec {
+                    image: "node:20".to_string(),
+                    depends_on: vec!["mongo".to_string()],
+                    resources: Some(ServiceResources {
+                        cpus: Some(1.5),
+                        memory_mb: Some(1024),
+                    }),
+                    ..Default::default()
+                },
+            )
+            .add_service(
+                "mongo",
+                ServiceSpec {
+                    image: "mongo:7".to_string(),
+                    resources: Some(ServiceResources {
+                        cpus: Some(0.5),
+                        memory_mb: None,
+                    }),
+                    ..Default::default()
+                },
+            );
+
+        let yaml = builder
+            .clone()
+            .with_vm_allocation(2, 2048)
+            .build_yaml()
+            .unwrap();
+        let parsed: serde_yaml::Value = serde_yaml::from_str(&yaml).unwrap();
+        let node_limits = &parsed["services"]["node"]["deploy"]["resources"]["limits"];
+        assert_eq!(node_limits["cpus"], "1.5");
+        assert_eq!(node_limits["memory"], "1024M");
+        let mongo_limits = &parsed["services"]["mongo"]["deploy"]["resources"]["limits"];
+        assert_eq!(mongo_limits["cpus"], "0.5");
+        assert!(mongo_limits.get("memory").is_none());
+
+        assert!(matches!(
+            builder.clone().with_vm_allocation(1, 2048).build_yaml(),
+            Err(Error::Configuration(_))
+        ));
+        assert!(matches!(
+            builder.with_vm_allocation(2, 512).build_yaml(),
+            Err(Error::Configuration(_))
+        ));
+    }
+
+    #[test]
+    fn test_interpolated_variables() {
+        let yaml = "services:\n  app:\n    image: app:${TAG:-latest}\n    environment:\n      - TOKEN=${TOKEN}\n      - HOME=$HOME\n      - PRICE=$$5\n      - AGAIN=${TOKEN:?required}\n";
+        let vars = interpolated_variables(yaml);
+
+        assert_eq!(
+            vars,
+            vec![
+                InterpolatedVar {
+                    name: "TAG".to_string(),
+                    has_default: true
+                },
+                InterpolatedVar {
+                    name: "TOKEN".to_string(),
+                    has_default: false
+                },
+                InterpolatedVar {
+                    name: "HOME".to_string(),
+                    has_default: false
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_strip_compose_version() {
+        let versioned = "version: '3'\nservices:\n  app:\n    image: nginx\n";
+        assert_eq!(
+            strip_compose_version(versioned).unwrap(),
+            "services:\n  app:\n    image: nginx\n"
+        );
+
+        // Nested keys named `version` are not the top-level field
+        let unversioned = "services:\n  app:\n    image: nginx\n    labels:\n      version: '2'\n";
+        assert_eq!(strip_compose_version(unversioned), None);
+    }
+
+    #[test]
+    fn test_secrets_and_configs() {
+        let with_secret = |secret: &str| {
+            ComposeBuilder::new()
+                .add_secret("client_key", "./certs/client.key")
+                .add_config("app_config", "./config.toml")
+                .add_service(
+                    "api",
+                    ServiceSpec {
+                        image: "api:1".to_string(),
+                        secrets: vec![secret.to_string()],
+                        configs: vec!["app_config".to_string()],
+                        ..Default::default()
+                    },
+                )
+                .build_yaml()
+        };
+
+        let yaml = with_secret("client_key").unwrap();
+        let parsed: serde_yaml::Value = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(
+            parsed["secrets"]["client_key"]["file"],
+            "./certs/client.key"
+        );
+        assert_eq!(parsed["configs"]["app_config"]["file"], "./config.toml");
+        assert_eq!(parsed["services"]["api"]["secrets"][0], "client_key");
+        assert_eq!(parsed["services"]["api"]["configs"][0], "app_config");
+
+        match with_secret("server_key") {
+            Err(Error::Configuration(message)) => {
+                assert!(message.contains("undeclared secret 'server_key'"))
+            }
+            other => panic!("expected undeclared secret error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_runtime_tunables() {
+        let with_shm_size = |shm_size: &str| {
+            ComposeBuilder::new()
+                .add_service(
+                    "db",
+                    ServiceSpec {
+                        image: "postgres:16".to_string(),
+                        shm_size: Some(shm_size.to_string()),
+                        ulimits: BTreeMap::from([(
+                            "nofile".to_string(),
+                            ServiceUlimit {
+                                soft: 20000,
+                                hard: 40000,
+                            },
+                        )]),
+                        sysctls: BTreeMap::from([(
+                            "net.core.somaxconn".to_string(),
+                            "1024".to_string(),
+                        )]),
+                        ..Default::default()
+                    },
+                )
+                .build_yaml()
+        };
+
+        let yaml = with_shm_size("256m").unwrap();
+        let parsed: serde_yaml::Value = serde_yaml::from_str(&yaml).unwrap();
+        let db = &parsed["services"]["db"];
+        assert_eq!(db["shm_size"], "256m");
+        assert_eq!(db["ulimits"]["nofile"]["soft"], 20000);
+        assert_eq!(db["ulimits"]["nofile"]["hard"], 40000);
+        assert_eq!(db["sysctls"]["net.core.somaxconn"], "1024");
+        assert!(with_shm_size("1GB").is_ok());
+
+        for invalid in ["", "256 megabytes", "m", "-1m", "1.5g"] {
+            match with_shm_size(invalid) {
+                Err(Error::Configuration(message)) => assert!(message.contains("shm_size")),
+                other => panic!("expected shm_size error for {:?}, got {:?}", invalid, other),
+            }
+        }
+
+        let with_ulimit = |ulimit: &str, soft: u64, hard: u64| {
+            ComposeBuilder::new()
+                .add_service(
+                    "db",
+                    ServiceSpec {
+                        image: "postgres:16".to_string(),
+                        ulimits: BTreeMap::from([(
+                            ulimit.to_string(),
+                            ServiceUlimit { soft, hard },
+                        )]),
+                        ..Default::default()
+                    },
+                )
+                .build_yaml()
+        };
+        match with_ulimit("nofile", 2, 1) {
+            Err(Error::Configuration(message)) => assert!(message.contains("exceeds hard limit")),
+            other => panic!("expected ulimit error, got {:?}", other),
+        }
+        match with_ulimit("", 1, 2) {
+            Err(Error::Configuration(message)) => assert!(message.contains("empty name")),
+            other => panic!("expected ulimit error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_environment_keys() {
+        let yaml = "services:\n  api:\n    image: api\n    environment:\n      - DB_URL=${DB_URL}\n      - API_TOKEN\n      - LOG_LEVEL=info=verbose\n  worker:\n    image: worker\n    environment:\n      DB_URL: ${DB_URL}\n      QUEUE: jobs\n  static:\n    image: nginx\n";
+        assert_eq!(
+            environment_keys(yaml),
+            ["DB_URL", "API_TOKEN", "LOG_LEVEL", "QUEUE"]
+        );
+        assert!(environment_keys("not: [valid").is_empty());
+    }
+
+    #[test]
+    fn test_network_references() {
+        let yaml = ComposeBuilder::new()
+            .add_network("backend", "bridge")
+            .add_service(
+                "api",
+                ServiceSpec {
+                    image: "api".to_string(),
+                    networks: vec!["backend".to_string(), "default".to_string()],
+                    ..Default::default()
+                },
+            )
+            .build_yaml()
+            .unwrap();
+        let parsed: serde_yaml::Value = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(parsed["networks"]["backend"]["driver"], "bridge");
+        assert_eq!(parsed["services"]["api"]["networks"][0], "backend");
+        assert!(check_network_references(&yaml).is_ok());
+
+        let mapped = "services:\n  db:\n    image: postgres\n    networks:\n      backend:\n        aliases: [database]\nnetworks:\n  backend: {}\n";
+        assert!(check_network_references(mapped).is_ok());
+
+        let dangling = "services:\n  web:\n    image: nginx\n    networks:\n      - frontend\nnetworks:\n  backend: {}\n";
+        match check_network_references(dangling) {
+            Err(Error::Configuration(message)) => {
+                assert_eq!(
+                    message,
+                    "Service 'web' references undeclared network 'frontend'"
+                )
+            }
+            other => panic!("expected network error, got {:?}", other),
+        }
+
+        let undeclared = ComposeBuilder::new()
+            .add_service(
+                "api",
+                ServiceSpec {
+                    image: "api".to_string(),
+                    networks: vec!["backend".to_string()],
+                    ..Default::default()
+                },
+            )
+            .build_yaml();
+        assert!(matches!(undeclared, Err(Error::Configuration(_))));
+    }
+
+    #[test]
+    fn test_build_sections_rejected() {
+        let building = "services:\n  app:\n    build:\n      context: .\n      args:\n        VERSION: \"1\"\n  db:\n    image: postgres\n  worker:\n    build: ./worker\n";
+        match validate_compose(building) {
+            Err(Error::Configuration(message)) => {
+                assert!(message.starts_with("Service(s) 'app', 'worker' use a `build:` section"));
+                assert!(message.contains("registry"));
+            }
+            other => panic!("expected build error, got {:?}", other),
+        }
+
+        assert!(check_prebuilt_images("services:\n  db:\n    image: postgres\n").is_ok());
+    }
+
+    #[test]
+    fn test_port_conflicts() {
+        let conflicting = "services:\n  web:\n    image: nginx\n    ports:\n      - \"80:80\"\n      - \"8000-8010:8000-8010\"\n  api:\n    image: api\n    ports:\n      - \"80:8080/tcp\"\n  worker:\n    image: worker\n    ports:\n      - published: 8005\n        target: 9000\n";
+        match check_port_conflicts(conflicting) {
+            Err(Error::Configuration(message)) => {
+                assert!(message.contains("'web' (80/tcp) and 'api' (80/tcp)"));
+                assert!(message.contains("'web' (8000-8010/tcp) and 'worker' (8005/tcp)"));
+            }
+            other => panic!("expected port conflict error, got {:?}", other),
+        }
+
+        // Different protocols, host IPs, container-only and ephemeral ports coexist
+        let distinct = "services:\n  web:\n    image: nginx\n    ports:\n      - \"80:80\"\n      - \"127.0.0.1:9000:9000\"\n      - \"3000\"\n  dns:\n    image: dns\n    ports:\n      - \"53:53/udp\"\n      - \"80/udp\"\n      - \"127.0.0.2:9000:9000\"\n      - \"127.0.0.1::80\"\n      - \"${PORT}:80\"\n";
+        assert!(check_port_conflicts(distinct).is_ok());
+
+        let builder_conflict = ComposeBuilder::new()
+            .add_service(
+                "a",
+                ServiceSpec {
+                    image: "a".to_string(),
+                    ports: vec!["8080:80".to_string()],
+                    ..Default::default()
+                },
+            )
+            .add_service(
+                "b",
+                ServiceSpec {
+                    image: "b".to_string(),
+                    ports: vec!["0.0.0.0:8080:81".to_string()],
+                    ..Default::default()
+                },
+            )
+            .build_yaml();
+        assert!(matches!(builder_conflict, Err(Error::Configuration(_))));
+    }
+}
@@ -1,5 +1,35 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::error::Error;
+
+/// Phala Cloud API endpoint used when no custom URL is configured.
+pub(crate) const DEFAULT_API_URL: &str = "https://cloud-api.phala.network/api/v1";
+
+/// Phala Cloud API host the versioned API path is composed onto by default.
+pub(crate) const DEFAULT_API_HOST: &str = "https://cloud-api.phala.network";
+
+/// API version used unless configured otherwise.
+pub(crate) const DEFAULT_API_VERSION: &str = "v1";
+
+fn default_api_version() -> String {
+    DEFAULT_API_VERSION.to_string()
+}
+
+/// Timeout applied to every API request unless configured or overridden per call.
+pub(crate) const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+fn default_request_timeout() -> Duration {
+    DEFAULT_REQUEST_TIMEOUT
+}
+
+/// Time allowed to establish a connection, so unreachable endpoints fail fast.
+pub(crate) const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+fn default_connect_timeout() -> Duration {
+    DEFAULT_CONNECT_TIMEOUT
+}
 
 /// Configuration for deploying applications to the Phala TEE Cloud.
 ///
@@ -7,9 +37,19 @@ use std::collections::HashMap;
 /// including API credentials, Docker Compose configuration, and environment variables.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DeploymentConfig {
-    /// Base URL for the Phala TEE Cloud API
+    /// Full base URL for the Phala TEE Cloud API, used verbatim when
+    /// `api_host` is unset
     pub api_url: String,
 
+    /// Host the API URL is composed onto as `{api_host}/api/{api_version}`.
+    /// Unset by default, so assigning `api_url` directly keeps working
+    #[serde(default)]
+    pub api_host: Option<String>,
+
+    /// API version used with `api_host` (default: `"v1"`)
+    #[serde(default = "default_api_version")]
+    pub api_version: String,
+
     /// API key for authentication with the Phala Cloud API
     pub api_key: String,
 
@@ -27,9 +67,51 @@ pub struct DeploymentConfig {
 
     /// Optional custom VM configuration
     pub vm_config: Option<super::types::VmConfig>,
+
+    /// Skip TLS certificate verification (development only, never use in production)
+    #[serde(default)]
+    pub danger_accept_invalid_certs: bool,
+
+    /// Gzip-compress JSON request bodies and send `Content-Encoding: gzip`
+    #[serde(default)]
+    pub compress_requests: bool,
+
+    /// Reject API responses containing fields the response types do not model
+    #[serde(default)]
+    pub strict: bool,
+
+    /// Default timeout for API requests, overridable per call with `RequestOptions`
+    #[serde(default = "default_request_timeout")]
+    pub request_timeout: Duration,
+
+    /// Timeout for establishing a connection, separate from `request_timeout`
+    #[serde(default = "default_connect_timeout")]
+    pub connect_timeout: Duration,
 }
 
 impl DeploymentConfig {
+    /// Creates a builder that fills every field the caller does not set with an
+    /// empty default.
+    ///
+    /// # Returns
+    ///
+    /// A new `DeploymentConfigBuilder` instance
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use phala_tee_deploy_rs::DeploymentConfig;
+    ///
+    /// let config = DeploymentConfig::builder()
+    ///     .with_api_key("my-api-key")
+    ///     .build()
+    ///     .unwrap();
+    /// assert!(config.docker_compose.is_empty());
+    /// ```
+    pub fn builder() -> DeploymentConfigBuilder {
+        DeploymentConfigBuilder::new()
+    }
+
     /// Creates a new deployment configuration with default API URL.
     ///
     /// # Parameters
@@ -51,18 +133,28 @@ impl DeploymentConfig {
         image: String,
     ) -> Self {
         Self {
-            api_url: "https://cloud-api.phala.network/api/v1".to_string(),
+            api_url: DEFAULT_API_URL.to_string(),
+            api_host: None,
+            api_version: default_api_version(),
             api_key,
             docker_compose,
             env_vars,
             teepod_id,
             image,
             vm_config: None,
+            danger_accept_invalid_certs: false,
+            compress_requests: false,
+            strict: false,
+            request_timeout: DEFAULT_REQUEST_TIMEOUT,
+            connect_timeout: DEFAULT_CONNECT_TIMEOUT,
         }
     }
 
     /// Sets a custom API URL for the Phala Cloud API.
     ///
+    /// The URL is used verbatim, including its version segment; it replaces
+    /// any host set with `with_api_host`.
+    ///
     /// # Parameters
     ///
     /// * `api_url` - The custom API URL to use
@@ -72,9 +164,59 @@ impl DeploymentConfig {
     /// The updated `DeploymentConfig` instance for method chaining
     pub fn with_api_url(mut self, api_url: String) -> Self {
         self.api_url = api_url;
+        self.api_host = None;
         self
     }
 
+    /// Sets the API host, composing the base URL as `{host}/api/{version}`.
+    ///
+    /// # Parameters
+    ///
+    /// * `api_host` - Scheme and host of the API, e.g. `https://cloud-api.phala.network`
+    ///
+    /// # Returns
+    ///
+    /// The updated `DeploymentConfig` instance for method chaining
+    pub fn with_api_host(mut self, api_host: impl Into<String>) -> Self {
+        self.api_host = Some(api_host.into());
+        self
+    }
+
+    /// Sets the API version used in the composed base URL.
+    ///
+    /// With the default API URL, the URL is composed from the default host
+    /// from then on. A custom URL set with `with_api_url` is left as-is.
+    ///
+    /// # Parameters
+    ///
+    /// * `api_version` - The API version, e.g. `"v2"` (default: `"v1"`)
+    ///
+    /// # Returns
+    ///
+    /// The updated `DeploymentConfig` instance for method chaining
+    pub fn with_api_version(mut self, api_version: impl Into<String>) -> Self {
+        self.api_version = api_version.into();
+        if self.api_host.is_none() && self.api_url == DEFAULT_API_URL {
+            self.api_host = Some(DEFAULT_API_HOST.to_string());
+        }
+        self
+    }
+
+    /// Returns the base URL API paths are appended to.
+    ///
+    /// `{api_host}/api/{api_version}` when a host is configured, otherwise
+    /// `api_url` as-is.
+    pub fn base_url(&self) -> String {
+        match &self.api_host {
+            Some(host) => format!(
+                "{}/api/{}",
+                host.trim_end_matches('/'),
+                self.api_version.trim_matches('/')
+            ),
+            None => self.api_url.clone(),
+        }
+    }
+
     /// Sets a custom VM configuration for the deployment.
     ///
     /// # Parameters
@@ -88,4 +230,412 @@ impl DeploymentConfig {
         self.vm_config = Some(vm_config);
         self
     }
+
+    /// Disables TLS certificate verification for API requests.
+    ///
+    /// This is intended only for local or development stacks that use
+    /// self-signed certificates. Never enable it against a production endpoint,
+    /// as it makes the connection vulnerable to man-in-the-middle attacks.
+    ///
+    /// # Parameters
+    ///
+    /// * `accept` - Whether invalid certificates should be accepted
+    ///
+    /// # Returns
+    ///
+    /// The updated `DeploymentConfig` instance for method chaining
+    pub fn with_danger_accept_invalid_certs(mut self, accept: bool) -> Self {
+        self.danger_accept_invalid_certs = accept;
+        self
+    }
+
+    /// Enables gzip compression of JSON request bodies.
+    ///
+    /// Useful for large compose files such as ELIZA deployments with embedded
+    /// character data. Only enable it against endpoints that accept
+    /// `Content-Encoding: gzip`.
+    ///
+    /// # Parameters
+    ///
+    /// * `compress` - Whether request bodies should be gzip-compressed
+    ///
+    /// # Returns
+    ///
+    /// The updated `DeploymentConfig` instance for method chaining
+    pub fn with_compress_requests(mut self, compress: bool) -> Self {
+        self.compress_requests = compress;
+        self
+    }
+
+    /// Enables strict parsing of API responses.
+    ///
+    /// A diagnostic for tracking down API changes: when enabled, any response
+    /// field not modelled by the target type fails parsing with an
+    /// `Error::Serialization` naming the field, instead of being silently ignored.
+    /// Fields captured by catch-all maps (such as `CvmInfo::extra`) are always
+    /// accepted.
+    ///
+    /// # Parameters
+    ///
+    /// * `strict` - Whether unknown response fields should be rejected
+    ///
+    /// # Returns
+    ///
+    /// The updated `DeploymentConfig` instance for method chaining
+    pub fn with_strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Sets the default timeout for API requests.
+    ///
+    /// Individual calls can override it through their `*_with_options`
+    /// variants, e.g. a longer timeout for a large deploy.
+    ///
+    /// # Parameters
+    ///
+    /// * `timeout` - Timeout applied to each request (default: 30 seconds)
+    ///
+    /// # Returns
+    ///
+    /// The updated `DeploymentConfig` instance for method chaining
+    pub fn with_request_timeout(mut self, timeout: Duration) -> Self {
+        self.request_timeout = timeout;
+        self
+    }
+
+    /// Sets the timeout for establishing a connection to the API.
+    ///
+    /// Bounds only the connect phase, so an unreachable endpoint fails well
+    /// before `request_timeout` elapses.
+    ///
+    /// # Parameters
+    ///
+    /// * `timeout` - Timeout for connecting (default: 5 seconds)
+    ///
+    /// # Returns
+    ///
+    /// The updated `DeploymentConfig` instance for method chaining
+    pub fn with_connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = timeout;
+        self
+    }
+}
+
+/// Per-call options overriding the client defaults for a single request.
+///
+/// Accepted by the `*_with_options` variants of `TeeClient` and `TeeDeployer`
+/// methods. Unset options fall back to the client configuration.
+///
+/// # Examples
+///
+/// ```
+/// use phala_tee_deploy_rs::RequestOptions;
+/// use std::time::Duration;
+///
+/// let options = RequestOptions::new().with_timeout(Duration::from_secs(300));
+/// assert_eq!(options.timeout, Some(Duration::from_secs(300)));
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RequestOptions {
+    /// Timeout for this call's requests, replacing `DeploymentConfig::request_timeout`
+    pub timeout: Option<Duration>,
+}
+
+impl RequestOptions {
+    /// Creates options that keep every client default.
+    ///
+    /// # Returns
+    ///
+    /// A new `RequestOptions` instance
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Overrides the request timeout for this call.
+    ///
+    /// # Parameters
+    ///
+    /// * `timeout` - Timeout applied to each request the call makes
+    ///
+    /// # Returns
+    ///
+    /// The updated `RequestOptions` instance for method chaining
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+}
+
+/// Builder for `DeploymentConfig` with defaults for every optional field.
+///
+/// Only the API key is required. Unlike a struct literal, code using the
+/// builder keeps compiling when new configuration fields are added.
+#[derive(Debug, Clone, Default)]
+pub struct DeploymentConfigBuilder {
+    api_key: Option<String>,
+    api_url: Option<String>,
+    api_host: Option<String>,
+    api_version: Option<String>,
+    docker_compose: String,
+    env_vars: HashMap<String, String>,
+    teepod_id: u64,
+    image: String,
+    vm_config: Option<super::types::VmConfig>,
+    danger_accept_invalid_certs: bool,
+    compress_requests: bool,
+    strict: bool,
+    request_timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+}
+
+impl DeploymentConfigBuilder {
+    /// Creates a new builder with no parameters set.
+    ///
+    /// # Returns
+    ///
+    /// A new `DeploymentConfigBuilder` instance
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the API key for authenticating with the Phala Cloud API.
+    ///
+    /// # Parameters
+    ///
+    /// * `api_key` - The API key to use
+    ///
+    /// # Returns
+    ///
+    /// The builder instance for method chaining
+    pub fn with_api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    /// Sets a custom API URL, replacing the default Phala Cloud endpoint.
+    ///
+    /// # Parameters
+    ///
+    /// * `api_url` - The custom API URL to use
+    ///
+    /// # Returns
+    ///
+    /// The builder instance for method chaining
+    pub fn with_api_url(mut self, api_url: impl Into<String>) -> Self {
+        self.api_url = Some(api_url.into());
+        self
+    }
+
+    /// Sets the API host, composing the base URL as `{host}/api/{version}`.
+    ///
+    /// Takes precedence over `with_api_url`.
+    ///
+    /// # Parameters
+    ///
+    /// * `api_host` - Scheme and host of the API
+    ///
+    /// # Returns
+    ///
+    /// The builder instance for method chaining
+    pub fn with_api_host(mut self, api_host: impl Into<String>) -> Self {
+        self.api_host = Some(api_host.into());
+        self
+    }
+
+    /// Sets the API version used in the composed base URL.
+    ///
+    /// # Parameters
+    ///
+    /// * `api_version` - The API version, e.g. `"v2"` (default: `"v1"`)
+    ///
+    /// # Returns
+    ///
+    /// The builder instance for method chaining
+    pub fn with_api_version(mut self, api_version: impl Into<String>) -> Self {
+        self.api_version = Some(api_version.into());
+        self
+    }
+
+    /// Sets the Docker Compose configuration used by `TeeClient::deploy`.
+    ///
+    /// # Parameters
+    ///
+    /// * `docker_compose` - Docker Compose configuration as a string
+    ///
+    /// # Returns
+    ///
+    /// The builder instance for method chaining
+    pub fn with_docker_compose(mut self, docker_compose: impl Into<String>) -> Self {
+        self.docker_compose = docker_compose.into();
+        self
+    }
+
+    /// Adds an environment variable to be encrypted into the deployment.
+    ///
+    /// # Parameters
+    ///
+    /// * `key` - Variable name
+    /// * `value` - Variable value
+    ///
+    /// # Returns
+    ///
+    /// The builder instance for method chaining
+    pub fn with_env_var(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.env_vars.insert(key.into(), value.into());
+        self
+    }
+
+    /// Sets the TEEPod to deploy to.
+    ///
+    /// # Parameters
+    ///
+    /// * `teepod_id` - ID of the TEEPod
+    ///
+    /// # Returns
+    ///
+    /// The builder instance for method chaining
+    pub fn with_teepod_id(mut self, teepod_id: u64) -> Self {
+        self.teepod_id = teepod_id;
+        self
+    }
+
+    /// Sets the VM image to deploy.
+    ///
+    /// # Parameters
+    ///
+    /// * `image` - Image name
+    ///
+    /// # Returns
+    ///
+    /// The builder instance for method chaining
+    pub fn with_image(mut self, image: impl Into<String>) -> Self {
+        self.image = image.into();
+        self
+    }
+
+    /// Sets a custom VM configuration for the deployment.
+    ///
+    /// # Parameters
+    ///
+    /// * `vm_config` - The custom VM configuration to use
+    ///
+    /// # Returns
+    ///
+    /// The builder instance for method chaining
+    pub fn with_vm_config(mut self, vm_config: super::types::VmConfig) -> Self {
+        self.vm_config = Some(vm_config);
+        self
+    }
+
+    /// Disables TLS certificate verification (development only).
+    ///
+    /// See `DeploymentConfig::with_danger_accept_invalid_certs`.
+    ///
+    /// # Parameters
+    ///
+    /// * `accept` - Whether invalid certificates should be accepted
+    ///
+    /// # Returns
+    ///
+    /// The builder instance for method chaining
+    pub fn danger_accept_invalid_certs(mut self, accept: bool) -> Self {
+        self.danger_accept_invalid_certs = accept;
+        self
+    }
+
+    /// Enables gzip compression of JSON request bodies.
+    ///
+    /// # Parameters
+    ///
+    /// * `compress` - Whether request bodies should be gzip-compressed
+    ///
+    /// # Returns
+    ///
+    /// The builder instance for method chaining
+    pub fn with_compress_requests(mut self, compress: bool) -> Self {
+        self.compress_requests = compress;
+        self
+    }
+
+    /// Enables strict parsing of API responses.
+    ///
+    /// # Parameters
+    ///
+    /// * `strict` - Whether unknown response fields should be rejected
+    ///
+    /// # Returns
+    ///
+    /// The builder instance for method chaining
+    pub fn with_strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Sets the default timeout for API requests.
+    ///
+    /// # Parameters
+    ///
+    /// * `timeout` - Timeout applied to each request (default: 30 seconds)
+    ///
+    /// # Returns
+    ///
+    /// The builder instance for method chaining
+    pub fn with_request_timeout(mut self, timeout: Duration) -> Self {
+        self.request_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the timeout for establishing a connection to the API.
+    ///
+    /// # Parameters
+    ///
+    /// * `timeout` - Timeout for connecting (default: 5 seconds)
+    ///
+    /// # Returns
+    ///
+    /// The builder instance for method chaining
+    pub fn with_connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Builds the `DeploymentConfig`.
+    ///
+    /// # Returns
+    ///
+    /// The configuration, using the default API URL unless one was set
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the API key is not set or is empty
+    pub fn build(self) -> Result<DeploymentConfig, Error> {
+        let api_key = self
+            .api_key
+            .ok_or_else(|| Error::Configuration("API key is required".into()))?;
+        if api_key.trim().is_empty() {
+            return Err(Error::Configuration("API key is empty".into()));
+        }
+
+        Ok(DeploymentConfig {
+            // Only compose the default URL when a version was asked for
+            api_host: self.api_host.or_else(|| {
+                (self.api_url.is_none() && self.api_version.is_some())
+                    .then(|| DEFAULT_API_HOST.to_string())
+            }),
+            api_url: self.api_url.unwrap_or_else(|| DEFAULT_API_URL.to_string()),
+            api_version: self.api_version.unwrap_or_else(default_api_version),
+            api_key,
+            docker_compose: self.docker_compose,
+            env_vars: self.env_vars,
+            teepod_id: self.teepod_id,
+            image: self.image,
+            vm_config: self.vm_config,
+            danger_accept_invalid_certs: self.danger_accept_invalid_certs,
+            compress_requests: self.compress_requests,
+            strict: self.strict,
+            request_timeout: self.request_timeout.unwrap_or(DEFAULT_REQUEST_TIMEOUT),
+            connect_timeout: self.connect_timeout.unwrap_or(DEFAULT_CONNECT_TIMEOUT),
+        })
+    }
 }
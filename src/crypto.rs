@@ -1,11 +1,11 @@
 use crate::error::Error;
 use aes_gcm::{
-    aead::{Aead, KeyInit},
+    aead::{Aead, KeyInit, Payload},
     Aes256Gcm, Key, Nonce,
 };
 use rand::{rngs::OsRng, RngCore};
 use serde::{Deserialize, Serialize};
-use x25519_dalek::{EphemeralSecret, PublicKey};
+use x25519_dalek::{EphemeralSecret, PublicKey, StaticSecret};
 
 /// Cryptographic utilities for secure data transmission.
 ///
@@ -15,6 +15,29 @@ use x25519_dalek::{EphemeralSecret, PublicKey};
 /// compatibility with the Phala TEE Cloud platform.
 pub struct Encryptor;
 
+/// Encrypts environment variables for a deployment's public key.
+///
+/// A synchronous, client-free entry point for the user side of the
+/// operator/user workflow: the user receives the public key from the
+/// operator and returns the encrypted payload, without API credentials or an
+/// async runtime. Equivalent to [`Encryptor::encrypt_env_vars`].
+///
+/// # Parameters
+///
+/// * `env` - Key-value pairs of the environment variables to encrypt
+/// * `pubkey_hex` - The deployment's public key as a hex string (with or without '0x' prefix)
+///
+/// # Returns
+///
+/// The hex-encoded encrypted environment, ready to pass to the operator
+///
+/// # Errors
+///
+/// Returns the same errors as [`Encryptor::encrypt_env_vars`]
+pub fn encrypt_env_for_pubkey(env: &[(String, String)], pubkey_hex: &str) -> Result<String, Error> {
+    Encryptor::encrypt_env_vars(env, pubkey_hex)
+}
+
 #[derive(Serialize, Deserialize)]
 struct EnvVar {
     key: String,
@@ -29,7 +52,8 @@ impl Encryptor {
     /// 2. AES-GCM for authenticated encryption of the actual data
     ///
     /// The process is compatible with the TypeScript implementation used by
-    /// the Phala Cloud API.
+    /// the Phala Cloud API. It is a thin wrapper around [`Encryptor::encrypt_json`]
+    /// using the `{ "env": [{ "key": ..., "value": ... }] }` payload shape.
     ///
     /// # Parameters
     ///
@@ -49,14 +73,192 @@ impl Encryptor {
     pub fn encrypt_env_vars(
         env_vars: &[(String, String)],
         remote_pubkey_hex: &str,
+    ) -> Result<String, Error> {
+        Self::encrypt_env_vars_with_aad(env_vars, remote_pubkey_hex, None)
+    }
+
+    /// Encrypts environment variables like [`Encryptor::encrypt_env_vars`],
+    /// binding the ciphertext to a context through AES-GCM associated data.
+    ///
+    /// See [`Encryptor::encrypt_json_with_aad`] for how `aad` is used.
+    ///
+    /// # Parameters
+    ///
+    /// * `env_vars` - A slice of key-value pairs representing environment variables to encrypt
+    /// * `remote_pubkey_hex` - The remote public key as a hex string (with or without '0x' prefix)
+    /// * `aad` - Optional associated data, such as the app ID or salt
+    ///
+    /// # Returns
+    ///
+    /// A hex-encoded string containing the ephemeral public key, IV, and encrypted data
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as [`Encryptor::encrypt_env_vars`]
+    pub fn encrypt_env_vars_with_aad(
+        env_vars: &[(String, String)],
+        remote_pubkey_hex: &str,
+        aad: Option<&[u8]>,
+    ) -> Result<String, Error> {
+        Self::encrypt_json_with_aad(&Self::env_payload(env_vars), remote_pubkey_hex, aad)
+    }
+
+    /// Encrypts an arbitrary JSON value using X25519 key exchange and AES-GCM.
+    ///
+    /// The value is serialized to a compact JSON string and encrypted with the
+    /// same scheme as [`Encryptor::encrypt_env_vars`], so the output format is
+    /// identical: ephemeral public key, IV, then ciphertext, hex-encoded.
+    ///
+    /// # Parameters
+    ///
+    /// * `value` - The JSON value to encrypt
+    /// * `remote_pubkey_hex` - The remote public key as a hex string (with or without '0x' prefix)
+    ///
+    /// # Returns
+    ///
+    /// A hex-encoded string containing the ephemeral public key, IV, and encrypted data
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// * The public key is not valid hex or has incorrect length
+    /// * JSON serialization fails
+    /// * Encryption fails
+    pub fn encrypt_json(
+        value: &serde_json::Value,
+        remote_pubkey_hex: &str,
+    ) -> Result<String, Error> {
+        Self::encrypt_json_with_aad(value, remote_pubkey_hex, None)
+    }
+
+    /// Encrypts a JSON value like [`Encryptor::encrypt_json`], binding the
+    /// ciphertext to a context through AES-GCM associated data.
+    ///
+    /// The associated data is authenticated but not encrypted or included in
+    /// the output: decryption succeeds only with the same `aad`, so a payload
+    /// encrypted for one deployment cannot be replayed to another. `None` is
+    /// the same as empty associated data, which keeps the output compatible
+    /// with the TypeScript client; only pass `aad` if the recipient decrypts
+    /// with the same associated data.
+    ///
+    /// # Parameters
+    ///
+    /// * `value` - The JSON value to encrypt
+    /// * `remote_pubkey_hex` - The remote public key as a hex string (with or without '0x' prefix)
+    /// * `aad` - Optional associated data, such as the app ID or salt
+    ///
+    /// # Returns
+    ///
+    /// A hex-encoded string containing the ephemeral public key, IV, and encrypted data
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as [`Encryptor::encrypt_json`]
+    pub fn encrypt_json_with_aad(
+        value: &serde_json::Value,
+        remote_pubkey_hex: &str,
+        aad: Option<&[u8]>,
     ) -> Result<String, Error> {
         // Generate random values for ephemeral secret and IV
         let ephemeral_secret = EphemeralSecret::random_from_rng(OsRng);
         let mut iv = [0u8; 12];
         OsRng.fill_bytes(&mut iv);
 
+        let data = serde_json::to_string(value)
+            .map_err(|e| Error::Encryption(format!("JSON serialization error: {}", e)))?;
+
         // Use the internal implementation with these random values
-        Self::encrypt_env_vars_internal(env_vars, remote_pubkey_hex, ephemeral_secret, iv)
+        Self::encrypt_internal(
+            data.as_bytes(),
+            remote_pubkey_hex,
+            ephemeral_secret,
+            iv,
+            aad.unwrap_or_default(),
+        )
+    }
+
+    /// Decrypts a payload produced by [`Encryptor::encrypt_json`] or
+    /// [`Encryptor::encrypt_env_vars`].
+    ///
+    /// This is the inverse operation performed inside the TEE, which holds the
+    /// private key. It is mainly useful for testing and for tooling that
+    /// manages its own key pairs.
+    ///
+    /// # Parameters
+    ///
+    /// * `encrypted_hex` - The hex-encoded ephemeral public key, IV, and ciphertext
+    /// * `private_key_hex` - The recipient's X25519 private key as a hex string (with or without '0x' prefix)
+    ///
+    /// # Returns
+    ///
+    /// The decrypted JSON value
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// * The payload or key is not valid hex or has incorrect length
+    /// * Authentication of the ciphertext fails
+    /// * The plaintext is not valid JSON
+    pub fn decrypt_json(
+        encrypted_hex: &str,
+        private_key_hex: &str,
+    ) -> Result<serde_json::Value, Error> {
+        Self::decrypt_json_with_aad(encrypted_hex, private_key_hex, None)
+    }
+
+    /// Decrypts a payload produced with associated data by
+    /// [`Encryptor::encrypt_json_with_aad`] or [`Encryptor::encrypt_env_vars_with_aad`].
+    ///
+    /// # Parameters
+    ///
+    /// * `encrypted_hex` - The hex-encoded ephemeral public key, IV, and ciphertext
+    /// * `private_key_hex` - The recipient's X25519 private key as a hex string (with or without '0x' prefix)
+    /// * `aad` - The associated data used for encryption, or `None` if there was none
+    ///
+    /// # Returns
+    ///
+    /// The decrypted JSON value
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as [`Encryptor::decrypt_json`]; authentication
+    /// fails if `aad` differs from the one used for encryption
+    pub fn decrypt_json_with_aad(
+        encrypted_hex: &str,
+        private_key_hex: &str,
+        aad: Option<&[u8]>,
+    ) -> Result<serde_json::Value, Error> {
+        let private_key = Self::decode_key(private_key_hex)?;
+        let payload = hex::decode(encrypted_hex.trim_start_matches("0x"))
+            .map_err(|e| Error::Encryption(format!("Invalid hex encoding: {}", e)))?;
+
+        if payload.len() < 32 + 12 {
+            return Err(Error::Encryption(format!(
+                "Encrypted payload too short: {} bytes",
+                payload.len()
+            )));
+        }
+        let (ephemeral_pubkey, rest) = payload.split_at(32);
+        let (iv, ciphertext) = rest.split_at(12);
+
+        let mut ephemeral_bytes = [0u8; 32];
+        ephemeral_bytes.copy_from_slice(ephemeral_pubkey);
+        let shared_secret =
+            StaticSecret::from(private_key).diffie_hellman(&PublicKey::from(ephemeral_bytes));
+
+        let key = Key::<Aes256Gcm>::from_slice(shared_secret.as_bytes());
+        let plaintext = Aes256Gcm::new(key)
+            .decrypt(
+                Nonce::from_slice(iv),
+                Payload {
+                    msg: ciphertext,
+                    aad: aad.unwrap_or_default(),
+                },
+            )
+            .map_err(|e| Error::Encryption(format!("AES decryption error: {}", e)))?;
+
+        serde_json::from_slice(&plaintext)
+            .map_err(|e| Error::Encryption(format!("JSON deserialization error: {}", e)))
     }
 
     /// Specialized version that uses a fixed ephemeral public key and IV for compatibility testing
@@ -75,6 +277,12 @@ impl Encryptor {
     /// # Returns
     ///
     /// A hex-encoded string containing the provided ephemeral public key, IV, and encrypted data
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidKey` if the remote public key is malformed, the
+    /// ephemeral public key is a low-order point (e.g. all zeros), or the shared
+    /// secret is all zeros
     pub fn encrypt_env_vars_with_fixed_components(
         env_vars: &[(String, String)],
         remote_pubkey_hex: &str,
@@ -82,28 +290,27 @@ impl Encryptor {
         shared_secret_bytes: [u8; 32],
         iv: [u8; 12],
     ) -> Result<String, Error> {
-        // Decode remote public key (remove 0x prefix if present)
-        let clean_pubkey = remote_pubkey_hex.trim_start_matches("0x");
-        let remote_pubkey_bytes = hex::decode(clean_pubkey)
-            .map_err(|e| Error::InvalidKey(format!("Invalid hex encoding: {}", e)))?;
-
-        if remote_pubkey_bytes.len() != 32 {
-            return Err(Error::InvalidKey(format!(
-                "Invalid public key length: expected 32 bytes, got {}",
-                remote_pubkey_bytes.len()
-            )));
+        // Validate the remote public key even though the shared secret is provided
+        Self::decode_key(remote_pubkey_hex)?;
+
+        // Clamped scalars are multiples of the cofactor, so a key exchange with a
+        // low-order point (such as all zeros) always yields the all-zero secret
+        let probe = StaticSecret::from([1u8; 32]);
+        if !probe
+            .diffie_hellman(&PublicKey::from(ephemeral_pubkey_bytes))
+            .was_contributory()
+        {
+            return Err(Error::InvalidKey(
+                "Ephemeral public key is not a valid X25519 point".to_string(),
+            ));
+        }
+        if shared_secret_bytes == [0u8; 32] {
+            return Err(Error::InvalidKey(
+                "Shared secret must not be all zeros".to_string(),
+            ));
         }
 
-        // Convert environment variables to match JS structure exactly
-        let env_vars_formatted: Vec<EnvVar> = env_vars
-            .iter()
-            .map(|(k, v)| EnvVar {
-                key: k.clone(),
-                value: v.clone(),
-            })
-            .collect();
-        let env_json = serde_json::json!({ "env": env_vars_formatted });
-        let env_data = serde_json::to_string(&env_json)
+        let env_data = serde_json::to_string(&Self::env_payload(env_vars))
             .map_err(|e| Error::Encryption(format!("JSON serialization error: {}", e)))?;
 
         // Use the provided IV
@@ -128,47 +335,66 @@ impl Encryptor {
         Ok(hex::encode(result))
     }
 
+    /// Builds the `{ "env": [...] }` payload expected by the Phala Cloud API.
+    fn env_payload(env_vars: &[(String, String)]) -> serde_json::Value {
+        // Convert environment variables to match JS structure exactly
+        let env_vars_formatted: Vec<EnvVar> = env_vars
+            .iter()
+            .map(|(k, v)| EnvVar {
+                key: k.clone(),
+                value: v.clone(),
+            })
+            .collect();
+        serde_json::json!({ "env": env_vars_formatted })
+    }
+
+    /// Decodes a hex public key, such as `app_env_encrypt_pubkey`, into its
+    /// raw 32 bytes for use with other X25519 implementations.
+    ///
+    /// # Parameters
+    ///
+    /// * `pubkey_hex` - The public key as a hex string (with or without '0x' prefix)
+    ///
+    /// # Returns
+    ///
+    /// The raw 32-byte key
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidKey` if the input is not valid hex or not 32 bytes long
+    pub fn decode_pubkey(pubkey_hex: &str) -> Result<[u8; 32], Error> {
+        Self::decode_key(pubkey_hex)
+    }
+
+    /// Decodes a 32-byte X25519 key from hex (remove 0x prefix if present).
+    fn decode_key(key_hex: &str) -> Result<[u8; 32], Error> {
+        let key_bytes = hex::decode(key_hex.trim_start_matches("0x"))
+            .map_err(|e| Error::InvalidKey(format!("Invalid hex encoding: {}", e)))?;
+
+        key_bytes.as_slice().try_into().map_err(|_| {
+            Error::InvalidKey(format!(
+                "Invalid key length: expected 32 bytes, got {}",
+                key_bytes.len()
+            ))
+        })
+    }
+
     /// Internal implementation of the encryption logic, used by both the public method
     /// and the test method that requires fixed values.
-    fn encrypt_env_vars_internal(
-        env_vars: &[(String, String)],
+    fn encrypt_internal(
+        plaintext: &[u8],
         remote_pubkey_hex: &str,
         ephemeral_secret: EphemeralSecret,
         iv: [u8; 12],
+        aad: &[u8],
     ) -> Result<String, Error> {
-        // Decode remote public key (remove 0x prefix if present)
-        let clean_pubkey = remote_pubkey_hex.trim_start_matches("0x");
-        let remote_pubkey_bytes = hex::decode(clean_pubkey)
-            .map_err(|e| Error::InvalidKey(format!("Invalid hex encoding: {}", e)))?;
-
-        if remote_pubkey_bytes.len() != 32 {
-            return Err(Error::InvalidKey(format!(
-                "Invalid public key length: expected 32 bytes, got {}",
-                remote_pubkey_bytes.len()
-            )));
-        }
-
         // Convert to PublicKey
-        let mut key_bytes = [0u8; 32];
-        key_bytes.copy_from_slice(&remote_pubkey_bytes);
-        let remote_pubkey = PublicKey::from(key_bytes);
+        let remote_pubkey = PublicKey::from(Self::decode_key(remote_pubkey_hex)?);
 
         // Get public key and shared secret from ephemeral secret
         let public_key = PublicKey::from(&ephemeral_secret);
         let shared_secret = ephemeral_secret.diffie_hellman(&remote_pubkey);
 
-        // Convert environment variables to JSON.
-        let env_vars_formatted: Vec<EnvVar> = env_vars
-            .iter()
-            .map(|(k, v)| EnvVar {
-                key: k.clone(),
-                value: v.clone(),
-            })
-            .collect();
-        let env_json = serde_json::json!({ "env": env_vars_formatted });
-        let env_data = serde_json::to_string(&env_json)
-            .map_err(|e| Error::Encryption(format!("JSON serialization error: {}", e)))?;
-
         // Use the provided IV
         let nonce = Nonce::from_slice(&iv);
 
@@ -178,7 +404,13 @@ impl Encryptor {
 
         // Encrypt the data
         let encrypted = cipher
-            .encrypt(nonce, env_data.as_bytes())
+            .encrypt(
+                nonce,
+                Payload {
+                    msg: plaintext,
+                    aad,
+                },
+            )
             .map_err(|e| Error::Encryption(format!("AES encryption error: {}", e)))?;
 
         // Combine components as in TypeScript: public key + IV + encrypted data
@@ -238,13 +470,28 @@ mod tests {
         assert!(encrypted.len() > 32 + 12); // public key + IV + some encrypted data
     }
 
+    #[test]
+    fn test_encrypt_env_for_pubkey_round_trip() {
+        let secret = StaticSecret::from([3u8; 32]);
+        let pubkey_hex = format!("0x{}", hex::encode(PublicKey::from(&secret).as_bytes()));
+        let env = [("API_KEY".to_string(), "secret".to_string())];
+
+        let encrypted = encrypt_env_for_pubkey(&env, &pubkey_hex).unwrap();
+        let decrypted =
+            Encryptor::decrypt_json(&encrypted, &hex::encode(secret.to_bytes())).unwrap();
+        assert_eq!(
+            decrypted,
+            serde_json::json!({ "env": [{ "key": "API_KEY", "value": "secret" }] })
+        );
+    }
+
     #[test]
     fn test_fixed_components_encryption() {
         // These variables are not directly used in the test but kept for documentation
         // and to show what would be used in a real scenario
         let _remote_pubkey = "3fffa0dbcda49049ad2418f45972c164f076d32ea5ed1e3632dea5d366e39926";
 
-        let _env_vars = vec![("FOO".to_string(), "BAR".to_string())];
+        let _env_vars = [("FOO".to_string(), "BAR".to_string())];
 
         // These values have been extracted from the expected output
         let expected_output = "db3295ac44a01fec9d154f760e02fa8f7e64475c54ea3f08a6f19f269ac6df24828b72b8884d12ce128840e489c6ef3c491785b732da9423312be14e63bf114f232f869f1f4a4a21721c7b7c4af26373b7e06d4cb49e3a30cb497a37006a0ee171";
@@ -281,4 +528,131 @@ mod tests {
 
         assert_eq!(compatible_output, expected_output);
     }
+
+    #[test]
+    fn test_encrypt_json_round_trip() {
+        let private_key = StaticSecret::random_from_rng(OsRng);
+        let public_key = PublicKey::from(&private_key);
+
+        let value = serde_json::json!({
+            "database": {
+                "host": "db.internal",
+                "ports": [5432, 5433],
+                "tls": { "enabled": true, "ca": null }
+            },
+            "feature_flags": ["a", "b"]
+        });
+
+        let encrypted =
+            Encryptor::encrypt_json(&value, &format!("0x{}", hex::encode(public_key.as_bytes())))
+                .unwrap();
+        let decrypted =
+            Encryptor::decrypt_json(&encrypted, &hex::encode(private_key.to_bytes())).unwrap();
+
+        assert_eq!(decrypted, value);
+    }
+
+    #[test]
+    fn test_encrypt_env_vars_uses_env_shape() {
+        let private_key = StaticSecret::random_from_rng(OsRng);
+        let public_key = hex::encode(PublicKey::from(&private_key).as_bytes());
+
+        let env_vars = [("FOO".to_string(), "BAR".to_string())];
+        let encrypted = Encryptor::encrypt_env_vars(&env_vars, &public_key).unwrap();
+        let decrypted =
+            Encryptor::decrypt_json(&encrypted, &hex::encode(private_key.to_bytes())).unwrap();
+
+        assert_eq!(
+            decrypted,
+            serde_json::json!({ "env": [{ "key": "FOO", "value": "BAR" }] })
+        );
+    }
+
+    #[test]
+    fn test_encrypt_json_with_aad() {
+        let private_key = StaticSecret::random_from_rng(OsRng);
+        let public_key = hex::encode(PublicKey::from(&private_key).as_bytes());
+        let private_key = hex::encode(private_key.to_bytes());
+        let value = serde_json::json!({ "env": [{ "key": "FOO", "value": "BAR" }] });
+
+        let bound =
+            Encryptor::encrypt_json_with_aad(&value, &public_key, Some(b"app_1:salt")).unwrap();
+        assert_eq!(
+            Encryptor::decrypt_json_with_aad(&bound, &private_key, Some(b"app_1:salt")).unwrap(),
+            value
+        );
+        assert!(matches!(
+            Encryptor::decrypt_json_with_aad(&bound, &private_key, Some(b"app_2:salt")),
+            Err(Error::Encryption(_))
+        ));
+        assert!(matches!(
+            Encryptor::decrypt_json(&bound, &private_key),
+            Err(Error::Encryption(_))
+        ));
+
+        // No AAD is the same as empty AAD, matching the TypeScript scheme
+        let unbound = Encryptor::encrypt_json_with_aad(&value, &public_key, None).unwrap();
+        assert_eq!(
+            Encryptor::decrypt_json_with_aad(&unbound, &private_key, Some(b"")).unwrap(),
+            value
+        );
+        assert_eq!(
+            Encryptor::decrypt_json(&unbound, &private_key).unwrap(),
+            value
+        );
+    }
+
+    #[test]
+    fn test_decode_pubkey() {
+        let key = [0xabu8; 32];
+        assert_eq!(
+            Encryptor::decode_pubkey(&format!("0x{}", hex::encode(key))).unwrap(),
+            key
+        );
+        assert_eq!(Encryptor::decode_pubkey(&hex::encode(key)).unwrap(), key);
+
+        for invalid in [
+            hex::encode([1u8; 31]),
+            hex::encode([1u8; 33]),
+            "0xzz".into(),
+        ] {
+            assert!(matches!(
+                Encryptor::decode_pubkey(&invalid),
+                Err(Error::InvalidKey(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_fixed_components_reject_invalid_keys() {
+        let remote_pubkey = hex::encode([1u8; 32]);
+        let env_vars = [("FOO".to_string(), "BAR".to_string())];
+
+        let zero_ephemeral = Encryptor::encrypt_env_vars_with_fixed_components(
+            &env_vars,
+            &remote_pubkey,
+            [0u8; 32],
+            [4u8; 32],
+            [5u8; 12],
+        );
+        assert!(matches!(zero_ephemeral, Err(Error::InvalidKey(_))));
+
+        let zero_secret = Encryptor::encrypt_env_vars_with_fixed_components(
+            &env_vars,
+            &remote_pubkey,
+            [3u8; 32],
+            [0u8; 32],
+            [5u8; 12],
+        );
+        assert!(matches!(zero_secret, Err(Error::InvalidKey(_))));
+
+        assert!(Encryptor::encrypt_env_vars_with_fixed_components(
+            &env_vars,
+            &remote_pubkey,
+            [3u8; 32],
+            [4u8; 32],
+            [5u8; 12],
+        )
+        .is_ok());
+    }
 }
@@ -1,12 +1,45 @@
+use crate::compose::{
+    environment_keys, expand_yaml_anchors, interpolated_variables, strip_compose_version,
+    validate_compose,
+};
+use crate::config::{
+    DEFAULT_API_URL, DEFAULT_API_VERSION, DEFAULT_CONNECT_TIMEOUT, DEFAULT_REQUEST_TIMEOUT,
+};
+use crate::types::check_kernel_cmdline_append;
 use crate::{
-    AdvancedFeatures, AttestationResponse, ComposeManifest, CvmInfo, CvmStateResponse,
-    DeploymentConfig, DeploymentResponse, DockerConfig, Error, NetworkInfoResponse, PubkeyResponse,
-    Result, SystemStatsResponse, TeeClient, TeePodDiscoveryResponse, VmConfig,
+    AdvancedFeatures, AppIdSalt, AppPublicKey, AttestationResponse, Clock, ClusterUtilization,
+    ComposeManifest, CostEstimate, CvmInfo, CvmStateResponse, DeploymentConfig, DeploymentPlan,
+    DeploymentResponse, DeploymentStatus, DockerConfig, Error, LogLine, LogsResponse,
+    NetworkInfoResponse, PollStrategy, PriceTable, PubkeyResponse, RequestOptions, Result, Runner,
+    Secret, SettingsUpdate, SystemStatsResponse, TeeClient, TeePodCapacity,
+    TeePodDiscoveryResponse, TeePodImage, TeePodNode, TokioClock, UpdateResponse, UploadProgress,
+    VmConfig,
 };
-use std::time::Duration;
+use futures_util::stream::{self, Stream};
 use serde_json::{json, Value};
-use std::collections::HashMap;
-use std::path::Path;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Hours in an average month, used for monthly cost estimates.
+const HOURS_PER_MONTH: f64 = 730.0;
+
+/// Delay between log polls when no new lines arrived or a poll failed.
+const LOG_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Delay between status polls when no poll strategy is configured.
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// How long `rotate_secrets` waits for the deployment to become healthy again
+/// when no operation timeout is configured.
+const SECRET_ROTATION_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Number of trailing log lines included in a `watch_until_terminal` timeout error.
+const WATCH_TIMEOUT_LOG_TAIL: usize = 20;
+
+/// Longest VM name produced by a name template.
+const MAX_VM_NAME_LEN: usize = 63;
 
 /// `TeeDeployer` provides a high-level interface for deploying Docker Compose applications
 /// to the Phala TEE Cloud platform.
@@ -61,7 +94,56 @@ use std::path::Path;
 /// ```
 pub struct TeeDeployer {
     client: TeeClient,
+    discovery: Mutex<Discovery>,
+    prefer_production: bool,
+    min_resource_score: f64,
+    name_template: Option<String>,
+    listed: bool,
+    labels: HashMap<String, String>,
+    runner: Runner,
+    kernel_cmdline_append: Option<String>,
+    vm_extra: serde_json::Map<String, Value>,
+    discovery_cache_ttl: Option<Duration>,
+    price_table: Option<PriceTable>,
+    user_id: Option<String>,
+    clock: Arc<dyn Clock>,
+    strip_compose_version: bool,
+    normalize_line_endings: bool,
+    expand_anchors: bool,
+    strict_env_check: bool,
+    operation_timeout: Option<Duration>,
+    poll_strategy: PollStrategy,
+    check_image: bool,
+    auto_discover: bool,
+    deploy_guard: bool,
+    in_flight: Mutex<HashSet<String>>,
+    deploy_retries: u32,
+}
+
+/// TEEPod selection and discovery results.
+///
+/// Kept behind a lock so that deploys, which take `&self`, can select a
+/// TEEPod themselves when auto-discovery is enabled.
+#[derive(Default)]
+struct Discovery {
     selected_teepod: Option<(u64, String)>,
+    selected_limits: Option<(u32, u32, u32)>,
+    selected_images: Vec<String>,
+    discovered_images: Vec<TeePodImage>,
+    cache: Option<(Instant, TeePodDiscoveryResponse)>,
+    tier: Option<String>,
+}
+
+/// Marks an app name as being deployed; releases it when dropped.
+struct InFlightDeploy<'a> {
+    in_flight: &'a Mutex<HashSet<String>>,
+    name: String,
+}
+
+impl Drop for InFlightDeploy<'_> {
+    fn drop(&mut self) {
+        self.in_flight.lock().unwrap().remove(&self.name);
+    }
 }
 
 impl TeeDeployer {
@@ -80,21 +162,76 @@ impl TeeDeployer {
     ///
     /// Returns an error if the underlying HTTP client cannot be created
     pub fn new(api_key: String, api_endpoint: Option<String>) -> Result<Self> {
-        let config = DeploymentConfig {
+        Self::with_config(Self::base_config(api_key, api_endpoint))
+    }
+
+    /// Creates a `TeeDeployer` from a fully populated client configuration.
+    fn with_config(config: DeploymentConfig) -> Result<Self> {
+        Ok(Self {
+            client: TeeClient::new(config)?,
+            discovery: Mutex::new(Discovery::default()),
+            prefer_production: true,
+            listed: false,
+            labels: HashMap::new(),
+            runner: Runner::default(),
+            kernel_cmdline_append: None,
+            vm_extra: serde_json::Map::new(),
+            discovery_cache_ttl: None,
+            price_table: None,
+            user_id: None,
+            clock: Arc::new(TokioClock),
+            strip_compose_version: true,
+            normalize_line_endings: true,
+            expand_anchors: false,
+            strict_env_check: false,
+            operation_timeout: None,
+            poll_strategy: PollStrategy::Fixed(DEFAULT_POLL_INTERVAL),
+            min_resource_score: 0.0,
+            name_template: None,
+            check_image: true,
+            auto_discover: false,
+            deploy_guard: false,
+            in_flight: Mutex::new(HashSet::new()),
+            deploy_retries: 0,
+        })
+    }
+
+    /// Builds the client configuration shared by all deployer constructors.
+    fn base_config(api_key: String, api_endpoint: Option<String>) -> DeploymentConfig {
+        DeploymentConfig {
             api_key,
-            api_url: api_endpoint
-                .unwrap_or_else(|| "https://cloud-api.phala.network/api/v1".to_string()),
+            api_host: None,
+            api_version: DEFAULT_API_VERSION.to_string(),
+            api_url: api_endpoint.unwrap_or_else(|| DEFAULT_API_URL.to_string()),
             docker_compose: String::new(),
             env_vars: HashMap::new(),
             teepod_id: 0,
             image: String::new(),
             vm_config: None,
-        };
+            danger_accept_invalid_certs: false,
+            compress_requests: false,
+            strict: false,
+            request_timeout: DEFAULT_REQUEST_TIMEOUT,
+            connect_timeout: DEFAULT_CONNECT_TIMEOUT,
+        }
+    }
 
-        Ok(Self {
-            client: TeeClient::new(config)?,
-            selected_teepod: None,
-        })
+    /// Verifies that the configured API key and endpoint are usable.
+    ///
+    /// The check uses the configured request timeout.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` if the API accepted the credentials
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::Api` with status 401 ("API key was rejected") or
+    /// 403 ("API key lacks permission") if the API key is not accepted,
+    /// `Error::Timeout` if the API does not answer within the request timeout,
+    /// or another error if the endpoint cannot be reached
+    pub async fn verify_credentials(&self) -> Result<()> {
+        self.client.ping().await
     }
 
     /// Discovers and selects the first available TEEPod automatically.
@@ -103,6 +240,9 @@ impl TeeDeployer {
     /// the first one from the response. It's a convenient way to get started without
     /// needing to choose a specific TEEPod.
     ///
+    /// When `prefer_production` is enabled (the default), development images
+    /// (`is_dev: true`) are skipped as long as any node offers a production image.
+    ///
     /// # Returns
     ///
     /// `Ok(())` if a TEEPod was successfully discovered and selected
@@ -112,24 +252,356 @@ impl TeeDeployer {
     /// Returns an error if:
     /// * The API request fails
     /// * No TEEPods are available
+    /// * No TEEPod meets the minimum resource score set with
+    ///   `TeeDeployerBuilder::with_min_resource_score`
+    /// * No available TEEPod offers any image
     /// * The API response has an unexpected format
     pub async fn discover_teepod(&mut self) -> Result<TeePodDiscoveryResponse> {
-        let teepods = self.client.get_available_teepods().await?;
+        self.discover_and_select().await
+    }
+
+    /// Selects a TEEPod the way `discover_teepod()` documents; shared with
+    /// auto-discovery, which only has `&self`.
+    async fn discover_and_select(&self) -> Result<TeePodDiscoveryResponse> {
+        let teepods = self.available_teepods().await?;
 
         let nodes = &teepods.nodes;
         if nodes.is_empty() {
-            return Err(Error::Api {
-                status_code: 400,
-                message: "No available TEEPods found".into(),
-            });
+            return Err(Error::NoTeepodAvailable(
+                "No available TEEPods found".into(),
+            ));
+        }
+
+        let qualifying = nodes
+            .iter()
+            .filter(|node| node.resource_score >= self.min_resource_score);
+        if qualifying.clone().next().is_none() {
+            let best = nodes
+                .iter()
+                .map(|node| node.resource_score)
+                .fold(f64::NEG_INFINITY, f64::max);
+            return Err(Error::NoTeepodAvailable(format!(
+                "No TEEPod meets the minimum resource score of {} (best available: {})",
+                self.min_resource_score, best
+            )));
+        }
+
+        let (node, image) = self.choose_node(qualifying).ok_or_else(|| {
+            Error::NoTeepodAvailable("No available TEEPod offers a usable image".into())
+        })?;
+
+        let discovered_images = nodes
+            .iter()
+            .flat_map(|node| node.images.iter().cloned())
+            .collect();
+        self.record_selection(node, image, &teepods.capacity, discovered_images);
+        Ok(teepods)
+    }
+
+    /// Discovers and selects a TEEPod located in the given region.
+    ///
+    /// Regions are compared case-insensitively against the `region` (or
+    /// `location`) reported for each node. Image selection follows the same
+    /// rules as `discover_teepod()`.
+    ///
+    /// # Parameters
+    ///
+    /// * `region` - The region the deployment must stay in (e.g. "eu-west")
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` if a TEEPod in the region was found and selected
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// * The API request fails
+    /// * No node with a usable image is located in `region`; the message lists
+    ///   the regions that are available
+    pub async fn select_teepod_in_region(&mut self, region: &str) -> Result<()> {
+        let teepods = self.available_teepods().await?;
+        let in_region = teepods.nodes.iter().filter(|node| {
+            node.region
+                .as_deref()
+                .is_some_and(|r| r.eq_ignore_ascii_case(region))
+        });
+
+        let Some((node, image)) = self.choose_node(in_region) else {
+            let mut regions: Vec<&str> = teepods
+                .nodes
+                .iter()
+                .filter(|node| !node.images.is_empty())
+                .filter_map(|node| node.region.as_deref())
+                .collect();
+            regions.sort_unstable();
+            regions.dedup();
+            return Err(Error::NoTeepodAvailable(format!(
+                "No TEEPod available in region '{}'. Available regions: {}",
+                region,
+                if regions.is_empty() {
+                    "none reported".to_string()
+                } else {
+                    regions.join(", ")
+                }
+            )));
+        };
+
+        self.record_selection(node, image, &teepods.capacity, node.images.clone());
+        Ok(())
+    }
+
+    /// Remembers `image` on `node` as the deployment target, together with
+    /// the node's deployable limits and images and the images seen during
+    /// discovery.
+    fn record_selection(
+        &self,
+        node: &TeePodNode,
+        image: &TeePodImage,
+        capacity: &TeePodCapacity,
+        discovered_images: Vec<TeePodImage>,
+    ) {
+        let mut discovery = self.discovery.lock().unwrap();
+        discovery.selected_teepod = Some((node.teepod_id, image.name.clone()));
+        discovery.selected_limits = Some(Self::deployable_limits(node, capacity));
+        discovery.selected_images = Self::image_names(node);
+        discovery.discovered_images = discovered_images;
+    }
+
+    /// Picks the first node offering an acceptable image, honouring
+    /// `prefer_production` across all nodes before falling back to any image.
+    /// Nodes without any image cannot host a deployment and are skipped.
+    fn choose_node<'a>(
+        &self,
+        nodes: impl Iterator<Item = &'a TeePodNode> + Clone,
+    ) -> Option<(&'a TeePodNode, &'a TeePodImage)> {
+        nodes
+            .clone()
+            .find_map(|node| {
+                self.preferred_image(node)
+                    .filter(|image| !self.prefer_production || !image.is_dev)
+                    .map(|image| (node, image))
+            })
+            .or_else(|| {
+                nodes
+                    .into_iter()
+                    .find_map(|node| node.images.first().map(|image| (node, image)))
+            })
+    }
+
+    /// Claims `name` for the duration of a deploy when the in-flight guard is
+    /// enabled; the claim is released when the returned value is dropped.
+    fn claim_deploy(&self, name: &str) -> Result<Option<InFlightDeploy<'_>>> {
+        if !self.deploy_guard {
+            return Ok(None);
+        }
+        if !self.in_flight.lock().unwrap().insert(name.to_string()) {
+            return Err(Error::Configuration(format!(
+                "deployment for '{}' already in progress",
+                name
+            )));
+        }
+        Ok(Some(InFlightDeploy {
+            in_flight: &self.in_flight,
+            name: name.to_string(),
+        }))
+    }
+
+    /// Discovers and remembers a TEEPod when auto-discovery is enabled and
+    /// none has been selected yet.
+    async fn ensure_teepod(&self) -> Result<()> {
+        if !self.auto_discover || self.selected_teepod_and_image().is_some() {
+            return Ok(());
+        }
+        self.discover_and_select().await.map(|_| ())
+    }
+
+    /// Returns the selected TEEPod and image, whether chosen explicitly or
+    /// by auto-discovery.
+    fn selected_teepod_and_image(&self) -> Option<(u64, String)> {
+        self.discovery.lock().unwrap().selected_teepod.clone()
+    }
+
+    /// Returns the selected TEEPod and image, or `Error::NoTeepodSelected`.
+    fn ensure_teepod_selected(&self) -> Result<(u64, String)> {
+        self.selected_teepod_and_image()
+            .ok_or(Error::NoTeepodSelected)
+    }
+
+    /// Names of the images a node offers.
+    fn image_names(node: &TeePodNode) -> Vec<String> {
+        node.images.iter().map(|image| image.name.clone()).collect()
+    }
+
+    /// Rejects a VM configuration for the selected TEEPod whose image that
+    /// node does not offer. Configurations for other TEEPods are not checked,
+    /// since their images are unknown.
+    fn check_image_offered(&self, vm_config: &VmConfig) -> Result<()> {
+        let discovery = self.discovery.lock().unwrap();
+        let selected = discovery.selected_teepod.as_ref().map(|(id, _)| *id);
+        if !self.check_image
+            || selected != Some(vm_config.teepod_id)
+            || discovery.selected_images.contains(&vm_config.image)
+        {
+            return Ok(());
+        }
+        Err(Error::Configuration(format!(
+            "Image '{}' is not offered by TEEPod {}. Available images: {}",
+            vm_config.image,
+            vm_config.teepod_id,
+            discovery.selected_images.join(", ")
+        )))
+    }
+
+    /// Floors a node's remaining vCPU and memory, capped by the cluster
+    /// maxima, and pairs them with the cluster's maximum disk size.
+    fn deployable_limits(node: &TeePodNode, capacity: &TeePodCapacity) -> (u32, u32, u32) {
+        let cap = |value: u64| value.min(u32::MAX as u64) as u32;
+        // Float-to-int casts saturate, so negative remainders become 0
+        (
+            (node.remaining_vcpu.floor() as u32).min(cap(capacity.max_vcpu)),
+            (node.remaining_memory.floor() as u32).min(cap(capacity.max_memory)),
+            cap(capacity.max_disk),
+        )
+    }
+
+    /// Fetches the available TEEPods, ignoring and replacing any cached response.
+    ///
+    /// Use this to force-invalidate the discovery cache configured with
+    /// `TeeDeployerBuilder::with_discovery_cache_ttl`.
+    ///
+    /// # Returns
+    ///
+    /// The freshly fetched discovery response
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the API request fails
+    pub async fn refresh_teepods(&mut self) -> Result<TeePodDiscoveryResponse> {
+        self.discovery.get_mut().unwrap().cache = None;
+        self.available_teepods().await
+    }
+
+    /// Returns the cached discovery response while it is within the TTL,
+    /// fetching and caching a new one otherwise.
+    async fn available_teepods(&self) -> Result<TeePodDiscoveryResponse> {
+        if let Some(ttl) = self.discovery_cache_ttl {
+            let discovery = self.discovery.lock().unwrap();
+            if let Some((fetched_at, teepods)) = &discovery.cache {
+                if self.clock.now().duration_since(*fetched_at) < ttl {
+                    return Ok(teepods.clone());
+                }
+            }
         }
 
-        let node = &nodes[0];
-        let image = node.images[0].name.clone();
-        self.selected_teepod = Some((node.teepod_id, image));
+        let teepods = self.client.get_available_teepods().await?;
+        let mut discovery = self.discovery.lock().unwrap();
+        discovery.tier = Some(teepods.tier.clone());
+        if self.discovery_cache_ttl.is_some() {
+            discovery.cache = Some((self.clock.now(), teepods.clone()));
+        }
         Ok(teepods)
     }
 
+    /// Returns the largest `(vcpu, memory_mb, disk_gb)` the selected TEEPod can
+    /// currently host.
+    ///
+    /// vCPU and memory are the node's remaining capacity, rounded down and
+    /// capped by the cluster maxima; disk is the cluster's `max_disk`. Values
+    /// come from the discovery response used to select the TEEPod, so they can
+    /// be stale. Cap requested resources with these to avoid a failed deploy.
+    ///
+    /// # Returns
+    ///
+    /// The maxima, or `None` if no TEEPod has been selected through discovery
+    pub fn max_deployable_resources(&self) -> Option<(u32, u32, u32)> {
+        self.discovery.lock().unwrap().selected_limits
+    }
+
+    /// Estimates the cost of running a VM with the given resources.
+    ///
+    /// Prices come from the table configured with
+    /// `TeeDeployerBuilder::with_price_table`, looked up by the service tier
+    /// reported during the last TEEPod discovery. Estimates are in the price
+    /// table's currency; a month is counted as 730 hours.
+    ///
+    /// # Parameters
+    ///
+    /// * `vcpu` - Number of virtual CPU cores
+    /// * `memory_mb` - Memory in MB
+    /// * `disk_gb` - Disk size in GB
+    ///
+    /// # Returns
+    ///
+    /// The hourly and monthly cost estimate
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// * No price table has been configured
+    /// * No TEEPod discovery has been performed yet, so the tier is unknown
+    /// * The price table has no prices for the tier
+    pub fn estimate_cost(&self, vcpu: u32, memory_mb: u32, disk_gb: u32) -> Result<CostEstimate> {
+        let price_table = self.price_table.as_ref().ok_or_else(|| {
+            Error::Configuration(
+                "No price table configured; set one with TeeDeployerBuilder::with_price_table"
+                    .into(),
+            )
+        })?;
+        let tier = self.discovery.lock().unwrap().tier.clone();
+        let tier = tier.ok_or_else(|| {
+            Error::Configuration(
+                "Service tier unknown. Call discover_teepod() or select_teepod() first".into(),
+            )
+        })?;
+        let prices = price_table.prices(&tier).ok_or_else(|| {
+            Error::Configuration(format!("Price table has no prices for tier '{}'", tier))
+        })?;
+
+        let hourly = f64::from(vcpu) * prices.vcpu_hour
+            + f64::from(memory_mb) / 1024.0 * prices.memory_gb_hour
+            + f64::from(disk_gb) * prices.disk_gb_hour;
+        Ok(CostEstimate {
+            tier,
+            hourly,
+            monthly: hourly * HOURS_PER_MONTH,
+        })
+    }
+
+    /// Fetches the available TEEPods and aggregates their resources.
+    ///
+    /// Covers every node the API reports, regardless of
+    /// `with_min_resource_score`, giving a fleet-wide view for capacity
+    /// planning before deploying.
+    ///
+    /// # Returns
+    ///
+    /// The aggregated remaining resources and average resource score
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the API request fails
+    pub async fn cluster_utilization(&self) -> Result<ClusterUtilization> {
+        Ok(self.client.get_available_teepods().await?.utilization())
+    }
+
+    /// Returns the VM images offered by the nodes seen during the last discovery.
+    ///
+    /// The list is empty until `discover_teepod()` or `select_teepod()` has
+    /// completed successfully.
+    pub fn list_images(&self) -> Vec<TeePodImage> {
+        self.discovery.lock().unwrap().discovered_images.clone()
+    }
+
+    /// Picks the image to deploy on a node, honouring `prefer_production`.
+    fn preferred_image<'a>(&self, node: &'a TeePodNode) -> Option<&'a TeePodImage> {
+        if self.prefer_production {
+            if let Some(image) = node.images.iter().find(|image| !image.is_dev) {
+                return Some(image);
+            }
+        }
+        node.images.first()
+    }
+
     /// Selects a specific TEEPod by ID and verifies its availability.
     ///
     /// This method allows you to choose a particular TEEPod for deployment instead
@@ -148,22 +620,28 @@ impl TeeDeployer {
     /// Returns an error if:
     /// * The API request fails
     /// * The specified TEEPod is not found or not available
+    /// * The specified TEEPod offers no image
     /// * The API response has an unexpected format
     pub async fn select_teepod(&mut self, teepod_id: u64) -> Result<()> {
-        let teepods = self.client.get_available_teepods().await?;
+        let teepods = self.available_teepods().await?;
 
         for node in &teepods.nodes {
             if node.teepod_id == teepod_id {
-                let image = node.images[0].name.clone();
-                self.selected_teepod = Some((teepod_id, image));
+                let image = self.preferred_image(node).ok_or_else(|| {
+                    Error::NoTeepodAvailable(format!(
+                        "TEEPod with ID {} has no usable image",
+                        teepod_id
+                    ))
+                })?;
+                self.record_selection(node, image, &teepods.capacity, node.images.clone());
                 return Ok(());
             }
         }
 
-        Err(Error::Api {
-            status_code: 404,
-            message: format!("TEEPod with ID {} not found or not available", teepod_id),
-        })
+        Err(Error::NoTeepodAvailable(format!(
+            "TEEPod with ID {} not found or not available",
+            teepod_id
+        )))
     }
 
     /// Deploys a Docker Compose application using a YAML string configuration.
@@ -175,7 +653,9 @@ impl TeeDeployer {
     ///
     /// * `docker_compose_file` - The Docker Compose configuration as a YAML string
     /// * `app_name` - Name for the deployed application
-    /// * `env_vars` - Environment variables for the application (will be securely encrypted)
+    /// * `env_vars` - Environment variables for the application (will be securely encrypted).
+    ///   Accepts any iterable of key/value pairs: a `HashMap`, a `Vec` of tuples,
+    ///   or an array such as `[("PORT", "3000")]`
     /// * `vcpu` - Optional vCPU cores for the VM (defaults to 1)
     /// * `memory` - Optional memory in MB for the VM (defaults to 1024)
     /// * `disk_size` - Optional disk size in GB for the VM (defaults to 10)
@@ -187,166 +667,498 @@ impl TeeDeployer {
     /// # Errors
     ///
     /// Returns an error if:
-    /// * No TEEPod has been selected
+    /// * No TEEPod has been selected (`Error::NoTeepodSelected`)
+    /// * The compose file fails validation: two services publish the same host
+    ///   port, a service joins an undeclared network or uses a `build:` section
+    /// * The deploy guard is enabled and `app_name` is already being deployed
     /// * The API request fails
     /// * Environment variable encryption fails
     pub async fn deploy_compose(
         &self,
         docker_compose_file: &str,
         app_name: &str,
-        env_vars: HashMap<String, String>,
+        env_vars: impl IntoIterator<Item = (impl Into<String>, impl Into<String>)>,
         vcpu: Option<u64>,
         memory: Option<u64>,
         disk_size: Option<u64>,
     ) -> Result<DeploymentResponse> {
-        // Ensure we have a selected TEEPod
-        let (teepod_id, image) = self.selected_teepod.as_ref().ok_or_else(|| Error::Api {
-            status_code: 400,
-            message: "No TEEPod selected. Call discover_teepod() or select_teepod() first".into(),
-        })?;
-
-        // Create VM configuration
-        let vm_config = json!({
-            "name": app_name,
-            "compose_manifest": {
-                "docker_compose_file": docker_compose_file,
-                "name": app_name,
-                "features": ["kms", "tproxy-net"]
-            },
-            "vcpu": vcpu.unwrap_or(1),
-            "memory": memory.unwrap_or(1024),
-            "disk_size": disk_size.unwrap_or(10),
-            "teepod_id": teepod_id,
-            "image": image
-        });
-
-        // Deploy the application with automatic encryption
-        let env_vars_vec: Vec<(String, String)> = env_vars.into_iter().collect();
-
-        // Get encryption keys
-        let pubkey_response = self.client.get_pubkey_for_config(&vm_config).await?;
-        let pubkey = pubkey_response.app_env_encrypt_pubkey;
-        let salt = pubkey_response.app_id_salt;
-
-        // Deploy with encrypted environment variables
-        let deployment = self
-            .client
-            .deploy_with_config_do_encrypt(vm_config, &env_vars_vec, &pubkey, &salt)
-            .await?;
-
-        // Add extra details if needed
-        if let Some(mut details) = deployment.details.clone() {
-            details.insert(
-                "teepod_id".to_string(),
-                serde_json::Value::Number(serde_json::Number::from(*teepod_id)),
-            );
-            details.insert(
-                "image".to_string(),
-                serde_json::Value::String(image.clone()),
-            );
-
-            let mut deployment_with_details = deployment.clone();
-            deployment_with_details.details = Some(details);
-            Ok(deployment_with_details)
-        } else {
-            // Create new details if none exist
-            let mut details = HashMap::new();
-            details.insert(
-                "teepod_id".to_string(),
-                serde_json::Value::Number(serde_json::Number::from(*teepod_id)),
-            );
-            details.insert(
-                "image".to_string(),
-                serde_json::Value::String(image.clone()),
-            );
-
-            let mut deployment_with_details = deployment.clone();
-            deployment_with_details.details = Some(details);
-            Ok(deployment_with_details)
-        }
+        self.ensure_teepod().await?;
+        // Create VM configuration; this fails if no TEEPod has been selected
+        let vm_config =
+            self.create_vm_config(docker_compose_file, app_name, vcpu, memory, disk_size)?;
+        self.deploy_vm_config(vm_config, Self::collect_env_vars(env_vars), None)
+            .await
     }
 
-    /// Deploys a Docker Compose application from a file path.
+    /// Deploys a Docker Compose application with secret-wrapped environment variables.
     ///
-    /// Reads a Docker Compose file from the specified path and deploys it to the selected TEEPod.
+    /// Behaves like `deploy_compose`, but takes the values as `Secret`s so they
+    /// cannot be printed by accident before they are encrypted.
     ///
     /// # Parameters
     ///
-    /// * `compose_path` - Path to the Docker Compose file
+    /// * `docker_compose_file` - Docker Compose configuration as a YAML string
     /// * `app_name` - Name for the deployed application
-    /// * `env_vars` - Environment variables for the application
-    /// * `vcpu` - Optional vCPU cores for the VM
-    /// * `memory` - Optional memory in MB for the VM
-    /// * `disk_size` - Optional disk size in GB for the VM
+    /// * `env_vars` - Environment variables to encrypt and pass to the containers
+    /// * `vcpu` - Optional number of virtual CPUs (default: 1)
+    /// * `memory` - Optional memory in MB (default: 1024)
+    /// * `disk_size` - Optional disk size in GB (default: 10)
     ///
     /// # Returns
     ///
-    /// A `DeploymentResponse` containing deployment details
+    /// A `DeploymentResponse` containing the deployment details
     ///
     /// # Errors
     ///
-    /// Returns an error if:
-    /// * The file cannot be read
-    /// * The underlying `deploy_compose` call fails
-    pub async fn deploy_compose_from_file<P: AsRef<Path>>(
+    /// Returns the same errors as `deploy_compose`
+    pub async fn deploy_compose_secret(
         &self,
-        compose_path: P,
+        docker_compose_file: &str,
         app_name: &str,
-        env_vars: HashMap<String, String>,
+        env_vars: HashMap<String, Secret<String>>,
         vcpu: Option<u64>,
         memory: Option<u64>,
         disk_size: Option<u64>,
     ) -> Result<DeploymentResponse> {
-        // Read compose file
-        let content = std::fs::read_to_string(compose_path)
-            .map_err(|e| Error::Configuration(format!("Failed to read compose file: {}", e)))?;
+        let env_vars: HashMap<String, String> = env_vars
+            .into_iter()
+            .map(|(key, value)| (key, value.into_inner()))
+            .collect();
+        self.deploy_compose(
+            docker_compose_file,
+            app_name,
+            env_vars,
+            vcpu,
+            memory,
+            disk_size,
+        )
+        .await
+    }
 
-        self.deploy_compose(&content, app_name, env_vars, vcpu, memory, disk_size)
-            .await
+    /// Collects environment variable pairs from any map, vector or array of
+    /// string-like keys and values.
+    fn collect_env_vars(
+        env_vars: impl IntoIterator<Item = (impl Into<String>, impl Into<String>)>,
+    ) -> HashMap<String, String> {
+        env_vars
+            .into_iter()
+            .map(|(key, value)| (key.into(), value.into()))
+            .collect()
     }
 
-    /// Deploys a Docker Compose application from a YAML string.
+    /// Encrypts `env_vars` for `vm_config`, deploys it and annotates the response
+    /// with the TEEPod, image, app ID and labels it was deployed with.
     ///
-    /// Takes a Docker Compose YAML string and deploys it to the selected TEEPod.
+    /// With a `salt_override`, the salt is sent with both the pubkey request and
+    /// the deployment in place of the server-generated one.
+    async fn deploy_vm_config(
+        &self,
+        vm_config: VmConfig,
+        env_vars: HashMap<String, String>,
+        salt_override: Option<AppIdSalt>,
+    ) -> Result<DeploymentResponse> {
+        let _claim = self.claim_deploy(&vm_config.name)?;
+        self.check_image_offered(&vm_config)?;
+        self.check_env_references(&vm_config.compose_manifest.docker_compose_file, &env_vars)?;
+        validate_compose(&vm_config.compose_manifest.docker_compose_file)?;
+
+        let teepod_id = vm_config.teepod_id;
+        let name = vm_config.name.clone();
+        let image = vm_config.image.clone();
+        let labels = vm_config.labels.clone();
+        let requested_user_id = vm_config
+            .extra
+            .get("user_id")
+            .and_then(|v| v.as_str())
+            .map(str::to_string);
+        let mut vm_config = Value::from(vm_config);
+        if let Some(salt) = &salt_override {
+            vm_config["app_id_salt"] = json!(salt.as_str());
+        }
+
+        // Deploy the application with automatic encryption
+        let env_vars_vec: Vec<(String, String)> = env_vars.into_iter().collect();
+
+        // Get encryption keys
+        let deadline = self.start_deadline();
+        let pubkey_response = self.client.get_pubkey_for_config(&vm_config).await?;
+        let pubkey = pubkey_response.public_key()?;
+        let salt = match salt_override {
+            Some(salt) => salt,
+            None => pubkey_response.salt()?,
+        };
+        let app_id = pubkey_response.app_id;
+        let user_id = pubkey_response.user_id.or(requested_user_id);
+
+        // Deploy with encrypted environment variables
+        self.check_deadline(deadline, "deploying")?;
+        let mut deployment = self
+            .send_deploy(&name, vm_config, &env_vars_vec, &pubkey, &salt, deadline)
+            .await?;
+
+        // Add extra details, creating the map if the API returned none
+        let mut details = deployment.details.clone().unwrap_or_default();
+        details.insert(
+            "teepod_id".to_string(),
+            serde_json::Value::Number(serde_json::Number::from(teepod_id)),
+        );
+        details.insert("image".to_string(), serde_json::Value::String(image));
+        // The pubkey step returns the real app identifier; prefer it over the numeric ID
+        if !app_id.is_empty() {
+            let app_id = Self::canonical_app_id(&app_id);
+            details.insert("app_id".to_string(), json!(app_id));
+            deployment.app_id = Some(app_id);
+        }
+        if !labels.is_empty() {
+            details.insert("labels".to_string(), json!(labels));
+        }
+        if let Some(user_id) = user_id {
+            details.insert("user_id".to_string(), json!(user_id));
+        }
+
+        deployment.details = Some(details);
+        Ok(deployment)
+    }
+
+    /// Deploys a Docker Compose application and returns its canonical app ID.
+    ///
+    /// Behaves like `deploy_compose`, additionally returning the `app_`-prefixed
+    /// application identifier derived from the pubkey step. The ID can be passed
+    /// directly to `get_network_info`, `get_system_stats` and the other per-app
+    /// methods, without reconstructing it from the numeric deployment ID.
     ///
     /// # Parameters
     ///
-    /// * `yaml_content` - Docker Compose YAML content as a string
+    /// * `docker_compose_file` - The Docker Compose configuration as a YAML string
     /// * `app_name` - Name for the deployed application
-    /// * `env_vars` - Environment variables for the application
-    /// * `vcpu` - Optional vCPU cores for the VM
-    /// * `memory` - Optional memory in MB for the VM
-    /// * `disk_size` - Optional disk size in GB for the VM
+    /// * `env_vars` - Environment variables for the application (will be securely encrypted)
+    /// * `vcpu` - Optional vCPU cores for the VM (defaults to 1)
+    /// * `memory` - Optional memory in MB for the VM (defaults to 1024)
+    /// * `disk_size` - Optional disk size in GB for the VM (defaults to 10)
     ///
     /// # Returns
     ///
-    /// A `DeploymentResponse` containing deployment details
+    /// The `DeploymentResponse` and the canonical app ID
     ///
     /// # Errors
     ///
-    /// Returns an error if the underlying `deploy_compose` call fails
-    pub async fn deploy_compose_from_string(
+    /// Returns the same errors as `deploy_compose`
+    pub async fn deploy_compose_full(
         &self,
-        yaml_content: &str,
+        docker_compose_file: &str,
         app_name: &str,
-        env_vars: HashMap<String, String>,
+        env_vars: impl IntoIterator<Item = (impl Into<String>, impl Into<String>)>,
         vcpu: Option<u64>,
         memory: Option<u64>,
         disk_size: Option<u64>,
-    ) -> Result<DeploymentResponse> {
-        self.deploy_compose(yaml_content, app_name, env_vars, vcpu, memory, disk_size)
-            .await
+    ) -> Result<(DeploymentResponse, String)> {
+        let deployment = self
+            .deploy_compose(
+                docker_compose_file,
+                app_name,
+                env_vars,
+                vcpu,
+                memory,
+                disk_size,
+            )
+            .await?;
+        // Only reached without an app ID from the pubkey step
+        let app_id = deployment
+            .app_id
+            .clone()
+            .unwrap_or_else(|| Self::canonical_app_id(&deployment.id.to_string()));
+        Ok((deployment, app_id))
     }
 
-    /// Deploys a simple service using just an image name and basic configuration.
-    ///
-    /// This is a convenience method for quickly deploying a single service
-    /// without needing to create a full Docker Compose configuration.
+    /// Flags `${VAR}` references in the compose file that no supplied
+    /// environment variable resolves; the container would silently receive an
+    /// empty value. References with a default are fine.
     ///
-    /// # Parameters
-    ///
-    /// * `image` - Docker image name (e.g. "nginx:latest")
-    /// * `service_name` - Name for the service
+    /// Logs a warning, or fails when `strict_env_check` is enabled.
+    fn check_env_references(
+        &self,
+        docker_compose_file: &str,
+        env_vars: &HashMap<String, String>,
+    ) -> Result<()> {
+        let unresolved: Vec<String> = interpolated_variables(docker_compose_file)
+            .into_iter()
+            .filter(|var| !var.has_default && !env_vars.contains_key(&var.name))
+            .map(|var| var.name)
+            .collect();
+        if unresolved.is_empty() {
+            return Ok(());
+        }
+
+        let message = format!(
+            "Compose file references environment variables that are not provided: {}",
+            unresolved.join(", ")
+        );
+        if self.strict_env_check {
+            return Err(Error::Configuration(message));
+        }
+        tracing::warn!("{}", message);
+        Ok(())
+    }
+
+    /// Returns the deadline for a multi-step operation starting now, if an
+    /// operation timeout is configured.
+    fn start_deadline(&self) -> Option<Instant> {
+        self.operation_timeout
+            .map(|timeout| self.clock.now() + timeout)
+    }
+
+    /// Fails with a timeout error if `deadline` has passed, so the next
+    /// request of a multi-step operation is not started.
+    fn check_deadline(&self, deadline: Option<Instant>, next_step: &str) -> Result<()> {
+        match (deadline, self.operation_timeout) {
+            (Some(deadline), Some(timeout)) if self.clock.now() >= deadline => {
+                Err(Error::Timeout {
+                    operation: format!(
+                        "an operation that exceeded its {:?} timeout before {}",
+                        timeout, next_step
+                    ),
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Sends the deployment request, retrying up to `deploy_retries` times
+    /// after ambiguous failures.
+    ///
+    /// A timed-out or dropped request, or a gateway error, may have created
+    /// the CVM anyway. Before each retry the CVM list is checked for a CVM
+    /// named `name` that did not exist before the first attempt; if one is
+    /// found it is returned instead of deploying a duplicate.
+    ///
+    /// A failed CVM list request uses up one retry. If the list before the
+    /// first attempt cannot be fetched, the deploy is sent once without
+    /// retries, since duplicates could not be told apart; if the list after
+    /// a failure cannot be fetched before the retries run out, the original
+    /// deploy error is returned.
+    async fn send_deploy(
+        &self,
+        name: &str,
+        vm_config: Value,
+        env_vars: &[(String, String)],
+        pubkey: &AppPublicKey,
+        salt: &AppIdSalt,
+        deadline: Option<Instant>,
+    ) -> Result<DeploymentResponse> {
+        if self.deploy_retries == 0 {
+            return self
+                .client
+                .deploy_with_config_do_encrypt(vm_config, env_vars, pubkey, salt)
+                .await;
+        }
+
+        let mut retries = 0;
+        let existing = loop {
+            match self.client.list_cvms().await {
+                Ok(cvms) => {
+                    break Some(
+                        cvms.into_iter()
+                            .filter(|cvm| cvm.name == name)
+                            .map(|cvm| cvm.id)
+                            .collect::<HashSet<u64>>(),
+                    )
+                }
+                Err(e) if retries < self.deploy_retries => {
+                    retries += 1;
+                    tracing::warn!(
+                        "Listing CVMs before deploying '{}' failed ({}); retrying ({}/{})",
+                        name,
+                        e,
+                        retries,
+                        self.deploy_retries
+                    );
+                    self.pause_before_retry(retries, deadline).await?;
+                }
+                Err(e) => {
+                    tracing::warn!(
+                        "Listing CVMs before deploying '{}' failed ({}); deploying without retries",
+                        name,
+                        e
+                    );
+                    break None;
+                }
+            }
+        };
+
+        loop {
+            let error = match self
+                .client
+                .deploy_with_config_do_encrypt(vm_config.clone(), env_vars, pubkey, salt)
+                .await
+            {
+                Ok(deployment) => return Ok(deployment),
+                Err(e) => e,
+            };
+            let ambiguous = matches!(error, Error::Timeout { .. } | Error::HttpClient(_))
+                || error.is_transient_gateway();
+            let Some(existing) = existing.as_ref() else {
+                return Err(error);
+            };
+            if !ambiguous || retries >= self.deploy_retries {
+                return Err(error);
+            }
+
+            // Each check uses up a retry, whether or not the list request succeeds
+            let cvms = loop {
+                retries += 1;
+                match self.client.list_cvms().await {
+                    Ok(cvms) => break cvms,
+                    Err(e) if retries < self.deploy_retries => {
+                        tracing::warn!(
+                            "Checking for CVM '{}' failed ({}); checking again ({}/{})",
+                            name,
+                            e,
+                            retries,
+                            self.deploy_retries
+                        );
+                        self.pause_before_retry(retries, deadline).await?;
+                    }
+                    Err(e) => {
+                        tracing::warn!(
+                            "Checking for CVM '{}' failed ({}); out of retries",
+                            name,
+                            e
+                        );
+                        return Err(error);
+                    }
+                }
+            };
+
+            if let Some(cvm) = cvms
+                .into_iter()
+                .find(|cvm| cvm.name == name && !existing.contains(&cvm.id))
+            {
+                tracing::warn!(
+                    "Deploy of '{}' failed ambiguously ({}), but CVM {} exists; not retrying",
+                    name,
+                    error,
+                    cvm.id
+                );
+                // Parsed like a deploy response, so the CVM's fields land in `details`
+                let cvm = serde_json::to_value(&cvm).map_err(|e| {
+                    Error::Serialization(format!("Failed to serialize CVM info: {}", e))
+                })?;
+                let mut deployment: DeploymentResponse =
+                    serde_json::from_value(cvm).map_err(|e| {
+                        Error::Serialization(format!("Failed to parse CVM info: {}", e))
+                    })?;
+                deployment.app_id = deployment.app_id.as_deref().map(Self::canonical_app_id);
+                return Ok(deployment);
+            }
+
+            tracing::warn!(
+                "Deploy of '{}' failed ({}); retrying ({}/{})",
+                name,
+                error,
+                retries,
+                self.deploy_retries
+            );
+            self.pause_before_retry(retries, deadline).await?;
+        }
+    }
+
+    /// Waits before retry number `retry` (starting at 1) of a deploy, then
+    /// fails if the operation deadline has passed.
+    async fn pause_before_retry(&self, retry: u32, deadline: Option<Instant>) -> Result<()> {
+        self.clock.sleep(self.poll_strategy.delay(retry - 1)).await;
+        self.check_deadline(deadline, "retrying the deployment")
+    }
+
+    /// Adds the `app_` prefix expected by the per-app endpoints, if missing.
+    fn canonical_app_id(app_id: &str) -> String {
+        if app_id.starts_with("app_") {
+            app_id.to_string()
+        } else {
+            format!("app_{}", app_id)
+        }
+    }
+
+    /// Deploys a Docker Compose application from a file path.
+    ///
+    /// Reads a Docker Compose file from the specified path and deploys it to the selected TEEPod.
+    ///
+    /// # Parameters
+    ///
+    /// * `compose_path` - Path to the Docker Compose file
+    /// * `app_name` - Name for the deployed application
+    /// * `env_vars` - Environment variables for the application
+    /// * `vcpu` - Optional vCPU cores for the VM
+    /// * `memory` - Optional memory in MB for the VM
+    /// * `disk_size` - Optional disk size in GB for the VM
+    ///
+    /// # Returns
+    ///
+    /// A `DeploymentResponse` containing deployment details
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// * The file cannot be read
+    /// * The underlying `deploy_compose` call fails
+    pub async fn deploy_compose_from_file<P: AsRef<Path>>(
+        &self,
+        compose_path: P,
+        app_name: &str,
+        env_vars: impl IntoIterator<Item = (impl Into<String>, impl Into<String>)>,
+        vcpu: Option<u64>,
+        memory: Option<u64>,
+        disk_size: Option<u64>,
+    ) -> Result<DeploymentResponse> {
+        // Read compose file
+        let content = std::fs::read_to_string(compose_path)
+            .map_err(|e| Error::Configuration(format!("Failed to read compose file: {}", e)))?;
+
+        self.deploy_compose(&content, app_name, env_vars, vcpu, memory, disk_size)
+            .await
+    }
+
+    /// Deploys a Docker Compose application from a YAML string.
+    ///
+    /// Takes a Docker Compose YAML string and deploys it to the selected TEEPod.
+    ///
+    /// # Parameters
+    ///
+    /// * `yaml_content` - Docker Compose YAML content as a string
+    /// * `app_name` - Name for the deployed application
+    /// * `env_vars` - Environment variables for the application
+    /// * `vcpu` - Optional vCPU cores for the VM
+    /// * `memory` - Optional memory in MB for the VM
+    /// * `disk_size` - Optional disk size in GB for the VM
+    ///
+    /// # Returns
+    ///
+    /// A `DeploymentResponse` containing deployment details
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the underlying `deploy_compose` call fails
+    pub async fn deploy_compose_from_string(
+        &self,
+        yaml_content: &str,
+        app_name: &str,
+        env_vars: impl IntoIterator<Item = (impl Into<String>, impl Into<String>)>,
+        vcpu: Option<u64>,
+        memory: Option<u64>,
+        disk_size: Option<u64>,
+    ) -> Result<DeploymentResponse> {
+        self.deploy_compose(yaml_content, app_name, env_vars, vcpu, memory, disk_size)
+            .await
+    }
+
+    /// Deploys a simple service using just an image name and basic configuration.
+    ///
+    /// This is a convenience method for quickly deploying a single service
+    /// without needing to create a full Docker Compose configuration.
+    ///
+    /// # Parameters
+    ///
+    /// * `image` - Docker image name (e.g. "nginx:latest")
+    /// * `service_name` - Name for the service
     /// * `app_name` - Name for the deployed application
     /// * `env_vars` - Environment variables for the service
     /// * `ports` - Optional port mappings (e.g. ["80:80"])
@@ -363,12 +1175,13 @@ impl TeeDeployer {
     /// # Errors
     ///
     /// Returns an error if the underlying `deploy_compose` call fails
+    #[allow(clippy::too_many_arguments)]
     pub async fn deploy_simple_service(
         &self,
         image: &str,
         service_name: &str,
         app_name: &str,
-        env_vars: HashMap<String, String>,
+        env_vars: impl IntoIterator<Item = (impl Into<String>, impl Into<String>)>,
         ports: Option<Vec<String>>,
         volumes: Option<Vec<String>>,
         command: Option<Vec<String>>,
@@ -376,6 +1189,8 @@ impl TeeDeployer {
         memory: Option<u64>,
         disk_size: Option<u64>,
     ) -> Result<DeploymentResponse> {
+        let env_vars = Self::collect_env_vars(env_vars);
+
         // Create a simple Docker Compose YAML configuration
         let mut yaml = String::from("services:\n");
         yaml.push_str(&format!("  {}:\n", service_name));
@@ -434,6 +1249,7 @@ impl TeeDeployer {
     ///
     /// Returns an error if:
     /// * The application cannot be found
+    /// * `compose_content` is given but the fetched compose has no `compose_manifest`
     /// * The API request fails
     pub async fn update_deployment(
         &self,
@@ -441,182 +1257,587 @@ impl TeeDeployer {
         compose_content: Option<&str>,
         env_vars: Option<HashMap<String, String>>,
     ) -> Result<Value> {
+        self.update_deployment_with_removals(app_id, compose_content, env_vars, &[])
+            .await
+    }
+
+    /// Updates an existing deployment and removes environment variables from it.
+    ///
+    /// The encrypted environment sent with an update replaces the previous one,
+    /// so `env_vars` must contain every variable to keep; keys listed in
+    /// `env_removals` are never re-sent and are dropped from `allowed_envs`.
+    ///
+    /// # Parameters
+    ///
+    /// * `app_id` - The ID of the application to update
+    /// * `compose_content` - Optional new Docker Compose configuration as a YAML string
+    /// * `env_vars` - Environment variables to keep or set
+    /// * `env_removals` - Keys of environment variables to remove
+    ///
+    /// # Returns
+    ///
+    /// A `Value` containing details about the update operation, including the removed keys
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// * Keys are removed without supplying the remaining `env_vars`
+    /// * The application cannot be found
+    /// * `compose_content` is given but the fetched compose has no `compose_manifest`
+    /// * The API request fails
+    pub async fn update_deployment_with_removals(
+        &self,
+        app_id: &str,
+        compose_content: Option<&str>,
+        env_vars: Option<HashMap<String, String>>,
+        env_removals: &[String],
+    ) -> Result<Value> {
+        if let Some(new_config) = compose_content {
+            validate_compose(new_config)?;
+        }
+
         // Get the current compose configuration
+        let deadline = self.start_deadline();
         let compose_response = self.client.get_compose(app_id).await?;
         let mut compose_file = compose_response.compose_file;
 
         // Update compose file if provided
         if let Some(new_config) = compose_content {
-            if let Some(manifest) = compose_file.get_mut("compose_manifest") {
-                if let Some(obj) = manifest.as_object_mut() {
-                    obj.insert("docker_compose_file".to_string(), json!(new_config));
-                }
-            }
+            let manifest = compose_file
+                .get_mut("compose_manifest")
+                .and_then(Value::as_object_mut)
+                .ok_or_else(|| {
+                    Error::Configuration(format!(
+                        "Compose fetched for deployment {} has an unexpected shape: \
+                         no compose_manifest object to place the new compose file in",
+                        app_id
+                    ))
+                })?;
+            manifest.insert(
+                "docker_compose_file".to_string(),
+                json!(self.normalize_compose(new_config)),
+            );
         }
 
         // Apply the update
+        self.check_deadline(deadline, "applying the update")?;
         let response = self
             .client
-            .update_compose(app_id, compose_file, env_vars, compose_response.env_pubkey)
+            .update_compose_with_removals(
+                app_id,
+                compose_file,
+                env_vars,
+                env_removals,
+                compose_response.env_pubkey,
+            )
             .await?;
 
-        Ok(json!({
+        let mut result = json!({
             "status": "updated",
             "app_id": app_id,
-            "details": response
-        }))
+            "details": response.raw
+        });
+        if !env_removals.is_empty() {
+            result["removed_envs"] = json!(env_removals);
+        }
+        Ok(result)
     }
 
-    /// Creates a VM configuration for a Docker Compose application.
+    /// Duplicates an existing deployment under a new name.
     ///
-    /// This method creates a VM configuration without actually deploying it,
-    /// which can be used to request encryption keys or for later deployment.
+    /// The source compose file is fetched and redeployed as a fresh CVM on the
+    /// currently selected TEEPod, keeping the source's vCPU, memory and disk
+    /// size when the API reports them. Encrypted environment variables cannot be
+    /// read back from the source, so every variable the compose file
+    /// interpolates without a default (and every key listed in the source's
+    /// `allowed_envs`) must be supplied in `env_overrides`.
     ///
     /// # Parameters
     ///
-    /// * `docker_compose_file` - The Docker Compose configuration as a YAML string
-    /// * `app_name` - Name for the application
-    /// * `vcpu` - Optional vCPU cores for the VM (defaults to 1)
-    /// * `memory` - Optional memory in MB for the VM (defaults to 1024)
-    /// * `disk_size` - Optional disk size in GB for the VM (defaults to 10)
+    /// * `source_app_id` - The ID of the application to clone
+    /// * `new_name` - Name for the cloned application
+    /// * `env_overrides` - Environment variables for the clone (will be securely encrypted)
     ///
     /// # Returns
     ///
-    /// A VM configuration object
+    /// A `DeploymentResponse` for the newly created deployment
     ///
     /// # Errors
     ///
-    /// Returns an error if no TEEPod has been selected
-    pub fn create_vm_config(
+    /// Returns an error if:
+    /// * The source compose file cannot be fetched or has no compose content
+    /// * A referenced environment variable is missing from `env_overrides`
+    /// * The underlying `deploy_compose` call fails
+    pub async fn clone_deployment(
         &self,
-        docker_compose_file: &str,
-        app_name: &str,
-        vcpu: Option<u64>,
-        memory: Option<u64>,
-        disk_size: Option<u64>,
-    ) -> Result<VmConfig> {
-        // Ensure we have a selected TEEPod
-        let (teepod_id, image) = self.selected_teepod.as_ref().ok_or_else(|| Error::Api {
-            status_code: 400,
-            message: "No TEEPod selected. Call discover_teepod() or select_teepod() first".into(),
+        source_app_id: &str,
+        new_name: &str,
+        env_overrides: HashMap<String, String>,
+    ) -> Result<DeploymentResponse> {
+        let compose_response = self.client.get_compose(source_app_id).await?;
+        let compose_file = &compose_response.compose_file;
+        let docker_compose = Self::docker_compose_of(compose_file).ok_or_else(|| {
+            Error::Configuration(format!(
+                "Deployment {} has no docker compose file to clone",
+                source_app_id
+            ))
         })?;
 
-        // Create VM configuration
-        let vm_config = VmConfig {
-            name: app_name.to_string(),
-            compose_manifest: ComposeManifest {
-                name: app_name.to_string(),
-                features: vec!["kms".to_string(), "tproxy-net".to_string()],
-                docker_compose_file: docker_compose_file.to_string(),
-            },
-            vcpu: vcpu.unwrap_or(1) as u32,
-            memory: memory.unwrap_or(1024) as u32,
-            disk_size: disk_size.unwrap_or(10) as u32,
-            teepod_id: *teepod_id,
-            image: image.to_string(),
-            advanced_features: AdvancedFeatures {
-                tproxy: true,
-                kms: true,
-                public_sys_info: true,
-                public_logs: true,
-                docker_config: DockerConfig {
-                    username: String::new(),
-                    password: String::new(),
-                    registry: None,
-                },
-                listed: true,
-            },
-        };
+        let allowed_envs = compose_file
+            .get("allowed_envs")
+            .and_then(|v| v.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|v| v.as_str());
+        let referenced = interpolated_variables(docker_compose)
+            .into_iter()
+            .filter(|var| !var.has_default)
+            .map(|var| var.name);
 
-        Ok(vm_config)
+        let mut missing: Vec<String> = Vec::new();
+        for key in referenced.chain(allowed_envs.map(str::to_string)) {
+            if !env_overrides.contains_key(&key) && !missing.contains(&key) {
+                missing.push(key);
+            }
+        }
+        if !missing.is_empty() {
+            return Err(Error::Configuration(format!(
+                "Cannot clone {}: secret environment variables must be supplied via overrides: {}",
+                source_app_id,
+                missing.join(", ")
+            )));
+        }
+
+        let source = self.client.get_cvm(source_app_id).await?;
+        let resource = |key: &str| source.extra.get(key).and_then(|v| v.as_u64());
+
+        self.deploy_compose(
+            docker_compose,
+            new_name,
+            env_overrides,
+            resource("vcpu"),
+            resource("memory"),
+            resource("disk_size"),
+        )
+        .await
     }
 
-    /// Creates a VM configuration from a Docker Compose file path.
+    /// Fetches the Docker Compose YAML of a deployment.
     ///
-    /// This is a convenience method that reads a Docker Compose file
-    /// and creates a VM configuration for it.
+    /// Extracts the `docker_compose_file` from the nested compose response,
+    /// ready to be edited and passed to `update_deployment`.
     ///
     /// # Parameters
     ///
-    /// * `compose_path` - Path to the Docker Compose file
-    /// * `app_name` - Name for the application
-    /// * `vcpu` - Optional vCPU cores for the VM
-    /// * `memory` - Optional memory in MB for the VM
-    /// * `disk_size` - Optional disk size in GB for the VM
+    /// * `app_id` - The ID of the deployed application
     ///
     /// # Returns
     ///
-    /// A VM configuration object
+    /// The deployed compose file as a YAML string
     ///
     /// # Errors
     ///
-    /// Returns an error if:
-    /// * The file cannot be read
-    /// * The `create_vm_config` call fails
-    pub fn create_vm_config_from_file<P: AsRef<Path>>(
-        &self,
-        compose_path: P,
-        app_name: &str,
-        vcpu: Option<u64>,
-        memory: Option<u64>,
-        disk_size: Option<u64>,
-    ) -> Result<VmConfig> {
-        // Read compose file
-        let content = std::fs::read_to_string(compose_path)
-            .map_err(|e| Error::Configuration(format!("Failed to read compose file: {}", e)))?;
-
-        self.create_vm_config(&content, app_name, vcpu, memory, disk_size)
+    /// Returns an error if the compose file cannot be fetched or has no compose content
+    pub async fn get_compose_yaml(&self, app_id: &str) -> Result<String> {
+        let compose_response = self.client.get_compose(app_id).await?;
+        Self::docker_compose_of(&compose_response.compose_file)
+            .map(str::to_string)
+            .ok_or_else(|| {
+                Error::Configuration(format!(
+                    "Deployment {} has no docker_compose_file in its compose response",
+                    app_id
+                ))
+            })
     }
 
-    /// Retrieves the encryption public key for a VM configuration.
+    /// Checks that the platform stored exactly the compose file that was deployed.
     ///
-    /// This method requests a public key from the API that can be used to
-    /// encrypt environment variables for a specific VM configuration.
+    /// Line endings and trailing whitespace are normalized before comparing, so
+    /// only meaningful differences count as a mismatch. A mismatch indicates
+    /// platform-side mutation or a stale deployment.
     ///
     /// # Parameters
     ///
-    /// * `vm_config` - The VM configuration as a JSON value
+    /// * `app_id` - The ID of the deployed application
+    /// * `expected_compose` - The Docker Compose YAML that was deployed
     ///
     /// # Returns
     ///
-    /// A JSON value containing the public key and salt for encryption
+    /// `true` if the deployed compose file matches, `false` otherwise
     ///
     /// # Errors
     ///
-    /// Returns an error if the API request fails
-    pub async fn get_pubkey_for_config(&self, vm_config: &Value) -> Result<PubkeyResponse> {
-        self.client
-            .get_pubkey_for_config(vm_config)
-            .await
-                }
+    /// Returns an error if the compose file cannot be fetched or has no compose content
+    pub async fn verify_deployed_compose(
+        &self,
+        app_id: &str,
+        expected_compose: &str,
+    ) -> Result<bool> {
+        let compose_response = self.client.get_compose(app_id).await?;
+        let deployed =
+            Self::docker_compose_of(&compose_response.compose_file).ok_or_else(|| {
+                Error::Configuration(format!(
+                    "Deployment {} has no docker compose file to verify",
+                    app_id
+                ))
+            })?;
 
-    /// Deploys a VM configuration with pre-encrypted environment variables.
+        let normalize = |yaml: &str| {
+            let lines: Vec<&str> = yaml.lines().map(str::trim_end).collect();
+            lines.join("\n").trim_end().to_string()
+        };
+        Ok(normalize(deployed) == normalize(expected_compose))
+    }
+
+    /// Lists the environment variable names a deployment already declares.
     ///
-    /// This method is useful for workflows where the user encrypts their own
-    /// environment variables and the operator deploys the application. It allows
-    /// for a separation of concerns between compute management and sensitive data.
+    /// Values are encrypted and never returned; only the key names are read,
+    /// from the compose file's `allowed_envs` and each service's `environment`.
+    /// Use this before `update_deployment` to avoid clobbering existing keys,
+    /// since an update replaces the whole encrypted environment.
     ///
     /// # Parameters
     ///
-    /// * `vm_config` - The VM configuration as a JSON value
-    /// * `encrypted_env` - Pre-encrypted environment variables as a hex string
-    /// * `app_env_encrypt_pubkey` - The public key used for encryption
-    /// * `app_id_salt` - The salt value used for encryption
+    /// * `app_id` - The ID of the deployed application
     ///
     /// # Returns
     ///
-    /// A JSON value containing the deployment details
+    /// The declared key names in order of first appearance, without duplicates
     ///
     /// # Errors
     ///
-    /// Returns an error if the API request fails
+    /// Returns an error if the compose file cannot be fetched
+    pub async fn get_env_keys(&self, app_id: &str) -> Result<Vec<String>> {
+        let compose_file = self.client.get_compose(app_id).await?.compose_file;
+
+        let mut keys: Vec<String> = compose_file
+            .get("allowed_envs")
+            .and_then(|v| v.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|key| key.as_str().map(str::to_string))
+            .collect();
+        if let Some(docker_compose) = Self::docker_compose_of(&compose_file) {
+            for key in environment_keys(docker_compose) {
+                if !keys.contains(&key) {
+                    keys.push(key);
+                }
+            }
+        }
+        Ok(keys)
+    }
+
+    /// Exports a fully-resolved deployment plan as pretty-printed JSON.
+    ///
+    /// The plan captures the VM configuration the deployer would send (TEEPod,
+    /// image, resources, feature flags and labels) so it can be reviewed and
+    /// applied later with `deploy_from_plan`. Registry passwords are stripped and
+    /// environment variables are never included. Object keys are sorted, so the
+    /// same inputs always produce the same text.
+    ///
+    /// # Parameters
+    ///
+    /// * `docker_compose_file` - The Docker Compose configuration as a YAML string
+    /// * `app_name` - Name for the application
+    /// * `vcpu` - Optional vCPU cores for the VM (defaults to 1)
+    /// * `memory` - Optional memory in MB for the VM (defaults to 1024)
+    /// * `disk_size` - Optional disk size in GB for the VM (defaults to 10)
+    ///
+    /// # Returns
+    ///
+    /// The plan serialized as JSON
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no TEEPod has been selected or serialization fails
+    pub fn export_plan(
+        &self,
+        docker_compose_file: &str,
+        app_name: &str,
+        vcpu: Option<u64>,
+        memory: Option<u64>,
+        disk_size: Option<u64>,
+    ) -> Result<String> {
+        let mut vm_config =
+            self.create_vm_config(docker_compose_file, app_name, vcpu, memory, disk_size)?;
+        vm_config.advanced_features.docker_config.password.clear();
+
+        let plan = serde_json::to_value(DeploymentPlan { vm_config })
+            .map_err(|e| Error::Serialization(format!("Failed to serialize plan: {}", e)))?;
+        serde_json::to_string_pretty(&plan)
+            .map_err(|e| Error::Serialization(format!("Failed to serialize plan: {}", e)))
+    }
+
+    /// Deploys a plan previously produced by `export_plan`, verbatim.
+    ///
+    /// The plan's TEEPod and image are used as-is, so no TEEPod needs to be
+    /// selected on this deployer.
+    ///
+    /// # Parameters
+    ///
+    /// * `plan` - The plan JSON
+    /// * `env_vars` - Environment variables for the application (will be securely encrypted)
+    ///
+    /// # Returns
+    ///
+    /// A `DeploymentResponse` containing deployment details
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// * The plan is not valid JSON or does not match the plan format
+    /// * The API request fails
+    /// * Environment variable encryption fails
+    pub async fn deploy_from_plan(
+        &self,
+        plan: &str,
+        env_vars: impl IntoIterator<Item = (impl Into<String>, impl Into<String>)>,
+    ) -> Result<DeploymentResponse> {
+        let plan: DeploymentPlan = serde_json::from_str(plan)
+            .map_err(|e| Error::Serialization(format!("Invalid deployment plan: {}", e)))?;
+        self.deploy_vm_config(plan.vm_config, Self::collect_env_vars(env_vars), None)
+            .await
+    }
+
+    /// Deploys a VM configuration, optionally with a caller-provided app ID salt.
+    ///
+    /// The typed counterpart of `deploy_with_encrypted_env`: the configuration
+    /// usually comes from `create_vm_config`, and `env_vars` are encrypted
+    /// automatically.
+    ///
+    /// The platform derives the app ID from the salt together with the
+    /// configuration, so a fixed `salt_override` makes redeploying the same
+    /// configuration yield the same app ID, and the environment is encrypted
+    /// for that app. Reusing a salt across different deployments makes their
+    /// app IDs collide, so only fix the salt for deployments that are meant to
+    /// replace each other. With `None`, the salt generated by the server is
+    /// used, as in the other deploy methods.
+    ///
+    /// # Parameters
+    ///
+    /// * `vm_config` - The VM configuration to deploy
+    /// * `env_vars` - Environment variables for the application (will be securely encrypted)
+    /// * `salt_override` - Optional app ID salt to send instead of the server-generated one
+    ///
+    /// # Returns
+    ///
+    /// A `DeploymentResponse` containing deployment details
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// * `salt_override` is an empty string
+    /// * The configuration targets the selected TEEPod with an image it does not
+    ///   offer, unless disabled with `TeeDeployerBuilder::with_image_check`
+    /// * The API request fails, including when the platform rejects the salt
+    /// * Environment variable encryption fails
+    pub async fn deploy_config(
+        &self,
+        vm_config: VmConfig,
+        env_vars: impl IntoIterator<Item = (impl Into<String>, impl Into<String>)>,
+        salt_override: Option<&str>,
+    ) -> Result<DeploymentResponse> {
+        let salt_override = salt_override.map(AppIdSalt::try_from).transpose()?;
+        self.deploy_vm_config(vm_config, Self::collect_env_vars(env_vars), salt_override)
+            .await
+    }
+
+    /// Extracts the docker compose YAML from a compose file returned by the API,
+    /// which may hold it at the top level or inside `compose_manifest`.
+    fn docker_compose_of(compose_file: &Value) -> Option<&str> {
+        compose_file
+            .get("docker_compose_file")
+            .or_else(|| {
+                compose_file
+                    .get("compose_manifest")?
+                    .get("docker_compose_file")
+            })
+            .and_then(|v| v.as_str())
+    }
+
+    /// Creates a VM configuration for a Docker Compose application.
+    ///
+    /// This method creates a VM configuration without actually deploying it,
+    /// which can be used to request encryption keys or for later deployment.
+    /// The deployment is only listed in public directories if the deployer was
+    /// built with `with_listed(true)`.
+    ///
+    /// # Parameters
+    ///
+    /// * `docker_compose_file` - The Docker Compose configuration as a YAML string
+    /// * `app_name` - Name for the application
+    /// * `vcpu` - Optional vCPU cores for the VM (defaults to 1)
+    /// * `memory` - Optional memory in MB for the VM (defaults to 1024)
+    /// * `disk_size` - Optional disk size in GB for the VM (defaults to 10)
+    ///
+    /// # Returns
+    ///
+    /// A VM configuration object
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// * No TEEPod has been selected (`Error::NoTeepodSelected`)
+    /// * A name template is configured and the resulting name is invalid
+    pub fn create_vm_config(
+        &self,
+        docker_compose_file: &str,
+        app_name: &str,
+        vcpu: Option<u64>,
+        memory: Option<u64>,
+        disk_size: Option<u64>,
+    ) -> Result<VmConfig> {
+        let (teepod_id, image) = self.ensure_teepod_selected()?;
+        let app_name = self.vm_name(app_name)?;
+        let app_name = app_name.as_str();
+
+        // Deploying on behalf of a sub-user is expressed as a top-level field
+        let mut extra = self.vm_extra.clone();
+        if let Some(user_id) = &self.user_id {
+            extra.insert("user_id".to_string(), json!(user_id));
+        }
+
+        // Create VM configuration
+        let vm_config = VmConfig {
+            name: app_name.to_string(),
+            compose_manifest: ComposeManifest {
+                name: app_name.to_string(),
+                features: vec!["kms".to_string(), "tproxy-net".to_string()],
+                docker_compose_file: self.normalize_compose(docker_compose_file),
+                runner: self.runner.clone(),
+            },
+            vcpu: vcpu.unwrap_or(1) as u32,
+            memory: memory.unwrap_or(1024) as u32,
+            disk_size: disk_size.unwrap_or(10) as u32,
+            teepod_id,
+            image: image.to_string(),
+            advanced_features: AdvancedFeatures {
+                tproxy: true,
+                kms: true,
+                public_sys_info: true,
+                public_logs: true,
+                docker_config: DockerConfig {
+                    username: String::new(),
+                    password: String::new(),
+                    registry: None,
+                },
+                listed: self.listed,
+            },
+            labels: self.labels.clone(),
+            kernel_cmdline_append: self.kernel_cmdline_append.clone(),
+            extra,
+        };
+
+        Ok(vm_config)
+    }
+
+    /// Applies the configured name template to `app_name`, validating the result.
+    fn vm_name(&self, app_name: &str) -> Result<String> {
+        let Some(template) = &self.name_template else {
+            return Ok(app_name.to_string());
+        };
+        let name = template.replace("{name}", app_name);
+        let valid = name.len() <= MAX_VM_NAME_LEN
+            && name.starts_with(|c: char| c.is_ascii_alphanumeric())
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid {
+            return Err(Error::Configuration(format!(
+                "VM name '{}' from template '{}' must be at most {} characters of letters, \
+                 digits, '-' and '_', starting with a letter or digit",
+                name, template, MAX_VM_NAME_LEN
+            )));
+        }
+        Ok(name)
+    }
+
+    /// Creates a VM configuration from a Docker Compose file path.
+    ///
+    /// This is a convenience method that reads a Docker Compose file
+    /// and creates a VM configuration for it.
+    ///
+    /// # Parameters
+    ///
+    /// * `compose_path` - Path to the Docker Compose file
+    /// * `app_name` - Name for the application
+    /// * `vcpu` - Optional vCPU cores for the VM
+    /// * `memory` - Optional memory in MB for the VM
+    /// * `disk_size` - Optional disk size in GB for the VM
+    ///
+    /// # Returns
+    ///
+    /// A VM configuration object
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// * The file cannot be read
+    /// * The `create_vm_config` call fails
+    pub fn create_vm_config_from_file<P: AsRef<Path>>(
+        &self,
+        compose_path: P,
+        app_name: &str,
+        vcpu: Option<u64>,
+        memory: Option<u64>,
+        disk_size: Option<u64>,
+    ) -> Result<VmConfig> {
+        // Read compose file
+        let content = std::fs::read_to_string(compose_path)
+            .map_err(|e| Error::Configuration(format!("Failed to read compose file: {}", e)))?;
+
+        self.create_vm_config(&content, app_name, vcpu, memory, disk_size)
+    }
+
+    /// Retrieves the encryption public key for a VM configuration.
+    ///
+    /// This method requests a public key from the API that can be used to
+    /// encrypt environment variables for a specific VM configuration.
+    ///
+    /// # Parameters
+    ///
+    /// * `vm_config` - The VM configuration as a JSON value
+    ///
+    /// # Returns
+    ///
+    /// A JSON value containing the public key and salt for encryption
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the API request fails
+    pub async fn get_pubkey_for_config(&self, vm_config: &Value) -> Result<PubkeyResponse> {
+        self.client.get_pubkey_for_config(vm_config).await
+    }
+
+    /// Deploys a VM configuration with pre-encrypted environment variables.
+    ///
+    /// This method is useful for workflows where the user encrypts their own
+    /// environment variables and the operator deploys the application. It allows
+    /// for a separation of concerns between compute management and sensitive data.
+    ///
+    /// # Parameters
+    ///
+    /// * `vm_config` - The VM configuration as a JSON value
+    /// * `encrypted_env` - Pre-encrypted environment variables as a hex string
+    /// * `app_env_encrypt_pubkey` - The public key used for encryption
+    /// * `app_id_salt` - The salt value used for encryption
+    ///
+    /// # Returns
+    ///
+    /// A JSON value containing the deployment details
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the API request fails
     pub async fn deploy_with_encrypted_env(
         &self,
         vm_config: Value,
         encrypted_env: String,
-        app_env_encrypt_pubkey: &str,
-        app_id_salt: &str,
+        app_env_encrypt_pubkey: &AppPublicKey,
+        app_id_salt: &AppIdSalt,
     ) -> Result<DeploymentResponse> {
+        let name = vm_config["name"].as_str().unwrap_or_default().to_string();
+        let _claim = self.claim_deploy(&name)?;
         let response = self
             .client
             .deploy_with_config_encrypted_env(
@@ -630,6 +1851,60 @@ impl TeeDeployer {
         Ok(response)
     }
 
+    /// Deploys a Docker Compose application using a previously fetched public key.
+    ///
+    /// The YAML counterpart of `deploy_with_encrypted_env` for the operator/user
+    /// split: the operator obtains the key once (e.g. via
+    /// `get_pubkey_for_config`), the user encrypts their secrets with it, and
+    /// the operator deploys without the deployer fetching a new key. The VM
+    /// configuration must match the one the key was requested for.
+    ///
+    /// # Parameters
+    ///
+    /// * `docker_compose_file` - Docker Compose configuration as a YAML string
+    /// * `app_name` - Name for the deployed application
+    /// * `encrypted_env` - Environment variables already encrypted with `app_env_encrypt_pubkey`
+    /// * `app_env_encrypt_pubkey` - The public key the environment was encrypted with
+    /// * `app_id_salt` - The salt returned with the public key
+    /// * `vcpu` - Optional number of virtual CPUs (default: 1)
+    /// * `memory` - Optional memory in MB (default: 1024)
+    /// * `disk_size` - Optional disk size in GB (default: 10)
+    ///
+    /// # Returns
+    ///
+    /// A `DeploymentResponse` containing the deployment details
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// * No TEEPod has been selected (`Error::NoTeepodSelected`)
+    /// * The API request fails
+    #[allow(clippy::too_many_arguments)]
+    pub async fn deploy_compose_with_pubkey(
+        &self,
+        docker_compose_file: &str,
+        app_name: &str,
+        encrypted_env: String,
+        app_env_encrypt_pubkey: &AppPublicKey,
+        app_id_salt: &AppIdSalt,
+        vcpu: Option<u64>,
+        memory: Option<u64>,
+        disk_size: Option<u64>,
+    ) -> Result<DeploymentResponse> {
+        validate_compose(docker_compose_file)?;
+        self.ensure_teepod().await?;
+        let vm_config =
+            self.create_vm_config(docker_compose_file, app_name, vcpu, memory, disk_size)?;
+
+        self.deploy_with_encrypted_env(
+            vm_config.into(),
+            encrypted_env,
+            app_env_encrypt_pubkey,
+            app_id_salt,
+        )
+        .await
+    }
+
     /// Provisions a new ELIZA chatbot deployment.
     ///
     /// This method initiates the ELIZA deployment process by requesting an app_id
@@ -741,43 +2016,363 @@ impl TeeDeployer {
         self.client.get_system_stats(app_id).await
     }
 
+    /// Retrieves system statistics with per-call request options, such as a
+    /// longer timeout for a heavily loaded CVM.
+    pub async fn get_system_stats_with_options(
+        &self,
+        app_id: &str,
+        options: &RequestOptions,
+    ) -> Result<SystemStatsResponse> {
+        self.client
+            .get_system_stats_with_options(app_id, options)
+            .await
+    }
+
     // ─────────────────────────────────────────────────────────────────────
     // CVM lifecycle
     // ─────────────────────────────────────────────────────────────────────
 
     /// Stop a CVM (force).
     pub async fn stop(&self, app_id: &str) -> Result<CvmInfo> {
-        self.client.stop_cvm(app_id).await    }
+        self.client.stop_cvm(app_id).await
+    }
 
     /// Graceful shutdown of a CVM.
     pub async fn shutdown(&self, app_id: &str) -> Result<CvmInfo> {
-        self.client.shutdown_cvm(app_id).await    }
+        self.client.shutdown_cvm(app_id).await
+    }
+
+    /// Change the mutable advanced features (public logs, listing) of a CVM.
+    pub async fn update_settings(
+        &self,
+        app_id: &str,
+        settings: &SettingsUpdate,
+    ) -> Result<UpdateResponse> {
+        self.client.update_settings(app_id, settings).await
+    }
 
     /// Start a stopped CVM.
     pub async fn start(&self, app_id: &str) -> Result<CvmInfo> {
-        self.client.start_cvm(app_id).await    }
-
-    /// Permanently delete a CVM.
-    pub async fn delete(&self, app_id: &str) -> Result<()> {
-        self.client.delete_cvm(app_id).await    }
-
-    /// Get TEE attestation for a CVM.
-    pub async fn get_attestation(&self, app_id: &str) -> Result<AttestationResponse> {
-        self.client.get_attestation(app_id).await    }
-
-    /// Get CVM state (running, stopped, etc.).
-    pub async fn get_status(&self, app_id: &str) -> Result<CvmStateResponse> {
-        self.client.get_state(app_id).await    }
+        self.client.start_cvm(app_id).await
+    }
+
+    /// Restarts a CVM by force-stopping it and starting it again.
+    ///
+    /// Intended for recovering a wedged container. The CVM must reach the
+    /// stopped state within `timeout` before it is started.
+    ///
+    /// # Parameters
+    ///
+    /// * `app_id` - The ID of the CVM to restart
+    /// * `timeout` - Maximum time to wait for the CVM to stop
+    ///
+    /// # Returns
+    ///
+    /// The CVM info returned by the start request, carrying its final status
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// * The stop or start request fails, or a status check fails with a
+    ///   non-gateway error
+    /// * The CVM does not stop within `timeout` (`Error::Timeout`), or fails
+    ///   while stopping (`Error::UnexpectedStatus`)
+    pub async fn restart_deployment(&self, app_id: &str, timeout: Duration) -> Result<CvmInfo> {
+        self.stop(app_id).await?;
+        self.wait_for_status(
+            app_id,
+            DeploymentStatus::Stopped,
+            timeout,
+            self.poll_strategy,
+        )
+        .await?;
+        self.start(app_id).await
+    }
+
+    /// Replaces every secret of a deployment and waits until it is healthy again.
+    ///
+    /// `new_env` is encrypted with the deployment's current environment public
+    /// key and replaces the whole encrypted environment, so it must contain
+    /// every variable the deployment needs, not only the rotated ones. After
+    /// the update, the CVM status and network info are polled until the CVM
+    /// has restarted and is running and online again, within the configured
+    /// operation timeout (default: 5 minutes). A restart is observed as a
+    /// status other than running or a new network handshake; until then the
+    /// pre-update instance may still be serving the old secrets.
+    ///
+    /// # Parameters
+    ///
+    /// * `app_id` - The ID of the application whose secrets to rotate
+    /// * `new_env` - The complete new set of environment variables
+    ///
+    /// # Errors
+    ///
+    /// Returns an error whose message states which secrets are active if:
+    /// * The update is rejected, in which case the old secrets remain active
+    /// * The update request fails in transit, in which case either set may be active
+    /// * The deployment fails (`Error::DeploymentFailed`) or does not become
+    ///   healthy after the update, in which case the new secrets are active
+    /// * Polling the deployment fails with an error other than a transient
+    ///   gateway error, such as a rejected API key
+    ///
+    /// Errors keep the variant of the underlying failure, such as
+    /// `Error::Timeout`; only their message is extended.
+    pub async fn rotate_secrets(
+        &self,
+        app_id: &str,
+        new_env: HashMap<String, String>,
+    ) -> Result<()> {
+        let timeout = self.operation_timeout.unwrap_or(SECRET_ROTATION_TIMEOUT);
+        // Identifies the pre-update instance, so its health is not mistaken
+        // for the restarted one's
+        let handshake_before = self
+            .get_network_info(app_id)
+            .await
+            .ok()
+            .map(|info| info.latest_handshake);
+        if let Err(e) = self.update_deployment(app_id, None, Some(new_env)).await {
+            let active = match e {
+                Error::HttpClient(_) | Error::Timeout { .. } | Error::Serialization(_) => {
+                    "it is unknown whether the old or new secrets are active"
+                }
+                _ => "the old secrets are still active",
+            };
+            return Err(Self::rotation_error(e, active));
+        }
+
+        let start = self.clock.now();
+        let mut attempt = 0u32;
+        let mut restarted = false;
+        loop {
+            let status = match self.get_deployment_status(app_id).await {
+                Ok(status) => Some(status),
+                Err(e) if e.is_transient_gateway() => None,
+                Err(e) => return Err(Self::rotation_error(e, "the new secrets are active")),
+            };
+            match status {
+                Some(DeploymentStatus::Failed) => {
+                    return Err(Error::DeploymentFailed {
+                        app_id: app_id.to_string(),
+                        message: "secret rotation failed, the new secrets are active, but the CVM failed after the update".to_string(),
+                    });
+                }
+                Some(DeploymentStatus::Running) => match self.get_network_info(app_id).await {
+                    Ok(info) => {
+                        restarted |= handshake_before
+                            .as_ref()
+                            .is_some_and(|before| *before != info.latest_handshake);
+                        if restarted && info.is_online {
+                            return Ok(());
+                        }
+                    }
+                    Err(e) if e.is_transient_gateway() => {}
+                    Err(e) => return Err(Self::rotation_error(e, "the new secrets are active")),
+                },
+                Some(_) => restarted = true,
+                None => {}
+            }
+            if self.clock.now().duration_since(start) > timeout {
+                let progress = if restarted {
+                    "did not become healthy"
+                } else {
+                    "was not seen restarting"
+                };
+                return Err(Error::Timeout {
+                    operation: format!(
+                        "secret rotation; the new secrets are stored, but CVM {} {} within {:?}",
+                        app_id, progress, timeout
+                    ),
+                });
+            }
+            self.clock.sleep(self.poll_strategy.delay(attempt)).await;
+            attempt = attempt.saturating_add(1);
+        }
+    }
+
+    /// Prefixes the message of an error from a failed secret rotation with
+    /// which secrets are active, keeping its variant.
+    ///
+    /// Errors without a message of their own, such as `Error::HttpClient`,
+    /// are returned unchanged after logging the context.
+    fn rotation_error(e: Error, active: &str) -> Error {
+        let context = |message: String| format!("Secret rotation failed, {}: {}", active, message);
+        match e {
+            Error::Api {
+                status_code,
+                message,
+            } => Error::Api {
+                status_code,
+                message: context(message),
+            },
+            Error::UpstreamHtml {
+                status_code,
+                message,
+                body,
+            } => Error::UpstreamHtml {
+                status_code,
+                message: context(message),
+                body,
+            },
+            Error::Timeout { operation } => Error::Timeout {
+                operation: format!("secret rotation ({}): {}", active, operation),
+            },
+            Error::Configuration(message) => Error::Configuration(context(message)),
+            Error::Encryption(message) => Error::Encryption(context(message)),
+            Error::InvalidKey(message) => Error::InvalidKey(context(message)),
+            Error::Serialization(message) => Error::Serialization(context(message)),
+            other => {
+                tracing::warn!("Secret rotation failed, {}: {}", active, other);
+                other
+            }
+        }
+    }
+
+    /// Permanently delete a CVM.
+    pub async fn delete(&self, app_id: &str) -> Result<()> {
+        self.client.delete_cvm(app_id).await
+    }
+
+    /// Polls the CVM until it is gone after a deletion.
+    ///
+    /// The CVM counts as gone once its state request returns 404 or reports a
+    /// "deleted" status. Other errors while polling are ignored, matching
+    /// `wait_until_running`.
+    ///
+    /// # Parameters
+    ///
+    /// * `app_id` - The ID of the deleted CVM
+    /// * `timeout` - Maximum time to wait
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::Timeout` if the CVM still exists when the timeout expires
+    pub async fn await_deletion(&self, app_id: &str, timeout: Duration) -> Result<()> {
+        let start = self.clock.now();
+        let mut attempt = 0u32;
+        loop {
+            match self.client.get_state(app_id).await {
+                Err(Error::Api {
+                    status_code: 404, ..
+                }) => return Ok(()),
+                Ok(state) if state.status.eq_ignore_ascii_case("deleted") => return Ok(()),
+                _ => {}
+            }
+            if self.clock.now().duration_since(start) > timeout {
+                return Err(Error::Timeout {
+                    operation: format!("wait for CVM {} to be deleted ({:?})", app_id, timeout),
+                });
+            }
+            self.clock.sleep(self.poll_strategy.delay(attempt)).await;
+            attempt = attempt.saturating_add(1);
+        }
+    }
+
+    /// Get TEE attestation for a CVM.
+    pub async fn get_attestation(&self, app_id: &str) -> Result<AttestationResponse> {
+        self.client.get_attestation(app_id).await
+    }
+
+    /// Get CVM state (running, stopped, etc.).
+    pub async fn get_status(&self, app_id: &str) -> Result<CvmStateResponse> {
+        self.client.get_state(app_id).await
+    }
+
+    /// Get a page of application logs, optionally only those after `since`.
+    pub async fn get_logs(&self, app_id: &str, since: Option<&str>) -> Result<LogsResponse> {
+        self.client.get_logs(app_id, since).await
+    }
+
+    /// Follows application logs by polling with a cursor.
+    ///
+    /// The stream never ends on its own; drop it to stop following. Transient
+    /// errors are yielded as `Err` items and polling resumes after a short
+    /// delay, so a single failure does not end the stream. Use
+    /// `get_logs_stream_with_error_limit` to stop after repeated failures.
+    ///
+    /// # Parameters
+    ///
+    /// * `app_id` - The ID of the application whose logs to follow
+    ///
+    /// # Returns
+    ///
+    /// A stream of log lines in emission order
+    pub fn get_logs_stream<'a>(
+        &'a self,
+        app_id: &'a str,
+    ) -> impl Stream<Item = Result<LogLine>> + 'a {
+        self.logs_stream(app_id, None)
+    }
+
+    /// Follows application logs like `get_logs_stream`, ending the stream
+    /// after too many consecutive errors.
+    ///
+    /// Avoids polling forever when the deployment is permanently gone, e.g.
+    /// after it was deleted. Every error is still yielded; the stream ends
+    /// right after the `max_consecutive_errors`-th error in a row. A
+    /// successful poll resets the count.
+    ///
+    /// # Parameters
+    ///
+    /// * `app_id` - The ID of the application whose logs to follow
+    /// * `max_consecutive_errors` - Number of errors in a row after which the stream ends
+    ///
+    /// # Returns
+    ///
+    /// A stream of log lines in emission order
+    pub fn get_logs_stream_with_error_limit<'a>(
+        &'a self,
+        app_id: &'a str,
+        max_consecutive_errors: u32,
+    ) -> impl Stream<Item = Result<LogLine>> + 'a {
+        self.logs_stream(app_id, Some(max_consecutive_errors))
+    }
+
+    /// Builds the log-following stream, optionally bounded by consecutive errors.
+    fn logs_stream<'a>(
+        &'a self,
+        app_id: &'a str,
+        max_consecutive_errors: Option<u32>,
+    ) -> impl Stream<Item = Result<LogLine>> + 'a {
+        let state = (None::<String>, VecDeque::<LogLine>::new(), false, 0u32);
+        stream::unfold(
+            state,
+            move |(mut cursor, mut pending, mut wait, errors)| async move {
+                loop {
+                    if let Some(line) = pending.pop_front() {
+                        return Some((Ok(line), (cursor, pending, false, errors)));
+                    }
+                    if max_consecutive_errors.is_some_and(|max| errors >= max) {
+                        return None;
+                    }
+                    if wait {
+                        self.clock.sleep(LOG_POLL_INTERVAL).await;
+                    }
+                    match self.client.get_logs(app_id, cursor.as_deref()).await {
+                        Ok(page) => {
+                            cursor = page.next_cursor.or(cursor);
+                            pending.extend(page.lines);
+                            wait = pending.is_empty();
+                        }
+                        Err(e) => {
+                            return Some((Err(e), (cursor, pending, true, errors + 1)));
+                        }
+                    }
+                }
+            },
+        )
+    }
 
     /// Poll until the CVM reaches "running" state or the timeout expires.
+    ///
+    /// Polls follow the strategy set with `TeeDeployerBuilder::with_poll_strategy`.
     pub async fn wait_until_running(&self, app_id: &str, timeout: Duration) -> Result<()> {
-        let start = std::time::Instant::now();
+        let start = self.clock.now();
+        let mut attempt = 0u32;
         loop {
-            if start.elapsed() > timeout {
-                return Err(Error::Api {
-                    status_code: 408,
-                    message: format!(
-                        "CVM {} did not reach running state within {:?}",
+            if self.clock.now().duration_since(start) > timeout {
+                return Err(Error::Timeout {
+                    operation: format!(
+                        "wait for CVM {} to reach running state ({:?})",
                         app_id, timeout
                     ),
                 });
@@ -787,7 +2382,392 @@ impl TeeDeployer {
                 Ok(_) => {}
                 Err(_) => {}
             }
-            tokio::time::sleep(Duration::from_secs(2)).await;
+            self.clock.sleep(self.poll_strategy.delay(attempt)).await;
+            attempt = attempt.saturating_add(1);
+        }
+    }
+
+    /// Polls the network info until the CVM reports itself online.
+    ///
+    /// Transient gateway errors (502-504, often HTML pages from the proxy
+    /// while the CVM initializes) count as "not online yet"; any other error,
+    /// such as a rejected API key, ends the wait.
+    ///
+    /// # Parameters
+    ///
+    /// * `app_id` - The ID of the application to wait for
+    /// * `timeout` - Maximum time to wait
+    /// * `poll` - Delay schedule between checks; a plain `Duration` polls at a fixed interval
+    ///
+    /// # Returns
+    ///
+    /// The first network info reporting the CVM online
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a request fails with a non-gateway error, or
+    /// `Error::Timeout` if the CVM is not online within `timeout`
+    pub async fn wait_until_online(
+        &self,
+        app_id: &str,
+        timeout: Duration,
+        poll: impl Into<PollStrategy>,
+    ) -> Result<NetworkInfoResponse> {
+        self.poll_until_ready(
+            timeout,
+            poll.into(),
+            || self.get_network_info(app_id),
+            |info| info.is_online,
+            || format!("wait for CVM {} to come online ({:?})", app_id, timeout),
+        )
+        .await
+    }
+
+    /// Polls the system stats until the CVM reports itself online.
+    ///
+    /// Handles transient gateway errors like `wait_until_online`.
+    ///
+    /// # Parameters
+    ///
+    /// * `app_id` - The ID of the application to wait for
+    /// * `timeout` - Maximum time to wait
+    /// * `poll` - Delay schedule between checks
+    ///
+    /// # Returns
+    ///
+    /// The first system stats reporting the CVM online
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a request fails with a non-gateway error, or
+    /// `Error::Timeout` if no stats are available within `timeout`
+    pub async fn wait_for_system_stats(
+        &self,
+        app_id: &str,
+        timeout: Duration,
+        poll: impl Into<PollStrategy>,
+    ) -> Result<SystemStatsResponse> {
+        self.poll_until_ready(
+            timeout,
+            poll.into(),
+            || self.get_system_stats(app_id),
+            |stats| stats.is_online,
+            || format!("wait for system stats of CVM {} ({:?})", app_id, timeout),
+        )
+        .await
+    }
+
+    /// Calls `fetch` on the `poll` schedule until its result is `ready`,
+    /// treating transient gateway errors as not ready yet.
+    async fn poll_until_ready<T, Fut>(
+        &self,
+        timeout: Duration,
+        poll: PollStrategy,
+        fetch: impl Fn() -> Fut,
+        ready: impl Fn(&T) -> bool,
+        operation: impl Fn() -> String,
+    ) -> Result<T>
+    where
+        Fut: std::future::Future<Output = Result<T>>,
+    {
+        let start = self.clock.now();
+        let mut attempt = 0u32;
+        loop {
+            match fetch().await {
+                Ok(value) if ready(&value) => return Ok(value),
+                Ok(_) => {}
+                Err(e) if e.is_transient_gateway() => {
+                    tracing::debug!("Gateway not ready yet, retrying: {}", e);
+                }
+                Err(e) => return Err(e),
+            }
+            if self.clock.now().duration_since(start) > timeout {
+                return Err(Error::Timeout {
+                    operation: operation(),
+                });
+            }
+            self.clock.sleep(poll.delay(attempt)).await;
+            attempt = attempt.saturating_add(1);
+        }
+    }
+
+    /// Lists deployments whose labels contain every entry in `labels`.
+    ///
+    /// Labels are attached client-side when deploying. If the API does not
+    /// persist them, deployments will carry no labels and only an empty filter
+    /// matches; pass an empty map to list every deployment.
+    ///
+    /// # Parameters
+    ///
+    /// * `labels` - Label key/value pairs that must all be present
+    ///
+    /// # Returns
+    ///
+    /// The matching deployments
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the API request fails
+    pub async fn list_deployments(&self, labels: &HashMap<String, String>) -> Result<Vec<CvmInfo>> {
+        let cvms = self.client.list_cvms().await?;
+
+        Ok(cvms
+            .into_iter()
+            .filter(|cvm| {
+                labels.iter().all(|(key, value)| {
+                    cvm.extra
+                        .get("labels")
+                        .and_then(|l| l.get(key))
+                        .and_then(|v| v.as_str())
+                        == Some(value.as_str())
+                })
+            })
+            .collect())
+    }
+
+    /// Get the parsed lifecycle status of a CVM.
+    pub async fn get_deployment_status(&self, app_id: &str) -> Result<DeploymentStatus> {
+        self.deployment_status(app_id, &RequestOptions::default())
+            .await
+    }
+
+    /// Fetches the parsed lifecycle status of a CVM with request options.
+    async fn deployment_status(
+        &self,
+        app_id: &str,
+        options: &RequestOptions,
+    ) -> Result<DeploymentStatus> {
+        let state = self.client.get_state_with_options(app_id, options).await?;
+        Ok(DeploymentStatus::parse(&state.status))
+    }
+
+    /// Polls the CVM status until it reaches `target` or another terminal status.
+    ///
+    /// Transient gateway errors (502-504) count as "not there yet", like in
+    /// `wait_until_online`; any other error, such as a rejected API key or an
+    /// unknown CVM, ends the wait.
+    ///
+    /// # Parameters
+    ///
+    /// * `app_id` - The ID of the CVM to watch
+    /// * `target` - The status to wait for
+    /// * `timeout` - Maximum time to wait
+    /// * `poll` - Delay schedule between status checks; a plain `Duration` polls at a fixed interval
+    ///
+    /// # Returns
+    ///
+    /// The reached status, which equals `target`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// * The timeout expires before the target is reached (`Error::Timeout`)
+    /// * The CVM settles in a terminal status other than `target`
+    ///   (`Error::UnexpectedStatus`)
+    /// * A status request fails with a non-gateway error
+    pub async fn wait_for_status(
+        &self,
+        app_id: &str,
+        target: DeploymentStatus,
+        timeout: Duration,
+        poll: impl Into<PollStrategy>,
+    ) -> Result<DeploymentStatus> {
+        self.wait_for_status_with_options(app_id, target, timeout, poll, &RequestOptions::default())
+            .await
+    }
+
+    /// Polls the CVM status like `wait_for_status`, applying `options` to each
+    /// status request.
+    ///
+    /// `timeout` bounds the whole wait, while a timeout in `options` bounds
+    /// each individual status request.
+    ///
+    /// # Parameters
+    ///
+    /// * `app_id` - The ID of the CVM to watch
+    /// * `target` - The status to wait for
+    /// * `timeout` - Maximum time to wait
+    /// * `poll` - Delay schedule between status checks
+    /// * `options` - Overrides for the client defaults
+    ///
+    /// # Returns
+    ///
+    /// The reached status, which equals `target`
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as `wait_for_status`
+    pub async fn wait_for_status_with_options(
+        &self,
+        app_id: &str,
+        target: DeploymentStatus,
+        timeout: Duration,
+        poll: impl Into<PollStrategy>,
+        options: &RequestOptions,
+    ) -> Result<DeploymentStatus> {
+        let poll = poll.into();
+        let start = self.clock.now();
+        let mut attempt = 0u32;
+        loop {
+            match self.deployment_status(app_id, options).await {
+                Ok(status) if status == target => return Ok(status),
+                Ok(status) if status.is_terminal() => {
+                    return Err(Error::UnexpectedStatus {
+                        app_id: app_id.to_string(),
+                        expected: target,
+                        actual: status,
+                    });
+                }
+                Ok(_) => {}
+                Err(e) if e.is_transient_gateway() => {
+                    tracing::debug!("Gateway not ready yet, retrying: {}", e);
+                }
+                Err(e) => return Err(e),
+            }
+            if self.clock.now().duration_since(start) > timeout {
+                return Err(Error::Timeout {
+                    operation: format!(
+                        "wait for CVM {} to reach '{}' state ({:?})",
+                        app_id, target, timeout
+                    ),
+                });
+            }
+            self.clock.sleep(poll.delay(attempt)).await;
+            attempt = attempt.saturating_add(1);
+        }
+    }
+
+    /// Polls the CVM status until it is terminal, collecting logs along the way.
+    ///
+    /// Meant for CI: a deployment that fails returns the logs explaining why.
+    /// Logs are fetched after each status check, so the lines leading up to
+    /// the terminal status are included. Transient errors while polling are
+    /// ignored, matching `wait_until_running`.
+    ///
+    /// # Parameters
+    ///
+    /// * `app_id` - The ID of the CVM to watch
+    /// * `timeout` - Maximum time to wait
+    ///
+    /// # Returns
+    ///
+    /// The terminal status (running, stopped or failed) and every log line
+    /// collected, in emission order
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::Timeout` if the timeout expires before a terminal
+    /// status is reached; its message includes the last collected log lines
+    pub async fn watch_until_terminal(
+        &self,
+        app_id: &str,
+        timeout: Duration,
+    ) -> Result<(DeploymentStatus, Vec<LogLine>)> {
+        self.watch_until_terminal_with_options(app_id, timeout, &RequestOptions::default())
+            .await
+    }
+
+    /// Watches the CVM like `watch_until_terminal`, applying `options` to each
+    /// status request.
+    ///
+    /// # Parameters
+    ///
+    /// * `app_id` - The ID of the CVM to watch
+    /// * `timeout` - Maximum time to wait
+    /// * `options` - Overrides for the client defaults
+    ///
+    /// # Returns
+    ///
+    /// The terminal status and every log line collected
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as `watch_until_terminal`
+    pub async fn watch_until_terminal_with_options(
+        &self,
+        app_id: &str,
+        timeout: Duration,
+        options: &RequestOptions,
+    ) -> Result<(DeploymentStatus, Vec<LogLine>)> {
+        let start = self.clock.now();
+        let mut cursor = None::<String>;
+        let mut logs = Vec::new();
+        loop {
+            let status = self.deployment_status(app_id, options).await.ok();
+            if let Ok(page) = self.client.get_logs(app_id, cursor.as_deref()).await {
+                cursor = page.next_cursor.or(cursor);
+                logs.extend(page.lines);
+            }
+            if let Some(status) = status.filter(DeploymentStatus::is_terminal) {
+                return Ok((status, logs));
+            }
+            if self.clock.now().duration_since(start) > timeout {
+                let tail = logs[logs.len().saturating_sub(WATCH_TIMEOUT_LOG_TAIL)..]
+                    .iter()
+                    .map(|line| format!("\n  {}", line.message))
+                    .collect::<String>();
+                return Err(Error::Timeout {
+                    operation: format!(
+                        "wait for CVM {} to reach a terminal state ({:?}); collected {} log lines{}",
+                        app_id,
+                        timeout,
+                        logs.len(),
+                        tail
+                    ),
+                });
+            }
+            self.clock.sleep(LOG_POLL_INTERVAL).await;
+        }
+    }
+
+    /// Applies Compose Spec normalization to a compose file before it is sent.
+    ///
+    /// CRLF line endings are converted to LF and a top-level `version` is
+    /// removed, unless disabled with `TeeDeployerBuilder::with_normalize_line_endings`
+    /// and `TeeDeployerBuilder::with_strip_compose_version`; either way a
+    /// warning is logged. YAML anchors are expanded if enabled with
+    /// `TeeDeployerBuilder::with_expand_anchors`.
+    fn normalize_compose(&self, docker_compose_file: &str) -> String {
+        let docker_compose_file = self.normalize_compose_text(docker_compose_file);
+        if !self.expand_anchors {
+            return docker_compose_file;
+        }
+        match expand_yaml_anchors(&docker_compose_file) {
+            Ok(Some(expanded)) => {
+                tracing::warn!("Expanded YAML anchors and aliases in compose file");
+                expanded
+            }
+            Ok(None) => docker_compose_file,
+            Err(e) => {
+                tracing::warn!("Could not expand YAML anchors in compose file: {}", e);
+                docker_compose_file
+            }
+        }
+    }
+
+    /// Line ending and `version` normalization of `normalize_compose`.
+    fn normalize_compose_text(&self, docker_compose_file: &str) -> String {
+        let mut docker_compose_file = docker_compose_file.to_string();
+        if docker_compose_file.contains("\r\n") {
+            if self.normalize_line_endings {
+                tracing::warn!("Converting CRLF line endings in compose file to LF");
+                docker_compose_file = docker_compose_file.replace("\r\n", "\n");
+            } else {
+                tracing::warn!("Compose file uses CRLF line endings; keeping them as configured");
+            }
+        }
+        match strip_compose_version(&docker_compose_file) {
+            Some(stripped) if self.strip_compose_version => {
+                tracing::warn!("Removing obsolete top-level `version` from compose file");
+                stripped
+            }
+            Some(_) => {
+                tracing::warn!(
+                    "Compose file sets obsolete top-level `version`; keeping it as configured"
+                );
+                docker_compose_file
+            }
+            None => docker_compose_file,
         }
     }
 
@@ -819,7 +2799,43 @@ impl TeeDeployer {
 /// ```
 pub struct TeeDeployerBuilder {
     api_key: Option<String>,
+    api_key_file: Option<PathBuf>,
     api_endpoint: Option<String>,
+    api_version: Option<String>,
+    danger_accept_invalid_certs: bool,
+    compress_requests: bool,
+    strict: bool,
+    request_timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+    prefer_production: bool,
+    listed: Option<bool>,
+    labels: HashMap<String, String>,
+    runner: Runner,
+    kernel_cmdline_append: Option<String>,
+    vm_extra: serde_json::Map<String, Value>,
+    discovery_cache_ttl: Option<Duration>,
+    price_table: Option<PriceTable>,
+    user_id: Option<String>,
+    clock: Option<Arc<dyn Clock>>,
+    strip_compose_version: bool,
+    normalize_line_endings: bool,
+    expand_anchors: bool,
+    strict_env_check: bool,
+    operation_timeout: Option<Duration>,
+    poll_strategy: PollStrategy,
+    upload_progress: Option<UploadProgress>,
+    min_resource_score: f64,
+    name_template: Option<String>,
+    check_image: bool,
+    auto_discover: bool,
+    deploy_guard: bool,
+    deploy_retries: u32,
+}
+
+impl Default for TeeDeployerBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl TeeDeployerBuilder {
@@ -831,7 +2847,37 @@ impl TeeDeployerBuilder {
     pub fn new() -> Self {
         Self {
             api_key: None,
+            api_key_file: None,
             api_endpoint: None,
+            api_version: None,
+            danger_accept_invalid_certs: false,
+            compress_requests: false,
+            strict: false,
+            request_timeout: None,
+            connect_timeout: None,
+            prefer_production: true,
+            listed: None,
+            labels: HashMap::new(),
+            runner: Runner::default(),
+            kernel_cmdline_append: None,
+            vm_extra: serde_json::Map::new(),
+            discovery_cache_ttl: None,
+            price_table: None,
+            user_id: None,
+            clock: None,
+            strip_compose_version: true,
+            normalize_line_endings: true,
+            expand_anchors: false,
+            strict_env_check: false,
+            operation_timeout: None,
+            poll_strategy: PollStrategy::Fixed(DEFAULT_POLL_INTERVAL),
+            upload_progress: None,
+            min_resource_score: 0.0,
+            name_template: None,
+            check_image: true,
+            auto_discover: false,
+            deploy_guard: false,
+            deploy_retries: 0,
         }
     }
 
@@ -849,6 +2895,24 @@ impl TeeDeployerBuilder {
         self
     }
 
+    /// Reads the API key from a file when the deployer is built.
+    ///
+    /// Suits secrets mounted as files by container orchestrators, keeping the
+    /// key out of the process environment. Surrounding whitespace is trimmed.
+    /// A key set with `with_api_key` takes precedence over the file.
+    ///
+    /// # Parameters
+    ///
+    /// * `path` - Path of the file containing the API key
+    ///
+    /// # Returns
+    ///
+    /// The builder instance for method chaining
+    pub fn with_api_key_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.api_key_file = Some(path.into());
+        self
+    }
+
     /// Sets a custom API endpoint URL.
     ///
     /// # Parameters
@@ -863,7 +2927,532 @@ impl TeeDeployerBuilder {
         self
     }
 
-    /// Builds a `TeeDeployer` instance with the configured parameters.
+    /// Sets the version of the default Phala Cloud API to use.
+    ///
+    /// Ignored when a full endpoint URL is set with `with_api_endpoint`. See
+    /// `DeploymentConfig::with_api_version`.
+    ///
+    /// # Parameters
+    ///
+    /// * `api_version` - The API version, e.g. `"v2"` (default: `"v1"`)
+    ///
+    /// # Returns
+    ///
+    /// The builder instance for method chaining
+    pub fn with_api_version(mut self, api_version: impl Into<String>) -> Self {
+        self.api_version = Some(api_version.into());
+        self
+    }
+
+    /// Disables TLS certificate verification for all API requests.
+    ///
+    /// **Development only.** This allows connecting to a local Phala stack served
+    /// over a self-signed certificate. Never enable it against a production
+    /// endpoint; a warning is logged whenever a client is built with it set.
+    ///
+    /// # Parameters
+    ///
+    /// * `accept` - Whether invalid TLS certificates should be accepted
+    ///
+    /// # Returns
+    ///
+    /// The builder instance for method chaining
+    pub fn danger_accept_invalid_certs(mut self, accept: bool) -> Self {
+        self.danger_accept_invalid_certs = accept;
+        self
+    }
+
+    /// Enables gzip compression of JSON request bodies.
+    ///
+    /// Reduces upload time for large compose payloads. Only enable it against
+    /// endpoints that accept `Content-Encoding: gzip`.
+    ///
+    /// # Parameters
+    ///
+    /// * `compress` - Whether request bodies should be gzip-compressed
+    ///
+    /// # Returns
+    ///
+    /// The builder instance for method chaining
+    pub fn with_compress_requests(mut self, compress: bool) -> Self {
+        self.compress_requests = compress;
+        self
+    }
+
+    /// Enables strict parsing of API responses.
+    ///
+    /// A debugging aid: unknown response fields fail with `Error::Serialization`
+    /// naming the field instead of being ignored. See
+    /// `DeploymentConfig::with_strict`.
+    ///
+    /// # Parameters
+    ///
+    /// * `strict` - Whether unknown response fields should be rejected
+    ///
+    /// # Returns
+    ///
+    /// The builder instance for method chaining
+    pub fn with_strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Sets the default timeout for API requests.
+    ///
+    /// Calls that need more or less time can override it with the
+    /// `*_with_options` method variants. See
+    /// `DeploymentConfig::with_request_timeout`.
+    ///
+    /// # Parameters
+    ///
+    /// * `timeout` - Timeout applied to each request (default: 30 seconds)
+    ///
+    /// # Returns
+    ///
+    /// The builder instance for method chaining
+    pub fn with_request_timeout(mut self, timeout: Duration) -> Self {
+        self.request_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the timeout for establishing a connection to the API.
+    ///
+    /// See `DeploymentConfig::with_connect_timeout`.
+    ///
+    /// # Parameters
+    ///
+    /// * `timeout` - Timeout for connecting (default: 5 seconds)
+    ///
+    /// # Returns
+    ///
+    /// The builder instance for method chaining
+    pub fn with_connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Controls whether TEEPod discovery avoids development images.
+    ///
+    /// Enabled by default. When set, `discover_teepod()` and `select_teepod()`
+    /// skip images flagged `is_dev` whenever a production image is available.
+    ///
+    /// # Parameters
+    ///
+    /// * `prefer_production` - Whether production images should be preferred
+    ///
+    /// # Returns
+    ///
+    /// The builder instance for method chaining
+    pub fn with_prefer_production(mut self, prefer_production: bool) -> Self {
+        self.prefer_production = prefer_production;
+        self
+    }
+
+    /// Sets the minimum resource score a node needs to be picked by
+    /// `discover_teepod()`, to avoid landing on overloaded TEEPods.
+    ///
+    /// Defaults to 0.0, which accepts every node.
+    ///
+    /// # Parameters
+    ///
+    /// * `min_score` - Lowest acceptable `resource_score`
+    ///
+    /// # Returns
+    ///
+    /// The builder instance for method chaining
+    pub fn with_min_resource_score(mut self, min_score: f64) -> Self {
+        self.min_resource_score = min_score;
+        self
+    }
+
+    /// Derives VM names from a template, such as "team-{name}-prod".
+    ///
+    /// `{name}` is replaced by the app name passed to the deploy methods.
+    /// The resulting name must be at most 63 characters of ASCII letters,
+    /// digits, `-` and `_`, starting with a letter or digit.
+    ///
+    /// # Parameters
+    ///
+    /// * `template` - Name template containing the `{name}` placeholder
+    ///
+    /// # Returns
+    ///
+    /// The builder instance for method chaining
+    pub fn with_name_template(mut self, template: impl Into<String>) -> Self {
+        self.name_template = Some(template.into());
+        self
+    }
+
+    /// Sets whether deployments are listed in public directories.
+    ///
+    /// Deployments are unlisted by default for privacy; opt in explicitly to
+    /// make them discoverable. The value is applied to
+    /// `advanced_features.listed` on every VM configuration the deployer creates.
+    ///
+    /// # Parameters
+    ///
+    /// * `listed` - Whether deployments should be publicly listed
+    ///
+    /// # Returns
+    ///
+    /// The builder instance for method chaining
+    pub fn with_listed(mut self, listed: bool) -> Self {
+        self.listed = Some(listed);
+        self
+    }
+
+    /// Adds a label attached to every deployment created by the deployer.
+    ///
+    /// Labels (team, environment, cost-center, ...) are sent as `labels` in the
+    /// VM configuration and echoed in `DeploymentResponse.details`. The API may
+    /// ignore them, in which case they are client-side metadata only.
+    ///
+    /// # Parameters
+    ///
+    /// * `key` - Label name
+    /// * `value` - Label value
+    ///
+    /// # Returns
+    ///
+    /// The builder instance for method chaining
+    pub fn with_label(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.labels.insert(key.into(), value.into());
+        self
+    }
+
+    /// Sets the runner used to launch deployed applications.
+    ///
+    /// Defaults to `Runner::DockerCompose`. Use `Runner::Other` to target a
+    /// runner the platform supports but this client does not model yet.
+    ///
+    /// # Parameters
+    ///
+    /// * `runner` - The runner written to the compose manifest
+    ///
+    /// # Returns
+    ///
+    /// The builder instance for method chaining
+    pub fn with_runner(mut self, runner: Runner) -> Self {
+        if let Runner::Other(name) = &runner {
+            tracing::warn!("Using non-default runner '{}'", name);
+        }
+        self.runner = runner;
+        self
+    }
+
+    /// Sets whether an obsolete top-level `version` is removed from compose files.
+    ///
+    /// Enabled by default: the platform follows the Compose Spec, which no
+    /// longer uses `version`. A warning is logged whenever the key is found.
+    ///
+    /// # Parameters
+    ///
+    /// * `strip` - Whether to remove the key before deploying or updating
+    ///
+    /// # Returns
+    ///
+    /// The builder instance for method chaining
+    pub fn with_strip_compose_version(mut self, strip: bool) -> Self {
+        self.strip_compose_version = strip;
+        self
+    }
+
+    /// Sets whether CRLF line endings in compose files are converted to LF.
+    ///
+    /// Enabled by default: compose files authored on Windows carry `\r\n`,
+    /// which breaks heredocs and shell commands embedded in the YAML once they
+    /// run inside the container. A warning is logged whenever CRLF is found.
+    ///
+    /// # Parameters
+    ///
+    /// * `normalize` - Whether to convert line endings before deploying or updating
+    ///
+    /// # Returns
+    ///
+    /// The builder instance for method chaining
+    pub fn with_normalize_line_endings(mut self, normalize: bool) -> Self {
+        self.normalize_line_endings = normalize;
+        self
+    }
+
+    /// Sets whether YAML anchors and aliases in compose files are expanded.
+    ///
+    /// Disabled by default. When enabled, compose files using anchors
+    /// (`&name`), aliases (`*name`) or `<<` merge keys are round-tripped
+    /// through a YAML parser before upload, so the platform receives concrete
+    /// values. Comments are not preserved. A warning is logged whenever
+    /// anchors are expanded.
+    ///
+    /// # Parameters
+    ///
+    /// * `expand` - Whether to expand anchors before deploying or updating
+    ///
+    /// # Returns
+    ///
+    /// The builder instance for method chaining
+    pub fn with_expand_anchors(mut self, expand: bool) -> Self {
+        self.expand_anchors = expand;
+        self
+    }
+
+    /// Makes unresolved compose variable references fail deployment.
+    ///
+    /// Before deploying, `${VAR}` references without a default are checked
+    /// against the supplied environment variables. By default unresolved ones
+    /// are only logged as a warning; with this enabled the deploy fails with
+    /// `Error::Configuration` listing them.
+    ///
+    /// # Parameters
+    ///
+    /// * `strict` - Whether unresolved references are an error
+    ///
+    /// # Returns
+    ///
+    /// The builder instance for method chaining
+    pub fn with_strict_env_check(mut self, strict: bool) -> Self {
+        self.strict_env_check = strict;
+        self
+    }
+
+    /// Controls the preflight check that a VM configuration's image is
+    /// offered by the selected TEEPod.
+    ///
+    /// Enabled by default. `deploy_config` and `deploy_from_plan` then reject
+    /// a configuration for the selected TEEPod whose `image` the node does not
+    /// offer, instead of letting the deployment fail server-side.
+    ///
+    /// # Parameters
+    ///
+    /// * `check` - Whether the image is checked before deploying
+    ///
+    /// # Returns
+    ///
+    /// The builder instance for method chaining
+    pub fn with_image_check(mut self, check: bool) -> Self {
+        self.check_image = check;
+        self
+    }
+
+    /// Selects a TEEPod automatically on the first deploy if none was selected.
+    ///
+    /// Disabled by default. When enabled, `deploy_compose` and the deploy
+    /// methods built on it run discovery once, following the same rules as
+    /// `discover_teepod()`, and reuse the chosen TEEPod for later deploys.
+    /// Calling `discover_teepod()` or `select_teepod()` still takes precedence.
+    ///
+    /// # Parameters
+    ///
+    /// * `auto_discover` - Whether deploys discover a TEEPod when none is selected
+    ///
+    /// # Returns
+    ///
+    /// The builder instance for method chaining
+    pub fn with_auto_discover(mut self, auto_discover: bool) -> Self {
+        self.auto_discover = auto_discover;
+        self
+    }
+
+    /// Rejects a deploy while another deploy of the same app name is running.
+    ///
+    /// Disabled by default. A safety net against callers accidentally
+    /// deploying the same app twice concurrently, which would create
+    /// duplicates; the second deploy fails with `Error::Configuration`.
+    ///
+    /// # Parameters
+    ///
+    /// * `guard` - Whether to reject concurrent deploys of the same app name
+    ///
+    /// # Returns
+    ///
+    /// The builder instance for method chaining
+    pub fn with_deploy_guard(mut self, guard: bool) -> Self {
+        self.deploy_guard = guard;
+        self
+    }
+
+    /// Retries the deployment request after ambiguous failures.
+    ///
+    /// Disabled (0) by default. A deploy that times out, loses its connection
+    /// or hits a gateway error may still have created the CVM, so before each
+    /// retry the deployer lists CVMs and, if a CVM with the app's name
+    /// appeared since the deploy started, returns it instead of deploying a
+    /// duplicate. Enabling retries adds one CVM list request to each deploy.
+    /// Retries are spaced by the schedule set with `with_poll_strategy`.
+    ///
+    /// # Parameters
+    ///
+    /// * `retries` - Maximum number of retries after the first attempt
+    ///
+    /// # Returns
+    ///
+    /// The builder instance for method chaining
+    pub fn with_deploy_retries(mut self, retries: u32) -> Self {
+        self.deploy_retries = retries;
+        self
+    }
+
+    /// Bounds the total duration of multi-step operations.
+    ///
+    /// Deploying (pubkey, then deploy) and updating (fetch compose, then
+    /// update) each make sequential requests with their own request timeouts.
+    /// With an operation timeout, the next request is not started once the
+    /// whole operation has exceeded `timeout`; an `Error::Timeout` is
+    /// returned instead. A request already in flight is not cut short.
+    ///
+    /// # Parameters
+    ///
+    /// * `timeout` - Overall time budget per operation
+    ///
+    /// # Returns
+    ///
+    /// The builder instance for method chaining
+    pub fn with_operation_timeout(mut self, timeout: Duration) -> Self {
+        self.operation_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the delay schedule between polls of the deployer's waits.
+    ///
+    /// Applies to `wait_until_running`, `await_deletion`,
+    /// `restart_deployment`, `rotate_secrets` and the back-off between deploy
+    /// retries; `wait_for_status` and
+    /// `wait_until_online` take a schedule per call instead. Defaults to a
+    /// fixed two-second interval.
+    ///
+    /// # Parameters
+    ///
+    /// * `poll` - Delay schedule; a plain `Duration` polls at a fixed interval
+    ///
+    /// # Returns
+    ///
+    /// The builder instance for method chaining
+    pub fn with_poll_strategy(mut self, poll: impl Into<PollStrategy>) -> Self {
+        self.poll_strategy = poll.into();
+        self
+    }
+
+    /// Reports upload progress of request bodies, such as large deploy payloads.
+    ///
+    /// See `TeeClient::with_upload_progress` for when `callback` is invoked.
+    ///
+    /// # Parameters
+    ///
+    /// * `callback` - Called with `(bytes_sent, total_bytes)`
+    ///
+    /// # Returns
+    ///
+    /// The builder instance for method chaining
+    pub fn with_upload_progress(mut self, callback: UploadProgress) -> Self {
+        self.upload_progress = Some(callback);
+        self
+    }
+
+    /// Appends extra kernel command-line arguments to every VM the deployer creates.
+    ///
+    /// Only honoured where the platform permits per-deployment cmdline
+    /// additions, e.g. for debugging. Not set by default.
+    ///
+    /// # Parameters
+    ///
+    /// * `cmdline` - Arguments appended to the image's kernel `cmdline`
+    ///
+    /// # Returns
+    ///
+    /// The builder instance for method chaining
+    pub fn with_kernel_cmdline_append(mut self, cmdline: impl Into<String>) -> Self {
+        self.kernel_cmdline_append = Some(cmdline.into());
+        self
+    }
+
+    /// Adds a raw top-level field to every VM configuration the deployer creates.
+    ///
+    /// Use this for platform fields this crate does not model yet; see
+    /// `VmConfig::with_extra`.
+    ///
+    /// # Parameters
+    ///
+    /// * `key` - Name of the top-level field
+    /// * `value` - JSON value of the field
+    ///
+    /// # Returns
+    ///
+    /// The builder instance for method chaining
+    pub fn with_vm_extra(mut self, key: impl Into<String>, value: Value) -> Self {
+        self.vm_extra.insert(key.into(), value);
+        self
+    }
+
+    /// Caches TEEPod discovery responses for the given time-to-live.
+    ///
+    /// Disabled by default. When set, `discover_teepod()` and `select_teepod()`
+    /// reuse a response fetched within the TTL instead of querying
+    /// `/teepods/available` again, which reduces API load for batch operations.
+    /// Call `refresh_teepods()` to force a fresh fetch.
+    ///
+    /// # Parameters
+    ///
+    /// * `ttl` - How long a discovery response stays valid
+    ///
+    /// # Returns
+    ///
+    /// The builder instance for method chaining
+    pub fn with_discovery_cache_ttl(mut self, ttl: Duration) -> Self {
+        self.discovery_cache_ttl = Some(ttl);
+        self
+    }
+
+    /// Sets the price table used by `TeeDeployer::estimate_cost`.
+    ///
+    /// # Parameters
+    ///
+    /// * `price_table` - Resource prices per service tier
+    ///
+    /// # Returns
+    ///
+    /// The builder instance for method chaining
+    pub fn with_price_table(mut self, price_table: PriceTable) -> Self {
+        self.price_table = Some(price_table);
+        self
+    }
+
+    /// Deploys on behalf of a sub-user of the API key's account.
+    ///
+    /// For operators that manage deployments for other users: the ID is sent
+    /// as the top-level `user_id` field of every VM configuration the deployer
+    /// creates, and the user the platform attributes a deployment to is
+    /// echoed as `user_id` in `DeploymentResponse.details` for auditing. Has no
+    /// effect if the platform does not support delegation.
+    ///
+    /// # Parameters
+    ///
+    /// * `user_id` - ID of the user to deploy for
+    ///
+    /// # Returns
+    ///
+    /// The builder instance for method chaining
+    pub fn deploy_as_user(mut self, user_id: impl Into<String>) -> Self {
+        self.user_id = Some(user_id.into());
+        self
+    }
+
+    /// Sets the clock used for polling delays, timeouts, and cache expiry.
+    ///
+    /// Defaults to `TokioClock`. Inject a fake clock in tests to step through
+    /// timeouts and backoff schedules without real sleeping.
+    ///
+    /// # Parameters
+    ///
+    /// * `clock` - The time source to use
+    ///
+    /// # Returns
+    ///
+    /// The builder instance for method chaining
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Some(Arc::new(clock));
+        self
+    }
+
+    /// Builds a `TeeDeployer` instance with the configured parameters.
     ///
     /// # Returns
     ///
@@ -871,12 +3460,77 @@ impl TeeDeployerBuilder {
     ///
     /// # Errors
     ///
-    /// Returns an error if the API key is not set or if the `TeeDeployer` creation fails
+    /// Returns an error if the API key is not set, the API key file cannot be
+    /// read, the key is empty or whitespace-only, the kernel cmdline append
+    /// contains newlines, or if the `TeeDeployer` creation fails
     pub fn build(self) -> Result<TeeDeployer> {
-        let api_key = self
-            .api_key
-            .ok_or_else(|| Error::Configuration("API key is required".into()))?;
+        let api_key = match (self.api_key, &self.api_key_file) {
+            (Some(api_key), _) => api_key,
+            (None, Some(path)) => std::fs::read_to_string(path)
+                .map(|key| key.trim().to_string())
+                .map_err(|e| {
+                    Error::Configuration(format!(
+                        "Failed to read API key file {}: {}",
+                        path.display(),
+                        e
+                    ))
+                })?,
+            (None, None) => return Err(Error::Configuration("API key is required".into())),
+        };
+        if api_key.trim().is_empty() {
+            return Err(Error::Configuration("API key is empty".into()));
+        }
+
+        let mut config = TeeDeployer::base_config(api_key, self.api_endpoint);
+        if let Some(api_version) = self.api_version {
+            config = config.with_api_version(api_version);
+        }
+        let config = config
+            .with_danger_accept_invalid_certs(self.danger_accept_invalid_certs)
+            .with_compress_requests(self.compress_requests)
+            .with_strict(self.strict)
+            .with_request_timeout(self.request_timeout.unwrap_or(DEFAULT_REQUEST_TIMEOUT))
+            .with_connect_timeout(self.connect_timeout.unwrap_or(DEFAULT_CONNECT_TIMEOUT));
 
-        TeeDeployer::new(api_key, self.api_endpoint)
+        let mut deployer = TeeDeployer::with_config(config)?;
+        deployer.prefer_production = self.prefer_production;
+        deployer.min_resource_score = self.min_resource_score;
+        if let Some(template) = &self.name_template {
+            if !template.contains("{name}") {
+                return Err(Error::Configuration(format!(
+                    "Name template '{}' must contain the {{name}} placeholder",
+                    template
+                )));
+            }
+        }
+        deployer.name_template = self.name_template;
+        deployer.check_image = self.check_image;
+        deployer.auto_discover = self.auto_discover;
+        deployer.deploy_guard = self.deploy_guard;
+        deployer.deploy_retries = self.deploy_retries;
+        deployer.listed = self.listed.unwrap_or(false);
+        deployer.labels = self.labels;
+        deployer.runner = self.runner;
+        if let Some(cmdline) = &self.kernel_cmdline_append {
+            check_kernel_cmdline_append(cmdline)?;
+        }
+        deployer.kernel_cmdline_append = self.kernel_cmdline_append;
+        deployer.vm_extra = self.vm_extra;
+        deployer.discovery_cache_ttl = self.discovery_cache_ttl;
+        deployer.price_table = self.price_table;
+        deployer.user_id = self.user_id;
+        if let Some(clock) = self.clock {
+            deployer.clock = clock;
+        }
+        deployer.strip_compose_version = self.strip_compose_version;
+        deployer.normalize_line_endings = self.normalize_line_endings;
+        deployer.expand_anchors = self.expand_anchors;
+        deployer.strict_env_check = self.strict_env_check;
+        deployer.operation_timeout = self.operation_timeout;
+        deployer.poll_strategy = self.poll_strategy;
+        if let Some(progress) = self.upload_progress {
+            deployer.client = deployer.client.with_upload_progress(progress);
+        }
+        Ok(deployer)
     }
 }
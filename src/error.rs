@@ -1,5 +1,7 @@
 use thiserror::Error;
 
+use crate::DeploymentStatus;
+
 /// Error types for the Phala TEE deployment library.
 ///
 /// This module defines a comprehensive error type hierarchy for handling
@@ -9,9 +11,19 @@ pub enum Error {
     /// Errors from the underlying HTTP client library.
     ///
     /// This variant wraps errors from the reqwest library, which include
-    /// network connectivity issues, timeouts, and TLS/SSL errors.
+    /// network connectivity issues and TLS/SSL errors. Timeouts are reported
+    /// as `Error::Timeout` instead.
     #[error("HTTP client error: {0}")]
-    HttpClient(#[from] reqwest::Error),
+    HttpClient(#[source] reqwest::Error),
+
+    /// Timeouts.
+    ///
+    /// These errors occur when an API request does not complete within the
+    /// configured or per-call request timeout, when a multi-step operation
+    /// exceeds its operation timeout, or when a wait for a CVM state expires.
+    /// `operation` names what timed out.
+    #[error("Timed out during {operation}")]
+    Timeout { operation: String },
 
     /// Configuration-related errors.
     ///
@@ -20,6 +32,50 @@ pub enum Error {
     #[error("Invalid configuration: {0}")]
     Configuration(String),
 
+    /// No TEEPod has been selected for the deployment.
+    ///
+    /// Occurs when a deployer method needs a TEEPod before
+    /// `discover_teepod()` or `select_teepod()` has succeeded.
+    #[error("No TEEPod selected")]
+    NoTeepodSelected,
+
+    /// No TEEPod matches the selection criteria.
+    ///
+    /// Occurs when discovery or an explicit selection finds no available
+    /// node that qualifies and offers a usable image. The message names the
+    /// criterion that could not be met.
+    #[error("No suitable TEEPod: {0}")]
+    NoTeepodAvailable(String),
+
+    /// A deployment entered the failed state.
+    ///
+    /// Occurs when the deployer is waiting on a CVM, e.g. while verifying a
+    /// secret rotation, and the CVM reports that it failed. `message` gives
+    /// the context of the wait.
+    #[error("Deployment {app_id} failed: {message}")]
+    DeploymentFailed { app_id: String, message: String },
+
+    /// A CVM settled in a status other than the one being waited for.
+    ///
+    /// Occurs when a wait such as `TeeDeployer::wait_for_status` sees the CVM
+    /// reach a terminal status (e.g. failed) that is not `expected`.
+    #[error("CVM {app_id} reached terminal state '{actual}' while waiting for '{expected}'")]
+    UnexpectedStatus {
+        app_id: String,
+        expected: DeploymentStatus,
+        actual: DeploymentStatus,
+    },
+
+    /// The API does not support a feature this call needs.
+    ///
+    /// Occurs when `ApiCapabilities::require` finds that the API version
+    /// reported by the server does not offer `feature`.
+    #[error("The '{feature}' endpoint isn't supported by your API version ({api_version})")]
+    Unsupported {
+        feature: String,
+        api_version: String,
+    },
+
     /// Encryption-related errors.
     ///
     /// These errors occur during the encryption or decryption of
@@ -34,6 +90,18 @@ pub enum Error {
     #[error("API error: {status_code} - {message}")]
     Api { status_code: u16, message: String },
 
+    /// HTML error pages returned instead of an API response.
+    ///
+    /// These errors occur when an upstream proxy (e.g. a load balancer or
+    /// CDN) answers with an HTML page. `message` is a concise summary; the
+    /// raw page is kept in `body` for debugging.
+    #[error("API error: {status_code} - {message}")]
+    UpstreamHtml {
+        status_code: u16,
+        message: String,
+        body: String,
+    },
+
     /// Missing environment variable errors.
     ///
     /// These errors occur when a required environment variable is not set
@@ -54,3 +122,89 @@ pub enum Error {
     #[error("Serialization error: {0}")]
     Serialization(String),
 }
+
+impl From<reqwest::Error> for Error {
+    fn from(err: reqwest::Error) -> Self {
+        if !err.is_timeout() {
+            return Error::HttpClient(err);
+        }
+        Error::Timeout {
+            operation: match err.url() {
+                Some(url) => format!("request to {}", url.path()),
+                None => "request".to_string(),
+            },
+        }
+    }
+}
+
+impl Error {
+    /// Whether this is a transient gateway failure (502, 503 or 504).
+    ///
+    /// Proxies in front of a CVM answer with these, often as HTML pages,
+    /// while the CVM is still starting; polling loops treat them as "not
+    /// ready yet" rather than as failures.
+    pub fn is_transient_gateway(&self) -> bool {
+        matches!(
+            self,
+            Error::Api {
+                status_code: 502..=504,
+                ..
+            } | Error::UpstreamHtml {
+                status_code: 502..=504,
+                ..
+            }
+        )
+    }
+
+    /// Returns actionable guidance for common failures, if any.
+    ///
+    /// Intended for CLIs that want to print a remediation step alongside the
+    /// error message.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use phala_tee_deploy_rs::Error;
+    ///
+    /// let err = Error::Api {
+    ///     status_code: 401,
+    ///     message: "unauthorized".to_string(),
+    /// };
+    /// assert!(err.hint().unwrap().contains("PHALA_CLOUD_API_KEY"));
+    /// ```
+    pub fn hint(&self) -> Option<&'static str> {
+        match self {
+            Error::Api {
+                status_code: 401 | 403,
+                ..
+            } => Some("Check that PHALA_CLOUD_API_KEY is set to a valid, unexpired API key"),
+            Error::Api {
+                status_code: 429, ..
+            } => Some("Too many requests; wait a moment before retrying"),
+            Error::Api {
+                status_code: 502..=504,
+                ..
+            }
+            | Error::UpstreamHtml {
+                status_code: 502..=504,
+                ..
+            } => Some("The Phala Cloud API is temporarily unreachable; retry shortly"),
+            Error::NoTeepodAvailable(_) => Some(
+                "Your account may lack capacity; check your plan's TEEPod quota or relax the selection criteria",
+            ),
+            Error::HttpClient(err) if err.is_connect() => {
+                Some("Check your network connection and the configured API endpoint URL")
+            }
+            Error::Timeout { .. } => {
+                Some("Retry, or raise the timeout with `with_request_timeout` or `RequestOptions`")
+            }
+            Error::NoTeepodSelected => Some(
+                "Call discover_teepod() or select_teepod() first, or enable TeeDeployerBuilder::with_auto_discover",
+            ),
+            Error::MissingEnvVar(_) => {
+                Some("Set the variable in your shell or in a .env file before retrying")
+            }
+            _ => None,
+        }
+    }
+}
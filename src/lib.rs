@@ -8,7 +8,8 @@
 //!
 //! - **Secure Deployment**: Environment variables are encrypted using industry-standard cryptography
 //! - **Flexible API**: Both high-level (TeeDeployer) and low-level (TeeClient) interfaces
-//! - **Docker Compose Integration**: Direct integration with Docker Compose configurations
+//! - **Docker Compose Integration**: Direct integration with Docker Compose configurations,
+//!   plus a [`ComposeBuilder`] for generating compose files without hand-written YAML
 //! - **TEEPod Management**: Discovery and selection of available TEE environments
 //! - **Robust Error Handling**: Comprehensive error types with detailed diagnostics
 //! - **Secure Workflows**: Support for separated operator/user deployment patterns
@@ -67,10 +68,12 @@
 //! This library supports a secure workflow pattern that separates infrastructure management from sensitive data:
 //!
 //! ```rust,no_run
-//! use phala_tee_deploy_rs::{Encryptor, Result, TeeDeployerBuilder, PubkeyResponse};
+//! use phala_tee_deploy_rs::{
+//!     AppIdSalt, AppPublicKey, Encryptor, PubkeyResponse, Result, TeeDeployerBuilder,
+//! };
 //!
 //! // OPERATOR PHASE 1: Setup infrastructure and get public key
-//! async fn operator_setup() -> Result<(serde_json::Value, String, String)> {
+//! async fn operator_setup() -> Result<(serde_json::Value, AppPublicKey, AppIdSalt)> {
 //!     let mut deployer = TeeDeployerBuilder::new()
 //!         .with_api_key("operator-api-key")
 //!         .build()?;
@@ -85,10 +88,10 @@
 //!     )?;
 //!
 //!     // Get encryption public key
-//!     let vm_config_value = serde_json::to_value(&vm_config).unwrap();
+//!     let vm_config_value: serde_json::Value = vm_config.into();
 //!     let pubkey_response: PubkeyResponse = deployer.get_pubkey_for_config(&vm_config_value).await?;
-//!     let pubkey = pubkey_response.app_env_encrypt_pubkey;
-//!     let salt = pubkey_response.app_id_salt;
+//!     let pubkey = pubkey_response.public_key()?;
+//!     let salt = pubkey_response.salt()?;
 //!
 //!     // Return VM config and encryption keys (to be sent to user)
 //!     Ok((vm_config_value, pubkey, salt))
@@ -112,8 +115,8 @@
 //! async fn operator_deploy(
 //!     vm_config: serde_json::Value,
 //!     encrypted_env: String,
-//!     pubkey: &str,
-//!     salt: &str
+//!     pubkey: &AppPublicKey,
+//!     salt: &AppIdSalt
 //! ) -> Result<()> {
 //!     let mut deployer = TeeDeployerBuilder::new()
 //!         .with_api_key("operator-api-key")
@@ -121,7 +124,7 @@
 //!
 //!     // Deploy with encrypted environment variables
 //!     let deployment = deployer.deploy_with_encrypted_env(
-//!         vm_config, encrypted_env, &pubkey, &salt
+//!         vm_config, encrypted_env, pubkey, salt
 //!     ).await?;
 //!     
 //!     println!("Deployed successfully: {}", deployment.id);
@@ -142,21 +145,34 @@
 //! The library uses a comprehensive [`Error`] type with variants for different
 //! failure scenarios, making error diagnosis and handling straightforward.
 
+mod api;
 mod client;
+mod clock;
+mod compose;
 mod config;
 mod crypto;
 mod deployer;
 mod error;
+mod secret;
+#[cfg(any(test, feature = "test-util"))]
+pub mod test_util;
 mod types;
 
 #[cfg(test)]
 mod tests;
 
-pub use client::TeeClient;
-pub use config::DeploymentConfig;
-pub use crypto::Encryptor;
+pub use api::DeploymentApi;
+pub use client::{TeeClient, UploadProgress};
+pub use clock::{Clock, TokioClock};
+pub use compose::{
+    ComposeBuilder, DependencyCondition, ServiceHealthcheck, ServiceResources, ServiceSpec,
+    ServiceUlimit,
+};
+pub use config::{DeploymentConfig, DeploymentConfigBuilder, RequestOptions};
+pub use crypto::{encrypt_env_for_pubkey, Encryptor};
 pub use deployer::{TeeDeployer, TeeDeployerBuilder};
 pub use error::Error;
+pub use secret::Secret;
 pub use types::*;
 
 /// Result type for Phala TEE deployment operations.
@@ -0,0 +1,54 @@
+use std::fmt;
+
+/// A sensitive value, such as an environment variable holding a credential.
+///
+/// `Debug` and `Display` render `[redacted]` instead of the value, so secrets
+/// cannot leak through logging or derived `Debug` impls of structs that hold
+/// them. The value is only reachable through `expose` or `into_inner`.
+///
+/// # Examples
+///
+/// ```
+/// use phala_tee_deploy_rs::Secret;
+///
+/// let password = Secret::new("hunter2".to_string());
+/// assert_eq!(format!("{:?}", password), "[redacted]");
+/// assert_eq!(password.expose(), "hunter2");
+/// ```
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct Secret<T>(T);
+
+impl<T> Secret<T> {
+    /// Wraps a sensitive value.
+    pub fn new(value: T) -> Self {
+        Self(value)
+    }
+
+    /// Returns a reference to the wrapped value.
+    pub fn expose(&self) -> &T {
+        &self.0
+    }
+
+    /// Unwraps the value.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> From<T> for Secret<T> {
+    fn from(value: T) -> Self {
+        Self(value)
+    }
+}
+
+impl<T> fmt::Debug for Secret<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("[redacted]")
+    }
+}
+
+impl<T> fmt::Display for Secret<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("[redacted]")
+    }
+}
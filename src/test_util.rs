@@ -0,0 +1,136 @@
+//! Mock Phala Cloud API responses for testing code built on this crate.
+//!
+//! Available with the `test-util` feature. The response bodies mirror what the
+//! API returns for a successful deployment, so downstream tests can run real
+//! `TeeClient`/`TeeDeployer` calls against a `wiremock` server without
+//! reverse-engineering the response shapes.
+//!
+//! # Examples
+//!
+//! ```ignore
+//! use phala_tee_deploy_rs::{test_util, TeeDeployerBuilder};
+//! use wiremock::MockServer;
+//!
+//! let server = MockServer::start().await;
+//! test_util::mount_happy_path(&server).await;
+//!
+//! let mut deployer = TeeDeployerBuilder::new()
+//!     .with_api_key("test_api_key")
+//!     .with_api_endpoint(server.uri())
+//!     .build()?;
+//! deployer.discover_teepod().await?;
+//! ```
+
+use serde_json::{json, Value};
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+use x25519_dalek::{PublicKey, StaticSecret};
+
+/// TEEPod ID of the node in `mock_teepods_response`.
+pub const MOCK_TEEPOD_ID: u64 = 7;
+
+/// Name of the production image in `mock_teepods_response`.
+pub const MOCK_IMAGE: &str = "dstack-0.3.5";
+
+/// App ID returned by `mock_pubkey_response`.
+pub const MOCK_APP_ID: &str = "app_7c1a2b3d4e5f";
+
+/// Deployment ID returned by `mock_deployment_response`.
+pub const MOCK_DEPLOYMENT_ID: u64 = 4242;
+
+/// Private key matching the public key in `mock_pubkey_response`, for tests
+/// that decrypt the environment variables sent to the API.
+pub const MOCK_PRIVATE_KEY: [u8; 32] = [7u8; 32];
+
+/// Discovery response (`GET /teepods/available`) with one node offering a
+/// production image.
+pub fn mock_teepods_response() -> Value {
+    json!({
+        "capacity": { "max_disk": 500, "max_instances": 20, "max_memory": 65536, "max_vcpu": 16 },
+        "tier": "pro",
+        "nodes": [{
+            "teepod_id": MOCK_TEEPOD_ID,
+            "listed": true,
+            "name": "prod-node-1",
+            "region": "us-east",
+            "remaining_cvm_slots": 10,
+            "remaining_memory": 32768.0,
+            "remaining_vcpu": 8.0,
+            "resource_score": 0.9,
+            "images": [{
+                "name": MOCK_IMAGE,
+                "bios": "ovmf.fd",
+                "cmdline": "console=ttyS0 init=/init panic=1 net.ifnames=0 biosdevname=0 mce=off oops=panic pci=noearly pci=nommconf random.trust_cpu=y random.trust_bootloader=n tsc=reliable no-kvmclock",
+                "description": "dstack production image",
+                "hda": null,
+                "initrd": "initramfs.cpio.gz",
+                "is_dev": false,
+                "kernel": "bzImage",
+                "rootfs": "rootfs.img.verity",
+                "rootfs_hash": "0f5e4fd9b8e3c0d1a2b3c4d5e6f708192a3b4c5d6e7f8091a2b3c4d5e6f70819",
+                "shared_ro": true,
+                "version": [0, 3, 5]
+            }]
+        }]
+    })
+}
+
+/// Pubkey response (`POST /cvms/pubkey/from_cvm_configuration`) carrying a
+/// valid X25519 public key derived from `MOCK_PRIVATE_KEY`.
+pub fn mock_pubkey_response() -> Value {
+    let public_key = PublicKey::from(&StaticSecret::from(MOCK_PRIVATE_KEY));
+    json!({
+        "app_env_encrypt_pubkey": format!("0x{}", hex::encode(public_key.as_bytes())),
+        "app_id": MOCK_APP_ID,
+        "app_id_salt": "a1b2c3d4e5f60718",
+        "compose_manifest": {
+            "name": "app",
+            "features": ["kms", "tproxy-net"],
+            "docker_compose_file": ""
+        },
+        "disk_size": 10,
+        "encrypted_env": "",
+        "image": MOCK_IMAGE,
+        "listed": false,
+        "memory": 1024,
+        "name": "app",
+        "teepod_id": MOCK_TEEPOD_ID,
+        "vcpu": 1
+    })
+}
+
+/// Deployment response (`POST /cvms/from_cvm_configuration`).
+pub fn mock_deployment_response() -> Value {
+    json!({
+        "id": MOCK_DEPLOYMENT_ID,
+        "status": "creating",
+        "app_id": MOCK_APP_ID.trim_start_matches("app_"),
+        "teepod_id": MOCK_TEEPOD_ID,
+        "image": MOCK_IMAGE
+    })
+}
+
+/// Mounts the discovery, pubkey, and deployment endpoints on `server` with
+/// the mock responses above, enough for a full `discover_teepod` and
+/// `deploy_compose` flow.
+///
+/// Point the client or deployer at `server.uri()` as its API endpoint.
+pub async fn mount_happy_path(server: &MockServer) {
+    Mock::given(method("GET"))
+        .and(path("/teepods/available"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(mock_teepods_response()))
+        .mount(server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/cvms/pubkey/from_cvm_configuration"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(mock_pubkey_response()))
+        .mount(server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/cvms/from_cvm_configuration"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(mock_deployment_response()))
+        .mount(server)
+        .await;
+}
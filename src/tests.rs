@@ -2,10 +2,19 @@ use super::*;
 use serde_json::json;
 use std::collections::HashMap;
 use std::time::Duration;
-use wiremock::matchers::{header, method, path};
+use wiremock::matchers::{body_partial_json, header, method, path, query_param};
 use wiremock::{Mock, MockServer, ResponseTemplate};
 
 // Helper function to create a test configuration
+// Public key and salt as the pubkey endpoint would return them
+fn test_pubkey() -> AppPublicKey {
+    AppPublicKey::try_from(format!("0x{}", hex::encode([1u8; 32]))).unwrap()
+}
+
+fn test_salt() -> AppIdSalt {
+    AppIdSalt::try_from("test_salt").unwrap()
+}
+
 fn create_test_config(api_url: String) -> DeploymentConfig {
     let mut env_vars = HashMap::new();
     env_vars.insert("TEST_KEY".to_string(), "test_value".to_string());
@@ -72,6 +81,65 @@ async fn test_successful_deployment_flow() {
     assert_eq!(result.status, "pending");
 }
 
+#[tokio::test]
+async fn test_deploy_with_reuses_one_client() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/cvms/pubkey/from_cvm_configuration"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "app_env_encrypt_pubkey": format!("0x{}", hex::encode([1u8; 32])),
+            "app_id": "app_1",
+            "app_id_salt": "test_salt",
+            "compose_manifest": { "name": "test", "features": [], "docker_compose_file": "" },
+            "disk_size": 10,
+            "encrypted_env": "",
+            "image": "test:latest",
+            "listed": false,
+            "memory": 1024,
+            "name": "test",
+            "teepod_id": 1,
+            "vcpu": 1
+        })))
+        .expect(2)
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/cvms/from_cvm_configuration"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "id": 123,
+            "status": "pending"
+        })))
+        .expect(2)
+        .mount(&mock_server)
+        .await;
+
+    let client = TeeClient::new(create_test_config(mock_server.uri())).unwrap();
+    let web = "services:\n  web:\n    image: nginx\n";
+    let api = "services:\n  api:\n    image: api\n";
+    client
+        .deploy_with(web, &[("PORT".to_string(), "80".to_string())], None)
+        .await
+        .unwrap();
+    client
+        .deploy_with(api, &[], Some(client.default_vm_config("ignored")))
+        .await
+        .unwrap();
+
+    let requests = mock_server.received_requests().await.unwrap();
+    let composes: Vec<String> = requests
+        .iter()
+        .filter(|r| r.url.path() == "/cvms/from_cvm_configuration")
+        .map(|r| {
+            let body: serde_json::Value = serde_json::from_slice(&r.body).unwrap();
+            body["compose_manifest"]["docker_compose_file"]
+                .as_str()
+                .unwrap()
+                .to_string()
+        })
+        .collect();
+    assert_eq!(composes, [web, api]);
+}
+
 #[tokio::test]
 async fn test_api_error_handling() {
     let mock_server = MockServer::start().await;
@@ -279,10 +347,10 @@ async fn test_deploy_with_config() {
     ];
 
     // Public key that would normally come from the API
-    let pubkey = format!("0x{}", hex::encode([1u8; 32]));
+    let pubkey = test_pubkey();
 
     let result = client
-        .deploy_with_config_do_encrypt(vm_config, &env_vars, &pubkey, "test_salt")
+        .deploy_with_config_do_encrypt(vm_config, &env_vars, &pubkey, &test_salt())
         .await
         .unwrap();
 
@@ -320,10 +388,10 @@ async fn test_deploy_with_config_error() {
     let env_vars = vec![("TEST_KEY".to_string(), "test_value".to_string())];
 
     // Public key that would normally come from the API
-    let pubkey = format!("0x{}", hex::encode([1u8; 32]));
+    let pubkey = test_pubkey();
 
     let result = client
-        .deploy_with_config_do_encrypt(vm_config, &env_vars, &pubkey, "test_salt")
+        .deploy_with_config_do_encrypt(vm_config, &env_vars, &pubkey, &test_salt())
         .await;
 
     assert!(matches!(
@@ -400,7 +468,12 @@ async fn test_update_compose() {
         .await
         .unwrap();
 
-    assert_eq!(result["status"], "success");
+    assert_eq!(result.status, "success");
+    assert_eq!(
+        result.message.as_deref(),
+        Some("Compose configuration updated")
+    );
+    assert_eq!(result.raw["message"], "Compose configuration updated");
 }
 
 #[tokio::test]
@@ -438,5 +511,3923 @@ async fn test_update_compose_without_env_vars() {
         .await
         .unwrap();
 
-    assert_eq!(result["status"], "success");
+    assert_eq!(result.status, "success");
+}
+
+#[tokio::test]
+async fn test_client_with_invalid_certs_accepted() {
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(path("/cvms/app_1/state"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "status": "running",
+            "is_running": true
+        })))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let config = create_test_config(mock_server.uri()).with_danger_accept_invalid_certs(true);
+    assert!(config.danger_accept_invalid_certs);
+    let client = TeeClient::new(config).unwrap();
+    let state = client.get_state("app_1").await.unwrap();
+    assert!(state.is_running);
+
+    let deployer = TeeDeployerBuilder::new()
+        .with_api_key("test_api_key")
+        .with_api_endpoint(mock_server.uri())
+        .danger_accept_invalid_certs(true)
+        .build();
+    assert!(deployer.is_ok());
+}
+
+// Helper function to build a TEEPod image entry for discovery responses
+fn teepod_image(name: &str, is_dev: bool) -> serde_json::Value {
+    json!({
+        "name": name,
+        "bios": "bios.bin",
+        "cmdline": "console=ttyS0 init=/init panic=1 quiet",
+        "description": "test image",
+        "hda": null,
+        "initrd": "initrd.img",
+        "is_dev": is_dev,
+        "kernel": "vmlinuz",
+        "rootfs": "rootfs.img",
+        "rootfs_hash": "abc123",
+        "shared_ro": false,
+        "version": [0, 3, 5]
+    })
+}
+
+// Helper function to mount a discovery endpoint returning a single node
+async fn mount_discovery(mock_server: &MockServer, images: Vec<serde_json::Value>) {
+    Mock::given(method("GET"))
+        .and(path("/teepods/available"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "capacity": { "max_disk": 100, "max_instances": 10, "max_memory": 65536, "max_vcpu": 16 },
+            "tier": "pro",
+            "nodes": [
+                {
+                    "teepod_id": 7,
+                    "listed": true,
+                    "name": "mixed-node",
+                    "remaining_cvm_slots": 5,
+                    "remaining_memory": 32768.0,
+                    "remaining_vcpu": 8.0,
+                    "resource_score": 0.8,
+                    "images": images
+                }
+            ]
+        })))
+        .mount(mock_server)
+        .await;
+}
+
+// Helper function to build a TEEPod node entry for discovery responses
+fn teepod_node(teepod_id: u64, images: Vec<serde_json::Value>) -> serde_json::Value {
+    json!({
+        "teepod_id": teepod_id,
+        "listed": true,
+        "name": format!("node-{}", teepod_id),
+        "remaining_cvm_slots": 5,
+        "remaining_memory": 32768.0,
+        "remaining_vcpu": 8.0,
+        "resource_score": 0.8,
+        "images": images
+    })
+}
+
+// Helper function to mount a discovery response with the given nodes
+async fn mount_nodes(mock_server: &MockServer, nodes: Vec<serde_json::Value>) {
+    Mock::given(method("GET"))
+        .and(path("/teepods/available"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "capacity": { "max_disk": 100, "max_instances": 10, "max_memory": 65536, "max_vcpu": 16 },
+            "tier": "pro",
+            "nodes": nodes
+        })))
+        .mount(mock_server)
+        .await;
+}
+
+#[tokio::test]
+async fn test_cluster_utilization_aggregates_nodes() {
+    let node = |teepod_id: u64, vcpu: f64, memory: f64, slots: u64, score: f64| {
+        let mut node = teepod_node(teepod_id, vec![teepod_image("dstack-0.3.5", false)]);
+        node["remaining_vcpu"] = json!(vcpu);
+        node["remaining_memory"] = json!(memory);
+        node["remaining_cvm_slots"] = json!(slots);
+        node["resource_score"] = json!(score);
+        node
+    };
+
+    let mock_server = MockServer::start().await;
+    mount_nodes(
+        &mock_server,
+        vec![
+            node(1, 4.0, 8192.0, 2, 0.25),
+            node(2, 8.5, 16384.0, 5, 0.5),
+            node(3, 0.0, 0.0, 0, 0.0),
+        ],
+    )
+    .await;
+    let deployer = test_deployer(mock_server.uri()).build().unwrap();
+
+    let utilization = deployer.cluster_utilization().await.unwrap();
+    assert_eq!(
+        utilization,
+        ClusterUtilization {
+            node_count: 3,
+            remaining_vcpu: 12.5,
+            remaining_memory: 24576.0,
+            remaining_cvm_slots: 7,
+            average_resource_score: 0.25,
+            max_vcpu: 16,
+            max_memory: 65536,
+        }
+    );
+}
+
+#[tokio::test]
+async fn test_discover_teepod_min_resource_score() {
+    let node = |teepod_id: u64, score: f64| {
+        let mut node = teepod_node(teepod_id, vec![teepod_image("dstack-0.3.5", false)]);
+        node["resource_score"] = json!(score);
+        node
+    };
+
+    let mock_server = MockServer::start().await;
+    mount_nodes(&mock_server, vec![node(1, 0.2), node(2, 0.7), node(3, 0.9)]).await;
+    let mut deployer = test_deployer(mock_server.uri())
+        .with_min_resource_score(0.5)
+        .build()
+        .unwrap();
+    deployer.discover_teepod().await.unwrap();
+    let vm_config = deployer
+        .create_vm_config("services: {}", "app", None, None, None)
+        .unwrap();
+    assert_eq!(vm_config.teepod_id, 2);
+
+    let overloaded = MockServer::start().await;
+    mount_nodes(&overloaded, vec![node(1, 0.2), node(2, 0.3)]).await;
+    let mut deployer = test_deployer(overloaded.uri())
+        .with_min_resource_score(0.5)
+        .build()
+        .unwrap();
+    match deployer.discover_teepod().await {
+        Err(Error::NoTeepodAvailable(message)) => {
+            assert!(message.contains("minimum resource score of 0.5"));
+            assert!(message.contains("best available: 0.3"));
+        }
+        other => panic!("expected resource score error, got {:?}", other),
+    }
+
+    // The default threshold accepts every node
+    let mut deployer = test_deployer(overloaded.uri()).build().unwrap();
+    deployer.discover_teepod().await.unwrap();
+}
+
+// Helper function to build a deployer pointed at the mock server
+fn test_deployer(api_url: String) -> TeeDeployerBuilder {
+    TeeDeployerBuilder::new()
+        .with_api_key("test_api_key")
+        .with_api_endpoint(api_url)
+}
+
+#[tokio::test]
+async fn test_discovery_prefers_production_image() {
+    let mock_server = MockServer::start().await;
+    mount_discovery(
+        &mock_server,
+        vec![
+            teepod_image("dstack-dev-0.3.5", true),
+            teepod_image("dstack-0.3.5", false),
+        ],
+    )
+    .await;
+
+    let mut deployer = test_deployer(mock_server.uri()).build().unwrap();
+    deployer.discover_teepod().await.unwrap();
+
+    let vm_config = deployer
+        .create_vm_config("services: {}", "app", None, None, None)
+        .unwrap();
+    assert_eq!(vm_config.teepod_id, 7);
+    assert_eq!(vm_config.image, "dstack-0.3.5");
+
+    let images = deployer.list_images();
+    assert_eq!(images.len(), 2);
+    assert!(images.iter().any(|image| image.is_dev));
+}
+
+#[tokio::test]
+async fn test_discovery_skips_nodes_without_images() {
+    let mock_server = MockServer::start().await;
+    mount_nodes(
+        &mock_server,
+        vec![
+            teepod_node(3, vec![]),
+            teepod_node(9, vec![teepod_image("dstack-0.3.5", false)]),
+        ],
+    )
+    .await;
+
+    let mut deployer = test_deployer(mock_server.uri()).build().unwrap();
+    deployer.discover_teepod().await.unwrap();
+
+    let vm_config = deployer
+        .create_vm_config("services: {}", "app", None, None, None)
+        .unwrap();
+    assert_eq!(vm_config.teepod_id, 9);
+    assert_eq!(vm_config.image, "dstack-0.3.5");
+
+    assert!(matches!(
+        deployer.select_teepod(3).await,
+        Err(Error::NoTeepodAvailable(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_discovery_allows_dev_image_when_not_preferring_production() {
+    let mock_server = MockServer::start().await;
+    mount_discovery(
+        &mock_server,
+        vec![
+            teepod_image("dstack-dev-0.3.5", true),
+            teepod_image("dstack-0.3.5", false),
+        ],
+    )
+    .await;
+
+    let mut deployer = test_deployer(mock_server.uri())
+        .with_prefer_production(false)
+        .build()
+        .unwrap();
+    deployer.discover_teepod().await.unwrap();
+
+    let vm_config = deployer
+        .create_vm_config("services: {}", "app", None, None, None)
+        .unwrap();
+    assert_eq!(vm_config.image, "dstack-dev-0.3.5");
+}
+
+#[tokio::test]
+async fn test_discovery_falls_back_to_dev_image() {
+    let mock_server = MockServer::start().await;
+    mount_discovery(&mock_server, vec![teepod_image("dstack-dev-0.3.5", true)]).await;
+
+    let mut deployer = test_deployer(mock_server.uri()).build().unwrap();
+    assert!(deployer.list_images().is_empty());
+    deployer.discover_teepod().await.unwrap();
+
+    let vm_config = deployer
+        .create_vm_config("services: {}", "app", None, None, None)
+        .unwrap();
+    assert_eq!(vm_config.image, "dstack-dev-0.3.5");
+
+    let args = deployer.list_images()[0].cmdline_args();
+    assert_eq!(args[0], ("console".to_string(), Some("ttyS0".to_string())));
+    assert_eq!(args[3], ("quiet".to_string(), None));
+}
+
+// Helper function to mount the pubkey and deployment endpoints used by high-level deploys
+async fn mount_deploy_endpoints(mock_server: &MockServer, listed: bool) {
+    Mock::given(method("POST"))
+        .and(path("/cvms/pubkey/from_cvm_configuration"))
+        .and(body_partial_json(json!({
+            "advanced_features": { "listed": listed }
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "app_env_encrypt_pubkey": format!("0x{}", hex::encode([1u8; 32])),
+            "app_id": "app_1",
+            "app_id_salt": "test_salt",
+            "compose_manifest": { "name": "test", "features": [], "docker_compose_file": "" },
+            "disk_size": 10,
+            "encrypted_env": "",
+            "image": "test:latest",
+            "listed": listed,
+            "memory": 1024,
+            "name": "test",
+            "teepod_id": 7,
+            "vcpu": 1
+        })))
+        .expect(1)
+        .mount(mock_server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/cvms/from_cvm_configuration"))
+        .and(body_partial_json(json!({
+            "advanced_features": { "listed": listed }
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "id": 42,
+            "status": "pending"
+        })))
+        .expect(1)
+        .mount(mock_server)
+        .await;
+}
+
+#[tokio::test]
+async fn test_upload_progress_reports_deploy_body() {
+    let mock_server = MockServer::start().await;
+    mount_discovery(&mock_server, vec![teepod_image("dstack-0.3.5", false)]).await;
+    mount_deploy_endpoints(&mock_server, false).await;
+
+    let reports = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let recorded = reports.clone();
+    let mut deployer = test_deployer(mock_server.uri())
+        .with_upload_progress(std::sync::Arc::new(move |sent, total| {
+            recorded.lock().unwrap().push((sent, total))
+        }))
+        .build()
+        .unwrap();
+    deployer.discover_teepod().await.unwrap();
+
+    let character = "x".repeat(256 * 1024);
+    deployer
+        .deploy_compose(
+            "services: {}",
+            "app",
+            [("CHARACTER_DATA", character.as_str())],
+            None,
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+
+    let reports = reports.lock().unwrap().clone();
+    assert!(reports.len() > 1);
+    let &(sent, total) = reports.last().unwrap();
+    assert_eq!(sent, total);
+
+    let requests = mock_server.received_requests().await.unwrap();
+    let deploy = requests
+        .iter()
+        .find(|r| r.url.path() == "/cvms/from_cvm_configuration")
+        .unwrap();
+    assert_eq!(deploy.body.len() as u64, total);
+    let body: serde_json::Value = serde_json::from_slice(&deploy.body).unwrap();
+    assert!(body["encrypted_env"].as_str().unwrap().len() > 256 * 1024);
+}
+
+#[tokio::test]
+async fn test_auto_discover_on_first_deploy() {
+    let mock_server = MockServer::start().await;
+    mount_discovery(&mock_server, vec![teepod_image("dstack-0.3.5", false)]).await;
+    mount_deploy_endpoints(&mock_server, false).await;
+
+    let deployer = test_deployer(mock_server.uri())
+        .with_auto_discover(true)
+        .build()
+        .unwrap();
+    let result = deployer
+        .deploy_compose("services: {}", "app", [("PORT", "80")], None, None, None)
+        .await
+        .unwrap();
+    assert_eq!(result.details.unwrap()["teepod_id"], 7);
+
+    // The discovered TEEPod is remembered for synchronous calls
+    let vm_config = deployer
+        .create_vm_config("services: {}", "app", None, None, None)
+        .unwrap();
+    assert_eq!(vm_config.teepod_id, 7);
+    assert_eq!(vm_config.image, "dstack-0.3.5");
+
+    // Auto-discovery records the same details as discover_teepod()
+    assert_eq!(deployer.max_deployable_resources(), Some((8, 32768, 100)));
+    assert_eq!(deployer.list_images().len(), 1);
+    let mut vm_config = vm_config;
+    vm_config.image = "dstack-0.4.0".to_string();
+    assert!(matches!(
+        deployer
+            .deploy_config(vm_config, HashMap::<String, String>::new(), None)
+            .await,
+        Err(Error::Configuration(message)) if message.contains("not offered")
+    ));
+
+    // Without the option the explicit selection is still required
+    let manual = test_deployer(mock_server.uri()).build().unwrap();
+    assert!(matches!(
+        manual
+            .deploy_compose("services: {}", "app", [("PORT", "80")], None, None, None)
+            .await,
+        Err(Error::NoTeepodSelected)
+    ));
+}
+
+#[tokio::test]
+async fn test_methods_require_selected_teepod() {
+    let mock_server = MockServer::start().await;
+    let deployer = test_deployer(mock_server.uri()).build().unwrap();
+    let compose = "services:\n  app:\n    image: nginx\n";
+
+    let results = [
+        deployer
+            .create_vm_config(compose, "app", None, None, None)
+            .map(|_| ()),
+        deployer
+            .export_plan(compose, "app", None, None, None)
+            .map(|_| ()),
+        deployer
+            .deploy_compose(compose, "app", [("PORT", "80")], None, None, None)
+            .await
+            .map(|_| ()),
+        deployer
+            .deploy_compose_with_pubkey(
+                compose,
+                "app",
+                "00".to_string(),
+                &test_pubkey(),
+                &test_salt(),
+                None,
+                None,
+                None,
+            )
+            .await
+            .map(|_| ()),
+    ];
+    for result in results {
+        assert!(matches!(result, Err(Error::NoTeepodSelected)));
+    }
+    assert!(mock_server.received_requests().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_deploy_expands_compose_anchors() {
+    let mock_server = MockServer::start().await;
+    mount_discovery(&mock_server, vec![teepod_image("dstack-0.3.5", false)]).await;
+    mount_deploy_endpoints(&mock_server, false).await;
+
+    let mut deployer = test_deployer(mock_server.uri())
+        .with_expand_anchors(true)
+        .build()
+        .unwrap();
+    deployer.discover_teepod().await.unwrap();
+    let compose = "\
+x-logging: &logging
+  driver: json-file
+services:
+  web:
+    image: nginx
+    logging: *logging
+  api:
+    image: api
+    logging: *logging
+";
+    deployer
+        .deploy_compose(compose, "app", [("PORT", "80")], None, None, None)
+        .await
+        .unwrap();
+
+    let requests = mock_server.received_requests().await.unwrap();
+    let deploy = requests
+        .iter()
+        .find(|r| r.url.path() == "/cvms/from_cvm_configuration")
+        .unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&deploy.body).unwrap();
+    let uploaded = body["compose_manifest"]["docker_compose_file"]
+        .as_str()
+        .unwrap();
+    assert!(!uploaded.contains("&logging") && !uploaded.contains("*logging"));
+    let parsed: serde_yaml::Value = serde_yaml::from_str(uploaded).unwrap();
+    for service in ["web", "api"] {
+        assert_eq!(
+            parsed["services"][service]["logging"]["driver"],
+            "json-file"
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_deploy_retry_returns_cvm_created_by_timed_out_request() {
+    let mock_server = MockServer::start().await;
+    mount_discovery(&mock_server, vec![teepod_image("dstack-0.3.5", false)]).await;
+    Mock::given(method("POST"))
+        .and(path("/cvms/pubkey/from_cvm_configuration"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(test_util::mock_pubkey_response()))
+        .mount(&mock_server)
+        .await;
+    // The request times out on the client, but the server creates the CVM
+    Mock::given(method("POST"))
+        .and(path("/cvms/from_cvm_configuration"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(test_util::mock_deployment_response())
+                .set_delay(Duration::from_secs(2)),
+        )
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/cvms"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([
+            { "id": 3, "name": "other", "status": "running" }
+        ])))
+        .up_to_n_times(1)
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/cvms"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([
+            { "id": 3, "name": "other", "status": "running" },
+            { "id": 99, "name": "app", "status": "creating", "app_id": "abc" }
+        ])))
+        .mount(&mock_server)
+        .await;
+
+    let clock = FakeClock::new();
+    let mut deployer = test_deployer(mock_server.uri())
+        .with_request_timeout(Duration::from_millis(300))
+        .with_deploy_retries(2)
+        .with_clock(clock.clone())
+        .build()
+        .unwrap();
+    deployer.discover_teepod().await.unwrap();
+
+    let deployment = deployer
+        .deploy_compose("services: {}", "app", [("PORT", "80")], None, None, None)
+        .await
+        .unwrap();
+    assert_eq!(deployment.id, 99);
+    assert_eq!(deployment.status, "creating");
+    // Annotated like a response from a successful deploy request
+    let details = deployment.details.as_ref().unwrap();
+    assert_eq!(details["name"], "app");
+    assert_eq!(details["teepod_id"], 7);
+    assert!(deployment.to_string().contains("Image: dstack-0.3.5"));
+    // Found on the first check, so the deploy was not retried
+    assert!(clock.sleeps().is_empty());
+}
+
+#[tokio::test]
+async fn test_deploy_retry_backs_off_with_poll_strategy() {
+    let mock_server = MockServer::start().await;
+    mount_discovery(&mock_server, vec![teepod_image("dstack-0.3.5", false)]).await;
+    Mock::given(method("POST"))
+        .and(path("/cvms/pubkey/from_cvm_configuration"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(test_util::mock_pubkey_response()))
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/cvms/from_cvm_configuration"))
+        .respond_with(ResponseTemplate::new(503).set_body_string("Service Unavailable"))
+        .expect(3)
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/cvms"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([])))
+        .mount(&mock_server)
+        .await;
+
+    let clock = FakeClock::new();
+    let mut deployer = test_deployer(mock_server.uri())
+        .with_deploy_retries(2)
+        .with_poll_strategy(PollStrategy::Linear {
+            start: Duration::from_secs(1),
+            step: Duration::from_secs(1),
+            max: Duration::from_secs(5),
+        })
+        .with_clock(clock.clone())
+        .build()
+        .unwrap();
+    deployer.discover_teepod().await.unwrap();
+
+    let result = deployer
+        .deploy_compose("services: {}", "app", [("PORT", "80")], None, None, None)
+        .await;
+    assert!(result.is_err_and(|e| e.is_transient_gateway()));
+    let secs: Vec<u64> = clock.sleeps().iter().map(Duration::as_secs).collect();
+    assert_eq!(secs, vec![1, 2]);
+}
+
+// Helper function to mount the pubkey and deploy endpoints, failing the first `failures` deploys
+async fn mount_flaky_deploy(mock_server: &MockServer, failures: u64) {
+    mount_discovery(mock_server, vec![teepod_image("dstack-0.3.5", false)]).await;
+    Mock::given(method("POST"))
+        .and(path("/cvms/pubkey/from_cvm_configuration"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(test_util::mock_pubkey_response()))
+        .mount(mock_server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/cvms/from_cvm_configuration"))
+        .respond_with(ResponseTemplate::new(503).set_body_string("Service Unavailable"))
+        .up_to_n_times(failures)
+        .mount(mock_server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/cvms/from_cvm_configuration"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(test_util::mock_deployment_response()),
+        )
+        .mount(mock_server)
+        .await;
+}
+
+#[tokio::test]
+async fn test_deploy_retry_survives_failed_cvm_listing() {
+    let deploys = |requests: &[wiremock::Request]| {
+        requests
+            .iter()
+            .filter(|r| r.url.path() == "/cvms/from_cvm_configuration")
+            .count()
+    };
+
+    // The listing before the deploy fails once; the deploy still goes out and is retried
+    let mock_server = MockServer::start().await;
+    mount_flaky_deploy(&mock_server, 1).await;
+    Mock::given(method("GET"))
+        .and(path("/cvms"))
+        .respond_with(ResponseTemplate::new(503).set_body_string("Service Unavailable"))
+        .up_to_n_times(1)
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/cvms"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([])))
+        .mount(&mock_server)
+        .await;
+    let clock = FakeClock::new();
+    let mut deployer = test_deployer(mock_server.uri())
+        .with_deploy_retries(2)
+        .with_clock(clock.clone())
+        .build()
+        .unwrap();
+    deployer.discover_teepod().await.unwrap();
+    deployer
+        .deploy_compose("services: {}", "app", [("PORT", "80")], None, None, None)
+        .await
+        .unwrap();
+    assert_eq!(deploys(&mock_server.received_requests().await.unwrap()), 2);
+    assert_eq!(clock.sleeps().len(), 2);
+
+    // A failed check after the deploy error returns the deploy error, not the list error
+    let mock_server = MockServer::start().await;
+    mount_flaky_deploy(&mock_server, 1).await;
+    Mock::given(method("GET"))
+        .and(path("/cvms"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([])))
+        .up_to_n_times(1)
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/cvms"))
+        .respond_with(ResponseTemplate::new(500).set_body_string("database unavailable"))
+        .mount(&mock_server)
+        .await;
+    let mut deployer = test_deployer(mock_server.uri())
+        .with_deploy_retries(1)
+        .with_clock(FakeClock::new())
+        .build()
+        .unwrap();
+    deployer.discover_teepod().await.unwrap();
+    assert!(deployer
+        .deploy_compose("services: {}", "app", [("PORT", "80")], None, None, None)
+        .await
+        .is_err_and(|e| e.is_transient_gateway()));
+    assert_eq!(deploys(&mock_server.received_requests().await.unwrap()), 1);
+}
+
+#[tokio::test]
+async fn test_deploy_compose_unlisted_by_default() {
+    let mock_server = MockServer::start().await;
+    mount_discovery(&mock_server, vec![teepod_image("dstack-0.3.5", false)]).await;
+    mount_deploy_endpoints(&mock_server, false).await;
+
+    let mut deployer = test_deployer(mock_server.uri()).build().unwrap();
+    deployer.discover_teepod().await.unwrap();
+
+    let vm_config = deployer
+        .create_vm_config("services: {}", "app", None, None, None)
+        .unwrap();
+    assert!(!vm_config.advanced_features.listed);
+
+    let result = deployer
+        .deploy_compose(
+            "services: {}",
+            "app",
+            HashMap::<String, String>::new(),
+            None,
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+    assert_eq!(result.id, 42);
+    // The deployment endpoint returned no app_id; it comes from the pubkey step
+    assert_eq!(result.app_id.as_deref(), Some("app_1"));
+    assert_eq!(result.details.unwrap()["app_id"], "app_1");
+}
+
+#[tokio::test]
+async fn test_deploy_compose_listed_when_requested() {
+    let mock_server = MockServer::start().await;
+    mount_discovery(&mock_server, vec![teepod_image("dstack-0.3.5", false)]).await;
+    mount_deploy_endpoints(&mock_server, true).await;
+
+    let mut deployer = test_deployer(mock_server.uri())
+        .with_listed(true)
+        .build()
+        .unwrap();
+    deployer.discover_teepod().await.unwrap();
+
+    let vm_config = deployer
+        .create_vm_config("services: {}", "app", None, None, None)
+        .unwrap();
+    assert!(vm_config.advanced_features.listed);
+
+    let result = deployer
+        .deploy_compose(
+            "services: {}",
+            "app",
+            HashMap::<String, String>::new(),
+            None,
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+    assert_eq!(result.id, 42);
+}
+
+#[tokio::test]
+async fn test_deploy_compose_includes_labels() {
+    let mock_server = MockServer::start().await;
+    mount_discovery(&mock_server, vec![teepod_image("dstack-0.3.5", false)]).await;
+
+    Mock::given(method("POST"))
+        .and(path("/cvms/pubkey/from_cvm_configuration"))
+        .and(body_partial_json(json!({
+            "labels": { "team": "infra", "environment": "staging" }
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "app_env_encrypt_pubkey": format!("0x{}", hex::encode([1u8; 32])),
+            "app_id": "app_1",
+            "app_id_salt": "test_salt",
+            "compose_manifest": { "name": "test", "features": [], "docker_compose_file": "" },
+            "disk_size": 10,
+            "encrypted_env": "",
+            "image": "test:latest",
+            "listed": false,
+            "memory": 1024,
+            "name": "test",
+            "teepod_id": 7,
+            "vcpu": 1
+        })))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/cvms/from_cvm_configuration"))
+        .and(body_partial_json(json!({
+            "labels": { "team": "infra", "environment": "staging" }
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "id": 42,
+            "status": "pending"
+        })))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let mut deployer = test_deployer(mock_server.uri())
+        .with_label("team", "infra")
+        .with_label("environment", "staging")
+        .build()
+        .unwrap();
+    deployer.discover_teepod().await.unwrap();
+
+    let result = deployer
+        .deploy_compose(
+            "services: {}",
+            "app",
+            HashMap::<String, String>::new(),
+            None,
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+    let details = result.details.unwrap();
+    assert_eq!(details["labels"]["team"], "infra");
+    assert_eq!(details["labels"]["environment"], "staging");
+}
+
+#[tokio::test]
+async fn test_list_deployments_filters_by_labels() {
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(path("/cvms"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([
+            { "id": 1, "status": "running", "name": "api", "labels": { "team": "infra" } },
+            { "id": 2, "status": "running", "name": "web", "labels": { "team": "frontend" } },
+            { "id": 3, "status": "stopped", "name": "legacy" }
+        ])))
+        .mount(&mock_server)
+        .await;
+
+    let deployer = test_deployer(mock_server.uri()).build().unwrap();
+
+    let all = deployer.list_deployments(&HashMap::new()).await.unwrap();
+    assert_eq!(all.len(), 3);
+
+    let filter = HashMap::from([("team".to_string(), "infra".to_string())]);
+    let infra = deployer.list_deployments(&filter).await.unwrap();
+    assert_eq!(infra.len(), 1);
+    assert_eq!(infra[0].name, "api");
+}
+
+// Helper function to mount a CVM state response that is served `times` times
+async fn mount_state(mock_server: &MockServer, status: &str, times: Option<u64>) {
+    let mock = Mock::given(method("GET"))
+        .and(path("/cvms/app_1/state"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "status": status,
+            "is_running": status == "running"
+        })));
+    match times {
+        Some(n) => mock.up_to_n_times(n).mount(mock_server).await,
+        None => mock.mount(mock_server).await,
+    }
+}
+
+#[tokio::test]
+async fn test_wait_for_status_reaches_target() {
+    let mock_server = MockServer::start().await;
+    mount_state(&mock_server, "stopping", Some(2)).await;
+    mount_state(&mock_server, "stopped", None).await;
+
+    let deployer = test_deployer(mock_server.uri()).build().unwrap();
+    let status = deployer
+        .wait_for_status(
+            "app_1",
+            DeploymentStatus::Stopped,
+            Duration::from_secs(5),
+            Duration::from_millis(10),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(status, DeploymentStatus::Stopped);
+}
+
+#[tokio::test]
+async fn test_wait_for_status_unexpected_terminal_state() {
+    let mock_server = MockServer::start().await;
+    mount_state(&mock_server, "starting", Some(1)).await;
+    mount_state(&mock_server, "failed", None).await;
+
+    let deployer = test_deployer(mock_server.uri()).build().unwrap();
+    let result = deployer
+        .wait_for_status(
+            "app_1",
+            DeploymentStatus::Running,
+            Duration::from_secs(5),
+            Duration::from_millis(10),
+        )
+        .await;
+
+    assert!(matches!(
+        result,
+        Err(Error::UnexpectedStatus {
+            actual: DeploymentStatus::Failed,
+            ..
+        })
+    ));
+
+    // Only gateway errors are retried
+    let revoked = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/cvms/app_1/state"))
+        .respond_with(ResponseTemplate::new(401).set_body_string("unauthorized"))
+        .expect(1)
+        .mount(&revoked)
+        .await;
+    let deployer = test_deployer(revoked.uri()).build().unwrap();
+    assert!(matches!(
+        deployer
+            .wait_for_status(
+                "app_1",
+                DeploymentStatus::Running,
+                Duration::from_secs(5),
+                Duration::from_millis(10),
+            )
+            .await,
+        Err(Error::Api {
+            status_code: 401,
+            ..
+        })
+    ));
+}
+
+#[tokio::test]
+async fn test_wait_for_status_timeout() {
+    let mock_server = MockServer::start().await;
+    mount_state(&mock_server, "starting", None).await;
+
+    let deployer = test_deployer(mock_server.uri()).build().unwrap();
+    let result = deployer
+        .wait_for_status(
+            "app_1",
+            DeploymentStatus::Running,
+            Duration::from_millis(50),
+            Duration::from_millis(10),
+        )
+        .await;
+
+    assert!(matches!(result, Err(Error::Timeout { .. })));
+}
+
+#[test]
+fn test_deployment_response_success() {
+    let response = |status: &str| DeploymentResponse {
+        id: 1,
+        status: status.to_string(),
+        app_id: None,
+        details: None,
+    };
+
+    for status in ["running", "creating", "pending", "Starting"] {
+        assert!(response(status).is_success(), "{}", status);
+        assert!(!response(status).is_failed(), "{}", status);
+    }
+    for status in ["failed", "ERROR"] {
+        assert!(!response(status).is_success(), "{}", status);
+        assert!(response(status).is_failed(), "{}", status);
+    }
+    for status in ["stopped", "something-new"] {
+        assert!(!response(status).is_success(), "{}", status);
+        assert!(!response(status).is_failed(), "{}", status);
+    }
+}
+
+#[test]
+fn test_poll_strategy_delays() {
+    let ms = Duration::from_millis;
+    let delays = |strategy: PollStrategy| (0..5).map(|n| strategy.delay(n)).collect::<Vec<_>>();
+
+    assert_eq!(delays(PollStrategy::Fixed(ms(500))), vec![ms(500); 5]);
+    assert_eq!(
+        delays(PollStrategy::Linear {
+            start: ms(100),
+            step: ms(250),
+            max: ms(700),
+        }),
+        vec![ms(100), ms(350), ms(600), ms(700), ms(700)]
+    );
+    assert_eq!(
+        delays(PollStrategy::Exponential {
+            base: ms(100),
+            factor: 2.0,
+            max: ms(1000),
+        }),
+        vec![ms(100), ms(200), ms(400), ms(800), ms(1000)]
+    );
+
+    // Huge attempt counts saturate at the cap instead of overflowing
+    let exponential = PollStrategy::Exponential {
+        base: ms(100),
+        factor: 2.0,
+        max: ms(1000),
+    };
+    assert_eq!(exponential.delay(u32::MAX), ms(1000));
+    assert_eq!(PollStrategy::from(ms(20)), PollStrategy::Fixed(ms(20)));
+}
+
+#[tokio::test]
+async fn test_wait_for_status_with_backoff() {
+    let mock_server = MockServer::start().await;
+    mount_state(&mock_server, "starting", Some(3)).await;
+    mount_state(&mock_server, "running", None).await;
+
+    let deployer = test_deployer(mock_server.uri()).build().unwrap();
+    let status = deployer
+        .wait_for_status(
+            "app_1",
+            DeploymentStatus::Running,
+            Duration::from_secs(5),
+            PollStrategy::Exponential {
+                base: Duration::from_millis(5),
+                factor: 2.0,
+                max: Duration::from_millis(50),
+            },
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(status, DeploymentStatus::Running);
+}
+
+// Hand-written fake used to exercise code written against `DeploymentApi`
+struct FakeApi;
+
+#[async_trait::async_trait]
+impl DeploymentApi for FakeApi {
+    async fn get_available_teepods(&self) -> Result<TeePodDiscoveryResponse> {
+        Ok(serde_json::from_value(json!({
+            "capacity": { "max_disk": 100, "max_instances": 10, "max_memory": 65536, "max_vcpu": 16 },
+            "tier": "pro",
+            "nodes": []
+        }))
+        .unwrap())
+    }
+
+    async fn get_pubkey_for_config(
+        &self,
+        _vm_config: &serde_json::Value,
+    ) -> Result<PubkeyResponse> {
+        Err(Error::Configuration("not used".to_string()))
+    }
+
+    async fn deploy_with_config_encrypted_env(
+        &self,
+        vm_config: serde_json::Value,
+        _encrypted_env: String,
+        _app_env_encrypt_pubkey: &AppPublicKey,
+        _app_id_salt: &AppIdSalt,
+    ) -> Result<DeploymentResponse> {
+        Ok(DeploymentResponse {
+            id: 99,
+            status: "pending".to_string(),
+            app_id: None,
+            details: Some(HashMap::from([(
+                "name".to_string(),
+                vm_config["name"].clone(),
+            )])),
+        })
+    }
+
+    async fn get_network_info(&self, _app_id: &str) -> Result<NetworkInfoResponse> {
+        Err(Error::Configuration("not used".to_string()))
+    }
+
+    async fn get_system_stats(&self, _app_id: &str) -> Result<SystemStatsResponse> {
+        Err(Error::Configuration("not used".to_string()))
+    }
+}
+
+fn assert_send_sync<T: Send + Sync + ?Sized>() {}
+
+#[test]
+fn test_public_types_are_send_and_sync() {
+    assert_send_sync::<TeeClient>();
+    assert_send_sync::<TeeDeployer>();
+    assert_send_sync::<dyn DeploymentApi>();
+}
+
+#[tokio::test]
+async fn test_deployment_api_accepts_fake() {
+    async fn deploy_named(api: &dyn DeploymentApi, name: &str) -> Result<DeploymentResponse> {
+        api.get_available_teepods().await?;
+        api.deploy_with_config_encrypted_env(
+            json!({ "name": name }),
+            String::new(),
+            &test_pubkey(),
+            &test_salt(),
+        )
+        .await
+    }
+
+    let api: Box<dyn DeploymentApi> = Box::new(FakeApi);
+    let result = deploy_named(api.as_ref(), "fake-app").await.unwrap();
+
+    assert_eq!(result.id, 99);
+    assert_eq!(result.details.unwrap()["name"], "fake-app");
+}
+
+#[tokio::test]
+async fn test_compressed_request_body() {
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("POST"))
+        .and(path("/cvms/from_cvm_configuration"))
+        .and(header("Content-Encoding", "gzip"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "id": 123,
+            "status": "creating"
+        })))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let config = create_test_config(mock_server.uri()).with_compress_requests(true);
+    let client = TeeClient::new(config).unwrap();
+
+    let vm_config = json!({
+        "name": "test-vm",
+        "compose_manifest": {
+            "docker_compose_file": "version: '3'",
+            "name": "test"
+        },
+        "teepod_id": 123,
+        "image": "test-image:latest"
+    });
+
+    let result = client
+        .deploy_with_config_encrypted_env(
+            vm_config,
+            "deadbeef".to_string(),
+            &test_pubkey(),
+            &test_salt(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(result.id, 123);
+
+    let requests = mock_server.received_requests().await.unwrap();
+    let mut decompressed = String::new();
+    GzDecoder::new(requests[0].body.as_slice())
+        .read_to_string(&mut decompressed)
+        .unwrap();
+    let body: serde_json::Value = serde_json::from_str(&decompressed).unwrap();
+
+    assert_eq!(body["name"], "test-vm");
+    assert_eq!(
+        body["compose_manifest"]["docker_compose_file"],
+        "version: '3'"
+    );
+    assert_eq!(body["encrypted_env"], "deadbeef");
+    assert_eq!(body["app_id_salt"], "test_salt");
+}
+
+// Helper function to mount the compose and CVM info endpoints of a source deployment
+async fn mount_source_deployment(mock_server: &MockServer) {
+    Mock::given(method("GET"))
+        .and(path("/cvms/app_src/compose"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "compose_file": {
+                "name": "prod-app",
+                "docker_compose_file": "services:\n  app:\n    image: app:${TAG:-latest}\n    environment:\n      - API_KEY=${API_KEY}\n",
+                "allowed_envs": ["API_KEY"]
+            },
+            "env_pubkey": format!("0x{}", hex::encode([1u8; 32]))
+        })))
+        .mount(mock_server)
+        .await;
+
+    Mock::given(method("GET"))
+        .and(path("/cvms/app_src"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "id": 1,
+            "status": "running",
+            "name": "prod-app",
+            "vcpu": 2,
+            "memory": 2048,
+            "disk_size": 20
+        })))
+        .mount(mock_server)
+        .await;
+}
+
+#[tokio::test]
+async fn test_clone_deployment() {
+    let mock_server = MockServer::start().await;
+    mount_discovery(&mock_server, vec![teepod_image("dstack-0.3.5", false)]).await;
+    mount_source_deployment(&mock_server).await;
+
+    Mock::given(method("POST"))
+        .and(path("/cvms/pubkey/from_cvm_configuration"))
+        .and(body_partial_json(json!({
+            "name": "staging-app",
+            "vcpu": 2,
+            "memory": 2048,
+            "disk_size": 20
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "app_env_encrypt_pubkey": format!("0x{}", hex::encode([1u8; 32])),
+            "app_id": "app_2",
+            "app_id_salt": "test_salt",
+            "compose_manifest": { "name": "staging-app", "features": [], "docker_compose_file": "" },
+            "disk_size": 20,
+            "encrypted_env": "",
+            "image": "dstack-0.3.5",
+            "listed": false,
+            "memory": 2048,
+            "name": "staging-app",
+            "teepod_id": 7,
+            "vcpu": 2
+        })))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/cvms/from_cvm_configuration"))
+        .and(body_partial_json(json!({ "name": "staging-app" })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "id": 2,
+            "status": "pending"
+        })))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let mut deployer = test_deployer(mock_server.uri()).build().unwrap();
+    deployer.discover_teepod().await.unwrap();
+
+    let overrides = HashMap::from([("API_KEY".to_string(), "staging-key".to_string())]);
+    let result = deployer
+        .clone_deployment("app_src", "staging-app", overrides)
+        .await
+        .unwrap();
+
+    assert_eq!(result.id, 2);
+}
+
+#[tokio::test]
+async fn test_clone_deployment_missing_secret() {
+    let mock_server = MockServer::start().await;
+    mount_discovery(&mock_server, vec![teepod_image("dstack-0.3.5", false)]).await;
+    mount_source_deployment(&mock_server).await;
+
+    let mut deployer = test_deployer(mock_server.uri()).build().unwrap();
+    deployer.discover_teepod().await.unwrap();
+
+    let result = deployer
+        .clone_deployment("app_src", "staging-app", HashMap::new())
+        .await;
+
+    match result {
+        Err(Error::Configuration(message)) => {
+            assert!(message.contains("API_KEY"));
+            assert!(!message.contains("TAG"));
+        }
+        other => panic!("expected configuration error, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_deploy_with_fixed_crypto_is_deterministic() {
+    // Known vector for {"env":[{"key":"FOO","value":"BAR"}]} encrypted with a
+    // shared secret of 0x02 bytes, ephemeral public key of 0x03 bytes and IV of 0x04 bytes
+    let expected_env = "03030303030303030303030303030303030303030303030303030303030303030404040404040404040404047f6eb4608869aa01e67cbc85cf6123845dfd96ebae09ed1ad2b75dfbf1994b98609c4b7cb0cadc3d129b6fa9d1430a4b041f645518";
+
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("POST"))
+        .and(path("/cvms/from_cvm_configuration"))
+        .and(body_partial_json(json!({ "encrypted_env": expected_env })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "id": 123,
+            "status": "creating"
+        })))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let config = create_test_config(mock_server.uri());
+    let client = TeeClient::new(config)
+        .unwrap()
+        .with_fixed_crypto([3u8; 32], [2u8; 32], [4u8; 12]);
+
+    let vm_config = json!({ "name": "test-vm", "teepod_id": 123 });
+    let env_vars = vec![("FOO".to_string(), "BAR".to_string())];
+    let pubkey = test_pubkey();
+
+    let result = client
+        .deploy_with_config_do_encrypt(vm_config, &env_vars, &pubkey, &test_salt())
+        .await
+        .unwrap();
+    assert_eq!(result.id, 123);
+
+    let requests = mock_server.received_requests().await.unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&requests[0].body).unwrap();
+    assert_eq!(body["encrypted_env"], expected_env);
+    assert_eq!(body["app_env_encrypt_pubkey"], pubkey.as_str());
+}
+
+#[tokio::test]
+async fn test_deploy_body_has_sorted_keys() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/cvms/from_cvm_configuration"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "id": 1,
+            "status": "creating"
+        })))
+        .expect(3)
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/cvms/pubkey/from_cvm_configuration"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(test_util::mock_pubkey_response()))
+        .mount(&mock_server)
+        .await;
+
+    let client = TeeClient::new(create_test_config(mock_server.uri())).unwrap();
+    let pubkey = test_pubkey();
+    for vm_config in [
+        json!({ "teepod_id": 1, "name": "vm", "compose_manifest": { "runner": "docker-compose", "features": [] } }),
+        json!({ "name": "vm", "compose_manifest": { "features": [], "runner": "docker-compose" }, "teepod_id": 1 }),
+    ] {
+        client
+            .deploy_with_config_encrypted_env(vm_config, "00".to_string(), &pubkey, &test_salt())
+            .await
+            .unwrap();
+    }
+
+    let requests = mock_server.received_requests().await.unwrap();
+    assert_eq!(requests[0].body, requests[1].body);
+    let body = String::from_utf8(requests[0].body.clone()).unwrap();
+    let position = |key: &str| body.find(&format!("\"{}\"", key)).unwrap();
+    let keys = [
+        "app_env_encrypt_pubkey",
+        "app_id_salt",
+        "compose_manifest",
+        "features",
+        "runner",
+        "encrypted_env",
+        "name",
+        "teepod_id",
+    ];
+    assert!(keys
+        .windows(2)
+        .all(|pair| position(pair[0]) < position(pair[1])));
+
+    // Bodies built from a VmConfig struct are sorted too
+    client.deploy_with("services: {}", &[], None).await.unwrap();
+    let requests = mock_server.received_requests().await.unwrap();
+    let deploy = requests
+        .iter()
+        .rfind(|r| r.url.path() == "/cvms/from_cvm_configuration")
+        .unwrap();
+    let body = String::from_utf8(deploy.body.clone()).unwrap();
+    let position = |key: &str| body.find(&format!("\"{}\"", key)).unwrap();
+    let keys = [
+        "advanced_features",
+        "app_env_encrypt_pubkey",
+        "compose_manifest",
+        "disk_size",
+        "encrypted_env",
+        "image",
+        "memory",
+        "teepod_id",
+        "vcpu",
+    ];
+    assert!(keys
+        .windows(2)
+        .all(|pair| position(pair[0]) < position(pair[1])));
+}
+
+#[tokio::test]
+async fn test_raw_deploy_response_keeps_all_fields() {
+    let mock_server = MockServer::start().await;
+    let body = json!({
+        "id": 42,
+        "status": "creating",
+        "app_id": "abc",
+        "vm_uuid": "uuid-1",
+        "extra": { "nested": [1, 2, 3] }
+    });
+    Mock::given(method("POST"))
+        .and(path("/cvms/from_cvm_configuration"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(body.clone()))
+        .mount(&mock_server)
+        .await;
+
+    let client = TeeClient::new(create_test_config(mock_server.uri())).unwrap();
+    let raw = client
+        .deploy_with_config_encrypted_env_raw(
+            json!({ "name": "vm" }),
+            "00".to_string(),
+            &test_pubkey(),
+            &test_salt(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(raw, body);
+}
+
+#[test]
+fn test_pubkey_response_app_identity() {
+    let mut response: PubkeyResponse =
+        serde_json::from_value(test_util::mock_pubkey_response()).unwrap();
+
+    let identity = response.app_identity().unwrap();
+    assert_eq!(identity.app_id, "app_7c1a2b3d4e5f");
+    assert_eq!(identity.app_id_salt.as_str(), "a1b2c3d4e5f60718");
+    assert_eq!(
+        serde_json::to_value(&identity).unwrap(),
+        json!({ "app_id": "app_7c1a2b3d4e5f", "app_id_salt": "a1b2c3d4e5f60718" })
+    );
+
+    response.app_id_salt = String::new();
+    assert!(matches!(
+        response.app_identity(),
+        Err(Error::Configuration(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_wait_until_online_skips_gateway_html() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/cvms/app_1/network"))
+        .respond_with(
+            ResponseTemplate::new(502)
+                .insert_header("content-type", "text/html")
+                .set_body_string("<html><body>502 Bad Gateway</body></html>"),
+        )
+        .up_to_n_times(1)
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/cvms/app_1/network"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(network_info(
+            true,
+            "https://app",
+            "",
+        )))
+        .mount(&mock_server)
+        .await;
+
+    let clock = FakeClock::new();
+    let deployer = test_deployer(mock_server.uri())
+        .with_clock(clock.clone())
+        .build()
+        .unwrap();
+    let info = deployer
+        .wait_until_online("app_1", Duration::from_secs(60), Duration::from_secs(2))
+        .await
+        .unwrap();
+    assert!(info.is_online);
+    assert_eq!(clock.sleeps(), [Duration::from_secs(2)]);
+}
+
+#[tokio::test]
+async fn test_wait_until_online_fails_on_auth_error() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/cvms/app_1/network"))
+        .respond_with(ResponseTemplate::new(401).set_body_string("unauthorized"))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let deployer = test_deployer(mock_server.uri())
+        .with_clock(FakeClock::new())
+        .build()
+        .unwrap();
+    match deployer
+        .wait_until_online("app_1", Duration::from_secs(60), Duration::from_secs(2))
+        .await
+    {
+        Err(Error::Api { status_code, .. }) => assert_eq!(status_code, 401),
+        other => panic!("expected auth error, got {:?}", other),
+    }
+}
+
+// Helper function to build a network info response with the given URLs
+fn network_info(is_online: bool, app: &str, instance: &str) -> NetworkInfoResponse {
+    serde_json::from_value(json!({
+        "is_online": is_online,
+        "is_public": true,
+        "error": null,
+        "internal_ip": "10.0.0.2",
+        "latest_handshake": "2024-03-14T12:00:00Z",
+        "public_urls": { "app": app, "instance": instance }
+    }))
+    .unwrap()
+}
+
+#[test]
+fn test_network_info_url_helpers() {
+    let ready = network_info(
+        true,
+        "https://app-1.dstack.phala.network",
+        "http://instance-1.dstack.phala.network:8090",
+    );
+    assert_eq!(
+        ready.app_url().unwrap().host_str(),
+        Some("app-1.dstack.phala.network")
+    );
+    assert_eq!(ready.instance_url().unwrap().port(), Some(8090));
+    assert!(ready.is_ready());
+
+    let empty = network_info(true, "", "");
+    assert!(empty.app_url().is_none());
+    assert!(empty.instance_url().is_none());
+    assert!(!empty.is_ready());
+
+    let malformed = network_info(true, "not a url", "ftp://instance.example.com");
+    assert!(malformed.app_url().is_none());
+    assert!(malformed.instance_url().is_none());
+    assert!(!malformed.is_ready());
+
+    let offline = network_info(false, "https://app-1.dstack.phala.network", "");
+    assert!(offline.app_url().is_some());
+    assert!(!offline.is_ready());
+}
+
+#[tokio::test]
+async fn test_runner_in_request() {
+    let mock_server = MockServer::start().await;
+    mount_discovery(&mock_server, vec![teepod_image("dstack-0.3.5", false)]).await;
+
+    Mock::given(method("POST"))
+        .and(path("/cvms/pubkey/from_cvm_configuration"))
+        .and(body_partial_json(json!({
+            "compose_manifest": { "runner": "bash" }
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "app_env_encrypt_pubkey": format!("0x{}", hex::encode([1u8; 32])),
+            "app_id": "app_1",
+            "app_id_salt": "test_salt",
+            "compose_manifest": { "name": "test", "features": [], "docker_compose_file": "" },
+            "disk_size": 10,
+            "encrypted_env": "",
+            "image": "test:latest",
+            "listed": false,
+            "memory": 1024,
+            "name": "test",
+            "teepod_id": 7,
+            "vcpu": 1
+        })))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let mut deployer = test_deployer(mock_server.uri())
+        .with_runner(Runner::Other("bash".to_string()))
+        .build()
+        .unwrap();
+    deployer.discover_teepod().await.unwrap();
+
+    let vm_config = deployer
+        .create_vm_config("services: {}", "app", None, None, None)
+        .unwrap();
+    let pubkey = deployer
+        .get_pubkey_for_config(&serde_json::to_value(&vm_config).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(pubkey.compose_manifest.runner, Runner::DockerCompose);
+
+    let mut default_deployer = test_deployer(mock_server.uri()).build().unwrap();
+    default_deployer.discover_teepod().await.unwrap();
+    let vm_config = default_deployer
+        .create_vm_config("services: {}", "app", None, None, None)
+        .unwrap();
+    assert_eq!(
+        serde_json::to_value(&vm_config).unwrap()["compose_manifest"]["runner"],
+        "docker-compose"
+    );
+}
+
+#[tokio::test]
+async fn test_ping_with_valid_key() {
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(path("/auth/me"))
+        .and(header("x-api-key", "test_api_key"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "username": "test" })))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let client = TeeClient::new(create_test_config(mock_server.uri())).unwrap();
+    client.ping().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_api_capabilities() {
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(path("/version"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "version": "1.4.0",
+            "features": ["delete", "Logs"]
+        })))
+        .mount(&mock_server)
+        .await;
+
+    let client = TeeClient::new(create_test_config(mock_server.uri())).unwrap();
+    let capabilities = client.api_capabilities().await.unwrap();
+    assert_eq!(capabilities.version, "1.4.0");
+    assert!(capabilities.supports_delete());
+    assert!(capabilities.supports_logs());
+    assert!(!capabilities.supports_resize());
+
+    assert!(capabilities.require("logs").is_ok());
+    match capabilities.require("resize") {
+        Err(Error::Unsupported {
+            feature,
+            api_version,
+        }) => {
+            assert_eq!(feature, "resize");
+            assert_eq!(api_version, "1.4.0");
+        }
+        other => panic!("expected unsupported error, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_verify_credentials_with_invalid_key() {
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(path("/auth/me"))
+        .respond_with(ResponseTemplate::new(401).set_body_string("unauthorized"))
+        .mount(&mock_server)
+        .await;
+
+    let deployer = test_deployer(mock_server.uri()).build().unwrap();
+    match deployer.verify_credentials().await {
+        Err(Error::Api {
+            status_code,
+            message,
+        }) => {
+            assert_eq!(status_code, 401);
+            assert!(message.contains("API key was rejected"));
+        }
+        other => panic!("expected credentials error, got {:?}", other),
+    }
+
+    // Missing permissions are reported separately, within the configured timeout
+    let forbidden = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/auth/me"))
+        .respond_with(ResponseTemplate::new(403).set_body_string("forbidden"))
+        .mount(&forbidden)
+        .await;
+    let deployer = test_deployer(forbidden.uri()).build().unwrap();
+    match deployer.verify_credentials().await {
+        Err(Error::Api {
+            status_code: 403,
+            message,
+        }) => assert!(message.contains("lacks permission")),
+        other => panic!("expected permission error, got {:?}", other),
+    }
+
+    let slow = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/auth/me"))
+        .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_millis(500)))
+        .mount(&slow)
+        .await;
+    let deployer = test_deployer(slow.uri())
+        .with_request_timeout(Duration::from_millis(50))
+        .build()
+        .unwrap();
+    assert!(matches!(
+        deployer.verify_credentials().await,
+        Err(Error::Timeout { .. })
+    ));
+}
+
+#[tokio::test]
+async fn test_vm_config_extra_fields_serialize_at_top_level() {
+    let mock_server = MockServer::start().await;
+    mount_discovery(&mock_server, vec![teepod_image("dstack-0.3.5", false)]).await;
+
+    let mut deployer = test_deployer(mock_server.uri())
+        .with_vm_extra("gpu_model", json!("h100"))
+        .build()
+        .unwrap();
+    deployer.discover_teepod().await.unwrap();
+
+    let vm_config = deployer
+        .create_vm_config("services: {}", "app", None, None, None)
+        .unwrap()
+        .with_extra("kms_id", json!({ "region": "us" }));
+    let value = serde_json::to_value(&vm_config).unwrap();
+
+    assert_eq!(value["gpu_model"], "h100");
+    assert_eq!(value["kms_id"]["region"], "us");
+    assert!(value.get("extra").is_none());
+
+    let parsed: VmConfig = serde_json::from_value(value).unwrap();
+    assert_eq!(parsed.extra.len(), 2);
+}
+
+#[tokio::test]
+async fn test_kernel_cmdline_append_is_sent_and_validated() {
+    let mock_server = MockServer::start().await;
+    mount_discovery(&mock_server, vec![teepod_image("dstack-0.3.5", false)]).await;
+    mount_deploy_endpoints(&mock_server, false).await;
+
+    let mut deployer = test_deployer(mock_server.uri())
+        .with_kernel_cmdline_append("debug loglevel=7")
+        .build()
+        .unwrap();
+    deployer.discover_teepod().await.unwrap();
+    deployer
+        .deploy_compose("services: {}", "app", [("PORT", "80")], None, None, None)
+        .await
+        .unwrap();
+
+    let requests = mock_server.received_requests().await.unwrap();
+    let deploy = requests
+        .iter()
+        .find(|r| r.url.path() == "/cvms/from_cvm_configuration")
+        .unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&deploy.body).unwrap();
+    assert_eq!(body["kernel_cmdline_append"], "debug loglevel=7");
+
+    let result = test_deployer(mock_server.uri())
+        .with_kernel_cmdline_append("debug\ninit=/bin/sh")
+        .build();
+    assert!(matches!(result, Err(Error::Configuration(_))));
+}
+
+#[tokio::test]
+async fn test_deploy_guard_rejects_concurrent_deploy_of_same_app() {
+    let mock_server = MockServer::start().await;
+    mount_discovery(&mock_server, vec![teepod_image("dstack-0.3.5", false)]).await;
+    // Expects exactly one deployment
+    mount_deploy_endpoints(&mock_server, false).await;
+
+    let mut deployer = test_deployer(mock_server.uri())
+        .with_deploy_guard(true)
+        .build()
+        .unwrap();
+    deployer.discover_teepod().await.unwrap();
+
+    let (first, second) = tokio::join!(
+        deployer.deploy_compose("services: {}", "app", [("PORT", "80")], None, None, None),
+        deployer.deploy_compose("services: {}", "app", [("PORT", "80")], None, None, None),
+    );
+    assert!(first.is_ok());
+    match second {
+        Err(Error::Configuration(message)) => {
+            assert_eq!(message, "deployment for 'app' already in progress")
+        }
+        other => panic!("expected in-progress error, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_export_plan_round_trip() {
+    let mock_server = MockServer::start().await;
+    mount_discovery(&mock_server, vec![teepod_image("dstack-0.3.5", false)]).await;
+
+    let mut deployer = test_deployer(mock_server.uri())
+        .with_label("team", "infra")
+        .build()
+        .unwrap();
+    deployer.discover_teepod().await.unwrap();
+
+    let plan = deployer
+        .export_plan("services: {}", "app", Some(2), None, None)
+        .unwrap();
+    let imported: DeploymentPlan = serde_json::from_str(&plan).unwrap();
+    assert_eq!(imported.vm_config.teepod_id, 7);
+    assert_eq!(imported.vm_config.image, "dstack-0.3.5");
+    assert_eq!(imported.vm_config.vcpu, 2);
+    assert_eq!(imported.vm_config.labels["team"], "infra");
+
+    let reexported =
+        serde_json::to_string_pretty(&serde_json::to_value(&imported).unwrap()).unwrap();
+    assert_eq!(plan, reexported);
+}
+
+#[tokio::test]
+async fn test_deploy_from_imported_plan() {
+    let mock_server = MockServer::start().await;
+    mount_discovery(&mock_server, vec![teepod_image("dstack-0.3.5", false)]).await;
+    mount_deploy_endpoints(&mock_server, false).await;
+
+    let mut planner = test_deployer(mock_server.uri()).build().unwrap();
+    planner.discover_teepod().await.unwrap();
+    let plan = planner
+        .export_plan("services: {}", "app", None, None, None)
+        .unwrap();
+
+    // The applying deployer never selected a TEEPod; the plan carries it
+    let applier = test_deployer(mock_server.uri()).build().unwrap();
+    let mut env_vars = HashMap::new();
+    env_vars.insert("SECRET".to_string(), "value".to_string());
+    let result = applier.deploy_from_plan(&plan, env_vars).await.unwrap();
+
+    assert_eq!(result.id, 42);
+    let details = result.details.unwrap();
+    assert_eq!(details["teepod_id"], 7);
+    assert_eq!(details["image"], "dstack-0.3.5");
+
+    assert!(matches!(
+        applier
+            .deploy_from_plan("{}", HashMap::<String, String>::new())
+            .await,
+        Err(Error::Serialization(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_discovery_cache_reuses_response_within_ttl() {
+    let mock_server = MockServer::start().await;
+    mount_discovery(&mock_server, vec![teepod_image("dstack-0.3.5", false)]).await;
+
+    let mut deployer = test_deployer(mock_server.uri())
+        .with_discovery_cache_ttl(Duration::from_secs(60))
+        .build()
+        .unwrap();
+    deployer.discover_teepod().await.unwrap();
+    deployer.select_teepod(7).await.unwrap();
+    assert_eq!(mock_server.received_requests().await.unwrap().len(), 1);
+
+    deployer.refresh_teepods().await.unwrap();
+    deployer.discover_teepod().await.unwrap();
+    assert_eq!(mock_server.received_requests().await.unwrap().len(), 2);
+}
+
+#[test]
+fn test_error_hints() {
+    let api_error = |status_code: u16, message: &str| Error::Api {
+        status_code,
+        message: message.to_string(),
+    };
+
+    assert_eq!(
+        api_error(401, "unauthorized").hint(),
+        Some("Check that PHALA_CLOUD_API_KEY is set to a valid, unexpired API key")
+    );
+    assert_eq!(
+        api_error(502, "Bad Gateway").hint(),
+        Some("The Phala Cloud API is temporarily unreachable; retry shortly")
+    );
+    assert!(
+        Error::NoTeepodAvailable("No available TEEPods found".into())
+            .hint()
+            .unwrap()
+            .contains("capacity")
+    );
+    assert_eq!(api_error(400, "No available TEEPods found").hint(), None);
+    assert_eq!(api_error(404, "not found").hint(), None);
+}
+
+#[tokio::test]
+async fn test_verify_deployed_compose() {
+    let mock_server = MockServer::start().await;
+    mount_source_deployment(&mock_server).await;
+
+    let deployer = test_deployer(mock_server.uri()).build().unwrap();
+
+    // Same content with CRLF line endings and trailing whitespace still matches
+    let expected = "services:\r\n  app:  \r\n    image: app:${TAG:-latest}\r\n    environment:\r\n      - API_KEY=${API_KEY}\r\n\r\n";
+    assert!(deployer
+        .verify_deployed_compose("app_src", expected)
+        .await
+        .unwrap());
+
+    let mutated = "services:\n  app:\n    image: app:latest\n";
+    assert!(!deployer
+        .verify_deployed_compose("app_src", mutated)
+        .await
+        .unwrap());
+}
+
+#[tokio::test]
+async fn test_strict_mode_rejects_unknown_response_fields() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/cvms/app_1/state"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "status": "running",
+            "is_running": true,
+            "uptime_seconds": 42
+        })))
+        .mount(&mock_server)
+        .await;
+
+    let lenient = TeeClient::new(create_test_config(mock_server.uri())).unwrap();
+    assert!(lenient.get_state("app_1").await.unwrap().is_running);
+
+    let strict = TeeClient::new(create_test_config(mock_server.uri()).with_strict(true)).unwrap();
+    match strict.get_state("app_1").await {
+        Err(Error::Serialization(message)) => assert!(message.contains("uptime_seconds")),
+        other => panic!("expected strict parsing error, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_deploy_compose_full_returns_canonical_app_id() {
+    let mock_server = MockServer::start().await;
+    mount_discovery(&mock_server, vec![teepod_image("dstack-0.3.5", false)]).await;
+    mount_deploy_endpoints(&mock_server, false).await;
+    Mock::given(method("GET"))
+        .and(path("/cvms/app_1/network"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "is_online": true,
+            "is_public": true,
+            "error": null,
+            "internal_ip": "10.0.0.2",
+            "latest_handshake": "2024-03-14T12:00:00Z",
+            "public_urls": { "app": "https://app-1.example", "instance": "https://instance-1.example" }
+        })))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let mut deployer = test_deployer(mock_server.uri()).build().unwrap();
+    deployer.discover_teepod().await.unwrap();
+
+    let (deployment, app_id) = deployer
+        .deploy_compose_full(
+            "services: {}",
+            "app",
+            HashMap::<String, String>::new(),
+            None,
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+    assert_eq!(deployment.id, 42);
+    assert_eq!(app_id, "app_1");
+
+    let network = deployer.get_network_info(&app_id).await.unwrap();
+    assert!(network.is_online);
+}
+
+#[tokio::test]
+async fn test_update_deployment_removes_env_vars() {
+    let mock_server = MockServer::start().await;
+    let secret = x25519_dalek::StaticSecret::from([5u8; 32]);
+    let pubkey = x25519_dalek::PublicKey::from(&secret);
+
+    Mock::given(method("GET"))
+        .and(path("/cvms/app_1/compose"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "compose_file": {
+                "compose_manifest": { "docker_compose_file": "services: {}" },
+                "allowed_envs": ["KEEP", "OLD_SECRET"]
+            },
+            "env_pubkey": format!("0x{}", hex::encode(pubkey.as_bytes()))
+        })))
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("PUT"))
+        .and(path("/cvms/app_1/compose"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "ok": true })))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let deployer = test_deployer(mock_server.uri()).build().unwrap();
+    let mut env_vars = HashMap::new();
+    env_vars.insert("KEEP".to_string(), "1".to_string());
+    env_vars.insert("OLD_SECRET".to_string(), "stale".to_string());
+    let removals = vec!["OLD_SECRET".to_string()];
+
+    let result = deployer
+        .update_deployment_with_removals("app_1", None, Some(env_vars), &removals)
+        .await
+        .unwrap();
+    assert_eq!(result["removed_envs"], json!(["OLD_SECRET"]));
+
+    let requests = mock_server.received_requests().await.unwrap();
+    let put = requests
+        .iter()
+        .find(|r| r.method == wiremock::http::Method::Put)
+        .unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&put.body).unwrap();
+    assert_eq!(body["compose_manifest"]["allowed_envs"], json!(["KEEP"]));
+
+    let decrypted = Encryptor::decrypt_json(
+        body["encrypted_env"].as_str().unwrap(),
+        &hex::encode(secret.to_bytes()),
+    )
+    .unwrap();
+    assert_eq!(decrypted["env"], json!([{ "key": "KEEP", "value": "1" }]));
+
+    // Removing keys without the remaining set would wipe every secret
+    assert!(matches!(
+        deployer
+            .update_deployment_with_removals("app_1", None, None, &removals)
+            .await,
+        Err(Error::Configuration(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_update_deployment_rejects_compose_without_manifest() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/cvms/app_1/compose"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "compose_file": {
+                "docker_compose_file": "services: {}",
+                "allowed_envs": []
+            },
+            "env_pubkey": format!("0x{}", hex::encode([9u8; 32]))
+        })))
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("PUT"))
+        .and(path("/cvms/app_1/compose"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "ok": true })))
+        .expect(0)
+        .mount(&mock_server)
+        .await;
+
+    let deployer = test_deployer(mock_server.uri()).build().unwrap();
+    match deployer
+        .update_deployment("app_1", Some("services:\n  web:\n    image: nginx\n"), None)
+        .await
+    {
+        Err(Error::Configuration(message)) => assert!(message.contains("compose_manifest")),
+        other => panic!("expected configuration error, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_get_logs_stream_follows_cursor_and_survives_errors() {
+    use futures_util::StreamExt;
+
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/cvms/app_1/logs"))
+        .and(query_param("since", "c1"))
+        .respond_with(ResponseTemplate::new(502))
+        .up_to_n_times(1)
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/cvms/app_1/logs"))
+        .and(query_param("since", "c1"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "lines": [{ "timestamp": "2024-03-14T12:00:02Z", "message": "agent ready" }],
+            "next_cursor": "c2"
+        })))
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/cvms/app_1/logs"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "lines": [
+                { "timestamp": "2024-03-14T12:00:00Z", "message": "booting" },
+                { "message": "loading character" }
+            ],
+            "next_cursor": "c1"
+        })))
+        .mount(&mock_server)
+        .await;
+
+    let deployer = test_deployer(mock_server.uri()).build().unwrap();
+    let items: Vec<_> = deployer.get_logs_stream("app_1").take(4).collect().await;
+
+    assert_eq!(items[0].as_ref().unwrap().message, "booting");
+    assert_eq!(items[1].as_ref().unwrap().timestamp, None);
+    assert!(matches!(
+        items[2],
+        Err(Error::Api {
+            status_code: 502,
+            ..
+        })
+    ));
+    assert_eq!(items[3].as_ref().unwrap().message, "agent ready");
+}
+
+#[tokio::test]
+async fn test_api_paths_use_configured_version() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v2/cvms"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([])))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let config = DeploymentConfig::builder()
+        .with_api_key("test_api_key")
+        .with_api_host(format!("{}/", mock_server.uri()))
+        .with_api_version("v2")
+        .build()
+        .unwrap();
+    assert_eq!(config.base_url(), format!("{}/api/v2", mock_server.uri()));
+    TeeClient::new(config).unwrap().list_cvms().await.unwrap();
+
+    // The default host gets the default version; a full URL is used verbatim
+    let config = DeploymentConfig::builder()
+        .with_api_key("test_api_key")
+        .build()
+        .unwrap();
+    assert_eq!(config.base_url(), "https://cloud-api.phala.network/api/v1");
+    let config = config.with_api_url("https://example.com/custom".to_string());
+    assert_eq!(config.base_url(), "https://example.com/custom");
+    assert_eq!(
+        config.with_api_version("v2").base_url(),
+        "https://example.com/custom"
+    );
+
+    // Assigning the URL field directly still takes effect
+    let mut config = DeploymentConfig::new(
+        "test_api_key".to_string(),
+        String::new(),
+        HashMap::new(),
+        0,
+        String::new(),
+    );
+    config.api_url = mock_server.uri();
+    assert_eq!(config.base_url(), mock_server.uri());
+    let config = config.with_api_url("https://cloud-api.phala.network/api/v1".to_string());
+    assert_eq!(
+        config.with_api_version("v2").base_url(),
+        "https://cloud-api.phala.network/api/v2"
+    );
+}
+
+#[test]
+fn test_deployment_config_builder_minimal() {
+    let config = DeploymentConfig::builder()
+        .with_api_key("test_api_key")
+        .build()
+        .unwrap();
+
+    assert_eq!(config.api_key, "test_api_key");
+    assert_eq!(config.api_url, "https://cloud-api.phala.network/api/v1");
+    assert!(config.docker_compose.is_empty());
+    assert!(config.env_vars.is_empty());
+    assert_eq!(config.teepod_id, 0);
+    assert!(config.vm_config.is_none());
+    assert!(!config.strict);
+
+    assert!(matches!(
+        DeploymentConfigBuilder::new().build(),
+        Err(Error::Configuration(_))
+    ));
+}
+
+#[test]
+fn test_builder_rejects_empty_api_key() {
+    for key in ["", "   "] {
+        match TeeDeployerBuilder::new().with_api_key(key).build() {
+            Err(Error::Configuration(message)) => assert_eq!(message, "API key is empty"),
+            other => panic!("expected empty key error, got {:?}", other.err()),
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_rejected_api_key_is_reported_clearly() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/cvms/app_1/state"))
+        .respond_with(ResponseTemplate::new(401).set_body_string("token expired"))
+        .mount(&mock_server)
+        .await;
+
+    let deployer = test_deployer(mock_server.uri()).build().unwrap();
+    match deployer.get_status("app_1").await {
+        Err(Error::Api {
+            status_code: 401,
+            message,
+        }) => {
+            assert!(message.contains("API key was rejected"));
+            assert!(message.contains("token expired"));
+        }
+        other => panic!("expected rejected key error, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_select_teepod_in_region() {
+    let mock_server = MockServer::start().await;
+    let mut us = teepod_node(1, vec![teepod_image("dstack-0.3.5", false)]);
+    us["region"] = json!("us-east");
+    let mut eu = teepod_node(2, vec![teepod_image("dstack-0.3.4", false)]);
+    eu["location"] = json!("eu-west");
+    let untagged = teepod_node(3, vec![teepod_image("dstack-0.3.5", false)]);
+    mount_nodes(&mock_server, vec![us, eu, untagged]).await;
+
+    let mut deployer = test_deployer(mock_server.uri()).build().unwrap();
+    deployer.select_teepod_in_region("EU-West").await.unwrap();
+    let vm_config = deployer
+        .create_vm_config("services: {}", "app", None, None, None)
+        .unwrap();
+    assert_eq!(vm_config.teepod_id, 2);
+    assert_eq!(vm_config.image, "dstack-0.3.4");
+
+    match deployer.select_teepod_in_region("ap-south").await {
+        Err(Error::NoTeepodAvailable(message)) => {
+            assert!(message.ends_with("Available regions: eu-west, us-east"))
+        }
+        other => panic!("expected region error, got {:?}", other),
+    }
+}
+
+// Helper function to build system info with the given CPU count and 1-minute load
+fn system_info(num_cpus: u32, loadavg_one: f32) -> SystemInfo {
+    serde_json::from_value(json!({
+        "os_name": "Linux",
+        "os_version": "6.1",
+        "kernel_version": "6.1.0",
+        "cpu_model": "Intel Xeon",
+        "num_cpus": num_cpus,
+        "total_memory": 4096,
+        "available_memory": 3072,
+        "used_memory": 1024,
+        "free_memory": 3072,
+        "total_swap": 0,
+        "used_swap": 0,
+        "free_swap": 0,
+        "uptime": 90061,
+        "loadavg_one": loadavg_one,
+        "loadavg_five": 0.5,
+        "loadavg_fifteen": 0.25,
+        "disks": []
+    }))
+    .unwrap()
+}
+
+#[test]
+fn test_system_info_computed_fields() {
+    let mut info = system_info(4, 1.0);
+
+    assert_eq!(info.memory_used_percent(), 25.0);
+    assert_eq!(info.swap_used_percent(), None);
+    assert_eq!(info.cpu_load_percent(), 25.0);
+    // 1 day, 1 hour, 1 minute and 1 second
+    assert_eq!(info.uptime_components(), (1, 1, 1, 1));
+
+    info.total_swap = 2048;
+    info.used_swap = 512;
+    assert_eq!(info.swap_used_percent(), Some(25.0));
+}
+
+#[test]
+fn test_cpu_load_percent_with_zero_cpus() {
+    let info = system_info(0, 1.5);
+    let percent = info.cpu_load_percent();
+
+    assert!(percent.is_finite());
+    assert_eq!(percent, 0.0);
+}
+
+#[test]
+fn test_system_stats_alerts() {
+    let mut sysinfo = system_info(2, 1.5);
+    sysinfo.total_swap = 1000;
+    sysinfo.used_swap = 600;
+    sysinfo.disks = vec![
+        DiskInfo {
+            name: "sda1".to_string(),
+            mount_point: Some("/".to_string()),
+            total_size: 100,
+            free_size: 5,
+        },
+        DiskInfo {
+            name: "sdb1".to_string(),
+            mount_point: None,
+            total_size: 100,
+            free_size: 50,
+        },
+    ];
+    let stats = SystemStatsResponse {
+        is_online: true,
+        is_public: false,
+        error: None,
+        sysinfo,
+    };
+
+    // Memory is 25% used, swap 60%, load 1.5, disks 95% and 50%
+    let thresholds = StatThresholds {
+        memory_used_percent: Some(80.0),
+        swap_used_percent: Some(50.0),
+        load_average: Some(1.0),
+        disk_used_percent: Some(90.0),
+    };
+    let alerts = stats.alerts(&thresholds);
+    let metrics: Vec<_> = alerts.iter().map(|alert| alert.metric.clone()).collect();
+    assert_eq!(
+        metrics,
+        vec![
+            AlertMetric::SwapUsed,
+            AlertMetric::LoadAverage,
+            AlertMetric::DiskUsed("sda1".to_string()),
+        ]
+    );
+    assert_eq!(alerts[0].actual, 60.0);
+    assert_eq!(alerts[0].threshold, 50.0);
+    assert_eq!(
+        alerts[2].to_string(),
+        "disk sda1 used % is 95.00, above threshold 90.00"
+    );
+
+    let relaxed = StatThresholds {
+        memory_used_percent: Some(25.0),
+        swap_used_percent: Some(90.0),
+        load_average: Some(4.0),
+        disk_used_percent: Some(99.0),
+    };
+    assert!(stats.alerts(&relaxed).is_empty());
+    assert!(stats.alerts(&StatThresholds::default()).is_empty());
+}
+
+#[tokio::test]
+async fn test_builder_reads_api_key_file() {
+    let mock_server = MockServer::start().await;
+    for key in ["file_api_key", "explicit_key"] {
+        Mock::given(method("GET"))
+            .and(path("/auth/me"))
+            .and(header("x-api-key", key))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+    }
+    let path = std::env::temp_dir().join(format!("phala-key-{}", uuid::Uuid::new_v4()));
+    std::fs::write(&path, "  file_api_key\n").unwrap();
+
+    let deployer = TeeDeployerBuilder::new()
+        .with_api_endpoint(mock_server.uri())
+        .with_api_key_file(&path)
+        .build()
+        .unwrap();
+    deployer.verify_credentials().await.unwrap();
+
+    // An explicit key wins over the file
+    let deployer = TeeDeployerBuilder::new()
+        .with_api_endpoint(mock_server.uri())
+        .with_api_key_file(&path)
+        .with_api_key("explicit_key")
+        .build()
+        .unwrap();
+    deployer.verify_credentials().await.unwrap();
+
+    std::fs::write(&path, "\n").unwrap();
+    assert!(matches!(
+        TeeDeployerBuilder::new().with_api_key_file(&path).build(),
+        Err(Error::Configuration(_))
+    ));
+
+    std::fs::remove_file(&path).unwrap();
+    match TeeDeployerBuilder::new().with_api_key_file(&path).build() {
+        Err(Error::Configuration(message)) => {
+            assert!(message.starts_with("Failed to read API key file"))
+        }
+        other => panic!("expected missing file error, got {:?}", other.err()),
+    }
+}
+
+#[tokio::test]
+async fn test_estimate_cost_from_price_table() {
+    let mock_server = MockServer::start().await;
+    mount_discovery(&mock_server, vec![teepod_image("dstack-0.3.5", false)]).await;
+    let prices = ResourcePrices {
+        vcpu_hour: 0.05,
+        memory_gb_hour: 0.01,
+        disk_gb_hour: 0.001,
+    };
+
+    let mut deployer = test_deployer(mock_server.uri())
+        .with_price_table(PriceTable::new().with_tier("pro", prices))
+        .build()
+        .unwrap();
+    // The tier is only known after discovery
+    assert!(matches!(
+        deployer.estimate_cost(2, 4096, 40),
+        Err(Error::Configuration(_))
+    ));
+    deployer.discover_teepod().await.unwrap();
+
+    let estimate = deployer.estimate_cost(2, 4096, 40).unwrap();
+    assert_eq!(estimate.tier, "pro");
+    // 2 * 0.05 + 4 * 0.01 + 40 * 0.001 = 0.18 per hour
+    assert!((estimate.hourly - 0.18).abs() < 1e-9);
+    assert!((estimate.monthly - 0.18 * 730.0).abs() < 1e-9);
+
+    let mut unpriced = test_deployer(mock_server.uri()).build().unwrap();
+    unpriced.discover_teepod().await.unwrap();
+    match unpriced.estimate_cost(1, 1024, 10) {
+        Err(Error::Configuration(message)) => assert!(message.contains("with_price_table")),
+        other => panic!("expected missing price table error, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_deploy_as_user_scopes_deployment() {
+    let mock_server = MockServer::start().await;
+    mount_discovery(&mock_server, vec![teepod_image("dstack-0.3.5", false)]).await;
+
+    Mock::given(method("POST"))
+        .and(path("/cvms/pubkey/from_cvm_configuration"))
+        .and(body_partial_json(json!({ "user_id": "sub_1" })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "app_env_encrypt_pubkey": format!("0x{}", hex::encode([1u8; 32])),
+            "app_id": "app_1",
+            "app_id_salt": "test_salt",
+            "compose_manifest": { "name": "test", "features": [], "docker_compose_file": "" },
+            "disk_size": 10,
+            "encrypted_env": "",
+            "image": "test:latest",
+            "listed": false,
+            "memory": 1024,
+            "name": "test",
+            "teepod_id": 7,
+            "vcpu": 1,
+            "user_id": "sub_1"
+        })))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/cvms/from_cvm_configuration"))
+        .and(body_partial_json(json!({ "user_id": "sub_1" })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "id": 42,
+            "status": "pending"
+        })))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let mut deployer = test_deployer(mock_server.uri())
+        .deploy_as_user("sub_1")
+        .build()
+        .unwrap();
+    deployer.discover_teepod().await.unwrap();
+
+    let result = deployer
+        .deploy_compose(
+            "services: {}",
+            "app",
+            HashMap::<String, String>::new(),
+            None,
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+    assert_eq!(result.details.unwrap()["user_id"], "sub_1");
+}
+
+#[cfg(feature = "schema")]
+#[test]
+fn test_vm_config_json_schema_properties() {
+    let schema: serde_json::Value = serde_json::from_str(&VmConfig::json_schema_string()).unwrap();
+    let properties = schema["properties"].as_object().unwrap();
+    for field in [
+        "name",
+        "compose_manifest",
+        "vcpu",
+        "memory",
+        "disk_size",
+        "teepod_id",
+        "image",
+        "advanced_features",
+    ] {
+        assert!(properties.contains_key(field), "missing property {field}");
+    }
+    assert!(schema["definitions"]["DockerConfig"].is_object());
+}
+
+/// Clock that advances only when slept on, recording every sleep.
+#[derive(Clone)]
+struct FakeClock {
+    start: std::time::Instant,
+    sleeps: std::sync::Arc<std::sync::Mutex<Vec<Duration>>>,
+}
+
+impl FakeClock {
+    fn new() -> Self {
+        Self {
+            start: std::time::Instant::now(),
+            sleeps: Default::default(),
+        }
+    }
+
+    fn sleeps(&self) -> Vec<Duration> {
+        self.sleeps.lock().unwrap().clone()
+    }
+}
+
+#[async_trait::async_trait]
+impl Clock for FakeClock {
+    fn now(&self) -> std::time::Instant {
+        self.start + self.sleeps().iter().sum::<Duration>()
+    }
+
+    async fn sleep(&self, duration: Duration) {
+        self.sleeps.lock().unwrap().push(duration);
+    }
+}
+
+#[tokio::test]
+async fn test_wait_for_status_backoff_with_fake_clock() {
+    let mock_server = MockServer::start().await;
+    mount_state(&mock_server, "starting", None).await;
+
+    let clock = FakeClock::new();
+    let deployer = test_deployer(mock_server.uri())
+        .with_clock(clock.clone())
+        .build()
+        .unwrap();
+    let result = deployer
+        .wait_for_status(
+            "app_1",
+            DeploymentStatus::Running,
+            Duration::from_secs(60),
+            PollStrategy::Exponential {
+                base: Duration::from_secs(1),
+                factor: 2.0,
+                max: Duration::from_secs(16),
+            },
+        )
+        .await;
+
+    assert!(matches!(result, Err(Error::Timeout { .. })));
+    // One minute of backoff elapsed without any real sleeping
+    let secs: Vec<u64> = clock.sleeps().iter().map(Duration::as_secs).collect();
+    assert_eq!(secs, vec![1, 2, 4, 8, 16, 16, 16]);
+}
+
+#[tokio::test]
+async fn test_update_settings_sends_only_changed_features() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("PATCH"))
+        .and(path("/cvms/app_1"))
+        .and(body_partial_json(json!({
+            "advanced_features": { "public_logs": false }
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "status": "ok",
+            "message": "Settings updated"
+        })))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let client = TeeClient::new(create_test_config(mock_server.uri())).unwrap();
+    let response = client
+        .update_settings("app_1", &SettingsUpdate::new().with_public_logs(false))
+        .await
+        .unwrap();
+    assert_eq!(response.status, "ok");
+    assert_eq!(response.message.as_deref(), Some("Settings updated"));
+
+    let requests = mock_server.received_requests().await.unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&requests[0].body).unwrap();
+    assert_eq!(
+        body,
+        json!({ "advanced_features": { "public_logs": false } })
+    );
+
+    assert!(matches!(
+        client
+            .update_settings("app_1", &SettingsUpdate::new())
+            .await,
+        Err(Error::Configuration(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_html_error_page_is_summarized() {
+    let mock_server = MockServer::start().await;
+    let page = "<html>\r\n<head><title>502 Bad Gateway</title></head>\r\n<body>\r\n<center><h1>502 Bad Gateway</h1></center>\r\n<hr><center>cloudflare</center>\r\n</body>\r\n</html>\r\n";
+    Mock::given(method("GET"))
+        .and(path("/teepods/available"))
+        .respond_with(ResponseTemplate::new(502).set_body_raw(page, "text/html"))
+        .mount(&mock_server)
+        .await;
+
+    let client = TeeClient::new(create_test_config(mock_server.uri())).unwrap();
+    let err = client.get_available_teepods().await.unwrap_err();
+
+    assert_eq!(
+        err.to_string(),
+        "API error: 502 - Bad Gateway: non-JSON HTML error from upstream proxy"
+    );
+    assert!(err.hint().is_some());
+    match err {
+        Error::UpstreamHtml { body, .. } => assert_eq!(body, page),
+        other => panic!("expected upstream HTML error, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_system_stats_display() {
+    let stats = SystemStatsResponse {
+        is_online: true,
+        is_public: false,
+        error: None,
+        sysinfo: system_info(4, 1.0),
+    };
+    let output = stats.to_string();
+
+    assert!(output.contains("OS: Linux 6.1"));
+    assert!(output.contains("Used memory: 0 MB (25.0%)"));
+    assert!(output.contains("System uptime: 1d 1h 1m 1s"));
+    assert!(output.contains("No disk information available"));
+    // No swap section without swap
+    assert!(!output.contains("Swap Usage"));
+}
+
+#[test]
+fn test_deployment_response_display() {
+    let deployment: DeploymentResponse = serde_json::from_value(json!({
+        "id": 42,
+        "status": "pending",
+        "app_id": "app_1",
+        "image": "dstack-0.3.5"
+    }))
+    .unwrap();
+
+    assert_eq!(
+        deployment.to_string(),
+        "Deployment ID: 42\nStatus: pending\nApp ID: app_1\nImage: dstack-0.3.5"
+    );
+}
+
+#[test]
+fn test_deployment_response_tolerates_unusual_ids() {
+    let parse =
+        |value: serde_json::Value| -> DeploymentResponse { serde_json::from_value(value).unwrap() };
+
+    // Float-encoded IDs
+    assert_eq!(parse(json!({ "id": 42.0 })).id, 42);
+    assert_eq!(parse(json!({ "id": "1.5e3" })).id, 1500);
+
+    // Oversized IDs are kept as text in `app_id`
+    let oversized = parse(json!({ "id": "123456789012345678901234567890" }));
+    assert_eq!(oversized.id, 0);
+    assert_eq!(
+        oversized.app_id.as_deref(),
+        Some("123456789012345678901234567890")
+    );
+    let oversized: DeploymentResponse =
+        serde_json::from_str(r#"{ "id": 123456789012345678901234567890 }"#).unwrap();
+    assert_eq!(oversized.id, 0);
+    let preserved: f64 = oversized.app_id.unwrap().parse().unwrap();
+    assert_eq!(preserved, 123456789012345678901234567890.0);
+
+    // A reported app ID wins, and non-numeric IDs are not copied
+    let with_app_id = parse(json!({ "id": 1.5, "app_id": "app_abc" }));
+    assert_eq!(with_app_id.id, 0);
+    assert_eq!(with_app_id.app_id.as_deref(), Some("app_abc"));
+    let uuid = parse(json!({ "uuid": "6f1c2d3e-aaaa-bbbb-cccc-1234567890ab" }));
+    assert_eq!(uuid.id, 0);
+    assert_eq!(uuid.app_id, None);
+}
+
+#[tokio::test]
+async fn test_compose_version_normalization() {
+    let mock_server = MockServer::start().await;
+    mount_discovery(&mock_server, vec![teepod_image("dstack-0.3.5", false)]).await;
+    let compose = "version: '3'\nservices:\n  app:\n    image: nginx\n";
+
+    let mut deployer = test_deployer(mock_server.uri()).build().unwrap();
+    deployer.discover_teepod().await.unwrap();
+    let vm_config = deployer
+        .create_vm_config(compose, "app", None, None, None)
+        .unwrap();
+    assert_eq!(
+        vm_config.compose_manifest.docker_compose_file,
+        "services:\n  app:\n    image: nginx\n"
+    );
+
+    let mut deployer = test_deployer(mock_server.uri())
+        .with_strip_compose_version(false)
+        .build()
+        .unwrap();
+    deployer.discover_teepod().await.unwrap();
+    let vm_config = deployer
+        .create_vm_config(compose, "app", None, None, None)
+        .unwrap();
+    assert_eq!(vm_config.compose_manifest.docker_compose_file, compose);
+}
+
+#[tokio::test]
+async fn test_restart_deployment_stops_then_starts() {
+    let mock_server = MockServer::start().await;
+    let cvm = |status: &str| {
+        ResponseTemplate::new(200).set_body_json(json!({
+            "id": 1,
+            "status": status,
+            "name": "app"
+        }))
+    };
+    Mock::given(method("POST"))
+        .and(path("/cvms/app_1/stop"))
+        .respond_with(cvm("stopping"))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+    mount_state(&mock_server, "stopping", Some(1)).await;
+    mount_state(&mock_server, "stopped", None).await;
+    Mock::given(method("POST"))
+        .and(path("/cvms/app_1/start"))
+        .respond_with(cvm("starting"))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let deployer = test_deployer(mock_server.uri())
+        .with_clock(FakeClock::new())
+        .build()
+        .unwrap();
+    let info = deployer
+        .restart_deployment("app_1", Duration::from_secs(30))
+        .await
+        .unwrap();
+
+    assert_eq!(info.status, "starting");
+    let paths: Vec<String> = mock_server
+        .received_requests()
+        .await
+        .unwrap()
+        .iter()
+        .map(|r| r.url.path().to_string())
+        .collect();
+    assert_eq!(
+        paths,
+        vec![
+            "/cvms/app_1/stop",
+            "/cvms/app_1/state",
+            "/cvms/app_1/state",
+            "/cvms/app_1/start"
+        ]
+    );
+}
+
+#[test]
+fn test_app_key_newtypes_validate() {
+    assert_eq!(test_pubkey().as_str(), format!("0x{}", "01".repeat(32)));
+    // Unprefixed keys are accepted as returned
+    assert!(AppPublicKey::try_from("01".repeat(32)).is_ok());
+
+    assert!(matches!(
+        AppPublicKey::try_from("0xnot-hex"),
+        Err(Error::InvalidKey(_))
+    ));
+    assert!(matches!(
+        AppPublicKey::try_from("0xabc"),
+        Err(Error::InvalidKey(_))
+    ));
+    // A salt is not a 32-byte key
+    assert!(AppPublicKey::try_from("test_salt").is_err());
+    assert!(matches!(
+        AppIdSalt::try_from(""),
+        Err(Error::Configuration(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_max_deployable_resources_floors_remaining_capacity() {
+    let mock_server = MockServer::start().await;
+    let mut node = teepod_node(7, vec![teepod_image("dstack-0.3.5", false)]);
+    node["remaining_vcpu"] = json!(3.75);
+    node["remaining_memory"] = json!(6143.9);
+    mount_nodes(&mock_server, vec![node]).await;
+
+    let mut deployer = test_deployer(mock_server.uri()).build().unwrap();
+    assert_eq!(deployer.max_deployable_resources(), None);
+
+    deployer.discover_teepod().await.unwrap();
+    assert_eq!(deployer.max_deployable_resources(), Some((3, 6143, 100)));
+}
+
+#[tokio::test]
+async fn test_util_happy_path_deploys() {
+    let mock_server = MockServer::start().await;
+    test_util::mount_happy_path(&mock_server).await;
+
+    let mut deployer = test_deployer(mock_server.uri()).build().unwrap();
+    deployer.discover_teepod().await.unwrap();
+    let env_vars = HashMap::from([("TOKEN".to_string(), "secret".to_string())]);
+    let result = deployer
+        .deploy_compose("services: {}", "app", env_vars, None, None, None)
+        .await
+        .unwrap();
+
+    assert_eq!(result.id, test_util::MOCK_DEPLOYMENT_ID);
+    assert_eq!(result.app_id.as_deref(), Some(test_util::MOCK_APP_ID));
+    let details = result.details.unwrap();
+    assert_eq!(details["teepod_id"], test_util::MOCK_TEEPOD_ID);
+    assert_eq!(details["image"], test_util::MOCK_IMAGE);
+}
+
+#[tokio::test]
+async fn test_unresolved_compose_variables() {
+    let mock_server = MockServer::start().await;
+    mount_discovery(&mock_server, vec![teepod_image("dstack-0.3.5", false)]).await;
+    mount_deploy_endpoints(&mock_server, false).await;
+    let compose = "services:\n  app:\n    image: app:${TAG:-latest}\n    environment:\n      DB_URL: ${DB_URL}\n      API_TOKEN: ${API_TOKEN}\n";
+    let env_vars = HashMap::from([("DB_URL".to_string(), "postgres://db".to_string())]);
+
+    let mut strict = test_deployer(mock_server.uri())
+        .with_strict_env_check(true)
+        .build()
+        .unwrap();
+    strict.discover_teepod().await.unwrap();
+    match strict
+        .deploy_compose(compose, "app", env_vars.clone(), None, None, None)
+        .await
+    {
+        Err(Error::Configuration(message)) => {
+            assert!(message.ends_with(": API_TOKEN"), "{}", message)
+        }
+        other => panic!("expected unresolved variable error, got {:?}", other),
+    }
+    // Nothing was sent to the API
+    assert!(mock_server
+        .received_requests()
+        .await
+        .unwrap()
+        .iter()
+        .all(|r| r.url.path() == "/teepods/available"));
+
+    // Without strict checking the deploy proceeds with a warning
+    let mut lenient = test_deployer(mock_server.uri()).build().unwrap();
+    lenient.discover_teepod().await.unwrap();
+    let result = lenient
+        .deploy_compose(compose, "app", env_vars, None, None, None)
+        .await
+        .unwrap();
+    assert_eq!(result.id, 42);
+}
+
+#[tokio::test]
+async fn test_raw_get_and_post() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/kms/keys"))
+        .and(header("x-api-key", "test_api_key"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "keys": ["k1"] })))
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/cvms/app_1/upgrade"))
+        .and(body_partial_json(json!({ "image": "dstack-0.4.0" })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "accepted": true })))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/missing"))
+        .respond_with(ResponseTemplate::new(404).set_body_string("not found"))
+        .mount(&mock_server)
+        .await;
+
+    let client = TeeClient::new(create_test_config(mock_server.uri())).unwrap();
+
+    let keys = client.get_raw("/kms/keys").await.unwrap();
+    assert_eq!(keys["keys"][0], "k1");
+    // The leading slash is optional
+    assert_eq!(client.get_raw("kms/keys").await.unwrap(), keys);
+
+    let upgraded = client
+        .post_raw("/cvms/app_1/upgrade", json!({ "image": "dstack-0.4.0" }))
+        .await
+        .unwrap();
+    assert_eq!(upgraded["accepted"], true);
+
+    assert!(matches!(
+        client.get_raw("/missing").await,
+        Err(Error::Api {
+            status_code: 404,
+            ..
+        })
+    ));
+}
+
+#[tokio::test]
+async fn test_secret_env_values_are_redacted() {
+    let secret = Secret::new("super-secret-password".to_string());
+    assert!(!format!("{:?}", secret).contains("super-secret-password"));
+    assert_eq!(secret.to_string(), "[redacted]");
+
+    let env_vars = HashMap::from([("DB_PASSWORD".to_string(), secret)]);
+    assert!(!format!("{:?}", env_vars).contains("super-secret-password"));
+
+    let mock_server = MockServer::start().await;
+    test_util::mount_happy_path(&mock_server).await;
+    let mut deployer = test_deployer(mock_server.uri()).build().unwrap();
+    deployer.discover_teepod().await.unwrap();
+    let result = deployer
+        .deploy_compose_secret("services: {}", "app", env_vars, None, None, None)
+        .await
+        .unwrap();
+    assert_eq!(result.id, test_util::MOCK_DEPLOYMENT_ID);
+}
+
+#[tokio::test]
+async fn test_deploy_compose_with_prefetched_pubkey() {
+    let mock_server = MockServer::start().await;
+    mount_discovery(&mock_server, vec![teepod_image("dstack-0.3.5", false)]).await;
+    Mock::given(method("POST"))
+        .and(path("/cvms/pubkey/from_cvm_configuration"))
+        .respond_with(ResponseTemplate::new(500))
+        .expect(0)
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/cvms/from_cvm_configuration"))
+        .and(body_partial_json(json!({
+            "name": "app",
+            "encrypted_env": "c0ffee",
+            "app_id_salt": "test_salt"
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "id": 42,
+            "status": "pending"
+        })))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let mut deployer = test_deployer(mock_server.uri()).build().unwrap();
+    deployer.discover_teepod().await.unwrap();
+    let result = deployer
+        .deploy_compose_with_pubkey(
+            "services: {}",
+            "app",
+            "c0ffee".to_string(),
+            &test_pubkey(),
+            &test_salt(),
+            None,
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(result.id, 42);
+}
+
+#[tokio::test]
+async fn test_operation_timeout_spans_deploy_steps() {
+    let mock_server = MockServer::start().await;
+    mount_discovery(&mock_server, vec![teepod_image("dstack-0.3.5", false)]).await;
+    Mock::given(method("POST"))
+        .and(path("/cvms/pubkey/from_cvm_configuration"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(test_util::mock_pubkey_response())
+                .set_delay(Duration::from_millis(300)),
+        )
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/cvms/from_cvm_configuration"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "id": 42 })))
+        .expect(0)
+        .mount(&mock_server)
+        .await;
+
+    let mut deployer = test_deployer(mock_server.uri())
+        .with_operation_timeout(Duration::from_millis(200))
+        .build()
+        .unwrap();
+    deployer.discover_teepod().await.unwrap();
+    let result = deployer
+        .deploy_compose(
+            "services: {}",
+            "app",
+            HashMap::<String, String>::new(),
+            None,
+            None,
+            None,
+        )
+        .await;
+
+    match result {
+        Err(Error::Timeout { operation }) => assert!(operation.ends_with("before deploying")),
+        other => panic!("expected deadline error, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_deploy_compose_env_var_input_shapes() {
+    let mock_server = MockServer::start().await;
+    test_util::mount_happy_path(&mock_server).await;
+    let mut deployer = test_deployer(mock_server.uri())
+        .with_strict_env_check(true)
+        .build()
+        .unwrap();
+    deployer.discover_teepod().await.unwrap();
+    // Strict mode rejects the deploy unless PORT made it into the env map
+    let compose = "services:\n  app:\n    image: app\n    ports:\n      - ${PORT}\n";
+
+    let map = HashMap::from([("PORT".to_string(), "3000".to_string())]);
+    let vec = vec![("PORT".to_string(), "3000".to_string())];
+    let array = [("PORT", "3000")];
+
+    let from_map = deployer
+        .deploy_compose(compose, "app", map, None, None, None)
+        .await
+        .unwrap();
+    let from_vec = deployer
+        .deploy_compose(compose, "app", vec, None, None, None)
+        .await
+        .unwrap();
+    let from_array = deployer
+        .deploy_compose(compose, "app", array, None, None, None)
+        .await
+        .unwrap();
+
+    for result in [from_map, from_vec, from_array] {
+        assert_eq!(result.id, test_util::MOCK_DEPLOYMENT_ID);
+    }
+}
+
+#[cfg(feature = "chrono")]
+#[test]
+fn test_network_info_latest_handshake_time() {
+    let mut info = network_info(true, "", "");
+    let time = info.latest_handshake_time().unwrap();
+    assert_eq!(time.to_rfc3339(), "2024-03-14T12:00:00+00:00");
+
+    info.latest_handshake = "2024-03-14T14:00:00+02:00".to_string();
+    assert_eq!(info.latest_handshake_time(), Some(time));
+
+    for raw in ["", "   ", "never", "2024-13-40"] {
+        info.latest_handshake = raw.to_string();
+        assert!(info.latest_handshake_time().is_none(), "parsed {raw:?}");
+    }
+}
+
+#[tokio::test]
+async fn test_deploy_config_with_salt_override() {
+    let mock_server = MockServer::start().await;
+    mount_discovery(&mock_server, vec![teepod_image("dstack-0.3.5", false)]).await;
+    Mock::given(method("POST"))
+        .and(path("/cvms/pubkey/from_cvm_configuration"))
+        .and(body_partial_json(json!({ "app_id_salt": "my_fixed_salt" })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(test_util::mock_pubkey_response()))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/cvms/from_cvm_configuration"))
+        .and(body_partial_json(json!({ "app_id_salt": "my_fixed_salt" })))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(test_util::mock_deployment_response()),
+        )
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let mut deployer = test_deployer(mock_server.uri()).build().unwrap();
+    deployer.discover_teepod().await.unwrap();
+    let vm_config = deployer
+        .create_vm_config("services: {}", "app", None, None, None)
+        .unwrap();
+
+    let result = deployer
+        .deploy_config(vm_config.clone(), [("PORT", "3000")], Some("my_fixed_salt"))
+        .await
+        .unwrap();
+    assert_eq!(result.id, test_util::MOCK_DEPLOYMENT_ID);
+
+    assert!(matches!(
+        deployer
+            .deploy_config(vm_config, [("PORT", "3000")], Some(""))
+            .await,
+        Err(Error::Configuration(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_deploy_config_rejects_image_not_offered() {
+    let mock_server = MockServer::start().await;
+    mount_discovery(
+        &mock_server,
+        vec![
+            teepod_image("dstack-0.3.5", false),
+            teepod_image("dstack-dev-0.3.5", true),
+        ],
+    )
+    .await;
+    Mock::given(method("POST"))
+        .and(path("/cvms/pubkey/from_cvm_configuration"))
+        .respond_with(ResponseTemplate::new(500))
+        .mount(&mock_server)
+        .await;
+
+    let mut deployer = test_deployer(mock_server.uri()).build().unwrap();
+    deployer.discover_teepod().await.unwrap();
+    let mut vm_config = deployer
+        .create_vm_config("services: {}", "app", None, None, None)
+        .unwrap();
+    vm_config.image = "dstack-0.4.0".to_string();
+
+    match deployer
+        .deploy_config(vm_config.clone(), HashMap::<String, String>::new(), None)
+        .await
+    {
+        Err(Error::Configuration(message)) => {
+            assert!(message.contains("'dstack-0.4.0'"));
+            assert!(message.contains("dstack-0.3.5, dstack-dev-0.3.5"));
+        }
+        other => panic!("expected image error, got {:?}", other),
+    }
+    let requests = mock_server.received_requests().await.unwrap();
+    assert!(requests
+        .iter()
+        .all(|r| r.method == wiremock::http::Method::Get));
+
+    // Opting out leaves the decision to the server
+    let mut unchecked = test_deployer(mock_server.uri())
+        .with_image_check(false)
+        .build()
+        .unwrap();
+    unchecked.discover_teepod().await.unwrap();
+    assert!(matches!(
+        unchecked
+            .deploy_config(vm_config, HashMap::<String, String>::new(), None)
+            .await,
+        Err(Error::Api {
+            status_code: 500,
+            ..
+        })
+    ));
+}
+
+#[tokio::test]
+async fn test_watch_until_terminal_returns_failure_logs() {
+    let mock_server = MockServer::start().await;
+    mount_state(&mock_server, "pending", Some(1)).await;
+    mount_state(&mock_server, "failed", None).await;
+    Mock::given(method("GET"))
+        .and(path("/cvms/app_1/logs"))
+        .and(query_param("since", "c1"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "lines": [{ "message": "error: DB_URL is not set" }],
+            "next_cursor": "c2"
+        })))
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/cvms/app_1/logs"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "lines": [{ "message": "pulling image" }],
+            "next_cursor": "c1"
+        })))
+        .mount(&mock_server)
+        .await;
+
+    let deployer = test_deployer(mock_server.uri())
+        .with_clock(FakeClock::new())
+        .build()
+        .unwrap();
+    let (status, logs) = deployer
+        .watch_until_terminal("app_1", Duration::from_secs(60))
+        .await
+        .unwrap();
+
+    assert_eq!(status, DeploymentStatus::Failed);
+    let messages: Vec<_> = logs.iter().map(|line| line.message.as_str()).collect();
+    assert_eq!(messages, ["pulling image", "error: DB_URL is not set"]);
+}
+
+#[tokio::test]
+async fn test_watch_until_terminal_timeout_includes_logs() {
+    let mock_server = MockServer::start().await;
+    mount_state(&mock_server, "starting", None).await;
+    Mock::given(method("GET"))
+        .and(path("/cvms/app_1/logs"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "lines": [{ "message": "waiting for database" }]
+        })))
+        .mount(&mock_server)
+        .await;
+
+    let deployer = test_deployer(mock_server.uri())
+        .with_clock(FakeClock::new())
+        .build()
+        .unwrap();
+    match deployer
+        .watch_until_terminal("app_1", Duration::from_secs(5))
+        .await
+    {
+        Err(Error::Timeout { operation }) => assert!(operation.contains("waiting for database")),
+        other => panic!("expected timeout error, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_per_call_timeout_overrides_client_default() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/cvms/app_1/stats"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(json!({
+                    "is_online": true,
+                    "is_public": true,
+                    "error": null,
+                    "sysinfo": system_info(4, 1.0)
+                }))
+                .set_delay(Duration::from_millis(500)),
+        )
+        .mount(&mock_server)
+        .await;
+
+    let deployer = test_deployer(mock_server.uri())
+        .with_request_timeout(Duration::from_millis(100))
+        .build()
+        .unwrap();
+
+    assert!(matches!(
+        deployer.get_system_stats("app_1").await,
+        Err(Error::Timeout { .. })
+    ));
+
+    let options = RequestOptions::new().with_timeout(Duration::from_secs(5));
+    let stats = deployer
+        .get_system_stats_with_options("app_1", &options)
+        .await
+        .unwrap();
+    assert_eq!(stats.sysinfo.num_cpus, 4);
+}
+
+#[tokio::test]
+async fn test_deploy_rejects_conflicting_host_ports() {
+    let mock_server = MockServer::start().await;
+    mount_discovery(&mock_server, vec![teepod_image("dstack-0.3.5", false)]).await;
+    Mock::given(method("POST"))
+        .and(path("/cvms/pubkey/from_cvm_configuration"))
+        .respond_with(ResponseTemplate::new(500))
+        .expect(0)
+        .mount(&mock_server)
+        .await;
+
+    let mut deployer = test_deployer(mock_server.uri()).build().unwrap();
+    deployer.discover_teepod().await.unwrap();
+    let compose = "services:\n  web:\n    image: nginx\n    ports:\n      - \"80:80\"\n  proxy:\n    image: caddy\n    ports:\n      - \"80:80/tcp\"\n";
+    match deployer
+        .deploy_compose(compose, "app", [("PORT", "80")], None, None, None)
+        .await
+    {
+        Err(Error::Configuration(message)) => {
+            assert!(message.contains("'web' (80/tcp) and 'proxy' (80/tcp)"))
+        }
+        other => panic!("expected port conflict error, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_deploy_checks_and_preserves_networks() {
+    let mock_server = MockServer::start().await;
+    mount_discovery(&mock_server, vec![teepod_image("dstack-0.3.5", false)]).await;
+    Mock::given(method("POST"))
+        .and(path("/cvms/pubkey/from_cvm_configuration"))
+        .respond_with(ResponseTemplate::new(500))
+        .expect(0)
+        .mount(&mock_server)
+        .await;
+
+    let mut deployer = test_deployer(mock_server.uri()).build().unwrap();
+    deployer.discover_teepod().await.unwrap();
+
+    let compose = "services:\n  api:\n    image: api\n    networks:\n      - backend\nnetworks:\n  backend:\n    driver: bridge\n";
+    let vm_config = deployer
+        .create_vm_config(compose, "app", None, None, None)
+        .unwrap();
+    assert_eq!(vm_config.compose_manifest.docker_compose_file, compose);
+
+    let dangling = compose.replace("      - backend", "      - frontend");
+    match deployer
+        .deploy_compose(
+            &dangling,
+            "app",
+            HashMap::<String, String>::new(),
+            None,
+            None,
+            None,
+        )
+        .await
+    {
+        Err(Error::Configuration(message)) => assert!(message.contains("'frontend'")),
+        other => panic!("expected network error, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_rotate_secrets_waits_for_health() {
+    let mock_server = MockServer::start().await;
+    let secret = x25519_dalek::StaticSecret::from([5u8; 32]);
+    let pubkey = x25519_dalek::PublicKey::from(&secret);
+    Mock::given(method("GET"))
+        .and(path("/cvms/app_1/compose"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "compose_file": { "compose_manifest": { "docker_compose_file": "services: {}" } },
+            "env_pubkey": format!("0x{}", hex::encode(pubkey.as_bytes()))
+        })))
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("PUT"))
+        .and(path("/cvms/app_1/compose"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "ok": true })))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+    mount_state(&mock_server, "starting", Some(1)).await;
+    mount_state(&mock_server, "running", None).await;
+    Mock::given(method("GET"))
+        .and(path("/cvms/app_1/network"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "is_online": true,
+            "is_public": true,
+            "error": null,
+            "internal_ip": "10.0.0.2",
+            "latest_handshake": "2024-03-14T12:00:00Z",
+            "public_urls": { "app": "https://app-1.example", "instance": "https://instance-1.example" }
+        })))
+        .expect(2)
+        .mount(&mock_server)
+        .await;
+
+    let clock = FakeClock::new();
+    let deployer = test_deployer(mock_server.uri())
+        .with_clock(clock.clone())
+        .build()
+        .unwrap();
+    let new_env = HashMap::from([("DB_PASSWORD".to_string(), "rotated".to_string())]);
+    deployer.rotate_secrets("app_1", new_env).await.unwrap();
+    assert_eq!(clock.sleeps(), [Duration::from_secs(2)]);
+
+    let requests = mock_server.received_requests().await.unwrap();
+    let put = requests
+        .iter()
+        .find(|r| r.method == wiremock::http::Method::Put)
+        .unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&put.body).unwrap();
+    let decrypted = Encryptor::decrypt_json(
+        body["encrypted_env"].as_str().unwrap(),
+        &hex::encode(secret.to_bytes()),
+    )
+    .unwrap();
+    assert_eq!(
+        decrypted["env"],
+        json!([{ "key": "DB_PASSWORD", "value": "rotated" }])
+    );
+}
+
+// Helper function to mount the compose endpoints used to rotate app_1's secrets
+async fn mount_compose_update(mock_server: &MockServer) {
+    let pubkey = x25519_dalek::PublicKey::from(&x25519_dalek::StaticSecret::from([5u8; 32]));
+    Mock::given(method("GET"))
+        .and(path("/cvms/app_1/compose"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "compose_file": { "compose_manifest": { "docker_compose_file": "services: {}" } },
+            "env_pubkey": format!("0x{}", hex::encode(pubkey.as_bytes()))
+        })))
+        .mount(mock_server)
+        .await;
+    Mock::given(method("PUT"))
+        .and(path("/cvms/app_1/compose"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "ok": true })))
+        .mount(mock_server)
+        .await;
+}
+
+// Helper function to mount app_1's network info with the given handshake
+async fn mount_handshake(mock_server: &MockServer, handshake: &str, times: Option<u64>) {
+    let mock = Mock::given(method("GET"))
+        .and(path("/cvms/app_1/network"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+        "is_online": true,
+        "is_public": true,
+        "error": null,
+        "internal_ip": "10.0.0.2",
+        "latest_handshake": handshake,
+        "public_urls": { "app": "https://app-1.example", "instance": "https://instance-1.example" }
+    })));
+    match times {
+        Some(n) => mock.up_to_n_times(n).mount(mock_server).await,
+        None => mock.mount(mock_server).await,
+    }
+}
+
+#[tokio::test]
+async fn test_rotate_secrets_waits_for_restart() {
+    // The status never leaves "running"; only the handshake reveals the restart
+    let mock_server = MockServer::start().await;
+    mount_compose_update(&mock_server).await;
+    mount_state(&mock_server, "running", None).await;
+    mount_handshake(&mock_server, "2024-03-14T12:00:00Z", Some(3)).await;
+    mount_handshake(&mock_server, "2024-03-14T12:05:00Z", None).await;
+
+    let clock = FakeClock::new();
+    let deployer = test_deployer(mock_server.uri())
+        .with_clock(clock.clone())
+        .build()
+        .unwrap();
+    let new_env = HashMap::from([("DB_PASSWORD".to_string(), "rotated".to_string())]);
+    deployer
+        .rotate_secrets("app_1", new_env.clone())
+        .await
+        .unwrap();
+    // The first two polls still saw the pre-update instance
+    assert_eq!(clock.sleeps().len(), 2);
+
+    // Without an observed restart the rotation is not reported as verified
+    let stuck = MockServer::start().await;
+    mount_compose_update(&stuck).await;
+    mount_state(&stuck, "running", None).await;
+    mount_handshake(&stuck, "2024-03-14T12:00:00Z", None).await;
+    let deployer = test_deployer(stuck.uri())
+        .with_clock(FakeClock::new())
+        .with_operation_timeout(Duration::from_secs(10))
+        .build()
+        .unwrap();
+    match deployer.rotate_secrets("app_1", new_env).await {
+        Err(Error::Timeout { operation }) => {
+            assert!(operation.contains("was not seen restarting"))
+        }
+        other => panic!("expected timeout, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_rotate_secrets_stops_on_failure_after_update() {
+    let failed = MockServer::start().await;
+    mount_compose_update(&failed).await;
+    mount_state(&failed, "failed", None).await;
+    let deployer = test_deployer(failed.uri())
+        .with_clock(FakeClock::new())
+        .build()
+        .unwrap();
+    let new_env = HashMap::from([("DB_PASSWORD".to_string(), "rotated".to_string())]);
+    match deployer.rotate_secrets("app_1", new_env.clone()).await {
+        Err(Error::DeploymentFailed { app_id, message }) => {
+            assert_eq!(app_id, "app_1");
+            assert!(message.contains("the new secrets are active"));
+        }
+        other => panic!("expected deployment failure, got {:?}", other),
+    }
+
+    // A rejected key ends the wait instead of polling until the timeout
+    let revoked = MockServer::start().await;
+    mount_compose_update(&revoked).await;
+    Mock::given(method("GET"))
+        .and(path("/cvms/app_1/state"))
+        .respond_with(ResponseTemplate::new(401).set_body_string("unauthorized"))
+        .mount(&revoked)
+        .await;
+    let clock = FakeClock::new();
+    let deployer = test_deployer(revoked.uri())
+        .with_clock(clock.clone())
+        .build()
+        .unwrap();
+    assert!(matches!(
+        deployer.rotate_secrets("app_1", new_env).await,
+        Err(Error::Api {
+            status_code: 401,
+            ..
+        })
+    ));
+    assert!(clock.sleeps().is_empty());
+}
+
+#[tokio::test]
+async fn test_rotate_secrets_reports_active_secrets_on_failure() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/cvms/app_1/compose"))
+        .respond_with(ResponseTemplate::new(404).set_body_string("CVM not found"))
+        .mount(&mock_server)
+        .await;
+
+    let deployer = test_deployer(mock_server.uri()).build().unwrap();
+    let new_env = HashMap::from([("DB_PASSWORD".to_string(), "rotated".to_string())]);
+    match deployer.rotate_secrets("app_1", new_env).await {
+        Err(Error::Api {
+            status_code: 404,
+            message,
+        }) => assert!(message.contains("the old secrets are still active")),
+        other => panic!("expected rotation error, got {:?}", other),
+    }
+
+    // Transport failures keep their variant
+    let timed_out = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/cvms/app_1/compose"))
+        .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_millis(500)))
+        .mount(&timed_out)
+        .await;
+    let deployer = test_deployer(timed_out.uri())
+        .with_request_timeout(Duration::from_millis(50))
+        .build()
+        .unwrap();
+    let new_env = HashMap::from([("DB_PASSWORD".to_string(), "rotated".to_string())]);
+    match deployer.rotate_secrets("app_1", new_env).await {
+        Err(Error::Timeout { operation }) => {
+            assert!(operation.contains("unknown whether the old or new secrets are active"))
+        }
+        other => panic!("expected timeout, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_search_listed_deployments() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/cvms/listed"))
+        .and(query_param("search", "Indexer"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([
+            { "app_id": "app_1", "name": "team-a-indexer", "status": "running", "listed": true },
+            { "app_id": "app_2", "name": "indexer-staging", "status": "stopped" },
+            { "app_id": "app_3", "name": "team-b-indexer", "status": "running", "listed": false },
+            { "app_id": "app_4", "name": "gateway", "status": "running", "listed": true }
+        ])))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let client = TeeClient::new(create_test_config(mock_server.uri())).unwrap();
+    let results = client.search_listed_deployments("Indexer").await.unwrap();
+
+    let app_ids: Vec<_> = results.iter().map(|d| d.app_id.as_str()).collect();
+    assert_eq!(app_ids, ["app_1", "app_2"]);
+    assert_eq!(results[0].status, "running");
+}
+
+#[tokio::test]
+async fn test_logs_stream_ends_after_consecutive_errors() {
+    use futures_util::StreamExt;
+
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/cvms/app_1/logs"))
+        .respond_with(ResponseTemplate::new(404).set_body_string("CVM not found"))
+        .expect(3)
+        .mount(&mock_server)
+        .await;
+
+    let deployer = test_deployer(mock_server.uri())
+        .with_clock(FakeClock::new())
+        .build()
+        .unwrap();
+    let items: Vec<_> = deployer
+        .get_logs_stream_with_error_limit("app_1", 3)
+        .collect()
+        .await;
+
+    assert_eq!(items.len(), 3);
+    assert!(items.iter().all(|item| matches!(
+        item,
+        Err(Error::Api {
+            status_code: 404,
+            ..
+        })
+    )));
+}
+
+#[test]
+fn test_vm_config_json_value_round_trip() {
+    let vm_config = VmConfig {
+        name: "app".to_string(),
+        compose_manifest: ComposeManifest {
+            name: "app".to_string(),
+            features: vec!["kms".to_string()],
+            docker_compose_file: "services: {}".to_string(),
+            runner: Runner::default(),
+        },
+        vcpu: 2,
+        memory: 2048,
+        disk_size: 20,
+        teepod_id: 7,
+        image: "dstack-0.3.5".to_string(),
+        advanced_features: AdvancedFeatures {
+            tproxy: true,
+            kms: true,
+            public_sys_info: false,
+            public_logs: false,
+            docker_config: DockerConfig {
+                username: String::new(),
+                password: String::new(),
+                registry: None,
+            },
+            listed: false,
+        },
+        labels: HashMap::from([("team".to_string(), "infra".to_string())]),
+        kernel_cmdline_append: None,
+        extra: serde_json::Map::new(),
+    }
+    .with_extra("gpu_model", json!("h100"));
+
+    let value: serde_json::Value = vm_config.clone().into();
+    assert_eq!(value["teepod_id"], 7);
+    assert_eq!(value["gpu_model"], "h100");
+
+    let parsed = VmConfig::try_from(value.clone()).unwrap();
+    assert_eq!(parsed.labels, vm_config.labels);
+    assert_eq!(parsed.extra["gpu_model"], "h100");
+    assert_eq!(serde_json::Value::from(parsed), value);
+
+    assert!(matches!(
+        VmConfig::try_from(json!({ "name": "app" })),
+        Err(Error::Serialization(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_compose_crlf_normalized_in_manifest() {
+    let mock_server = MockServer::start().await;
+    test_util::mount_happy_path(&mock_server).await;
+    let compose = "services:\r\n  app:\r\n    image: nginx\r\n";
+
+    let mut deployer = test_deployer(mock_server.uri()).build().unwrap();
+    deployer.discover_teepod().await.unwrap();
+    deployer
+        .deploy_compose(compose, "app", [("PORT", "80")], None, None, None)
+        .await
+        .unwrap();
+
+    let requests = mock_server.received_requests().await.unwrap();
+    let deploy = requests
+        .iter()
+        .find(|r| r.url.path() == "/cvms/from_cvm_configuration")
+        .unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&deploy.body).unwrap();
+    assert_eq!(
+        body["compose_manifest"]["docker_compose_file"],
+        "services:\n  app:\n    image: nginx\n"
+    );
+
+    let mut deployer = test_deployer(mock_server.uri())
+        .with_normalize_line_endings(false)
+        .build()
+        .unwrap();
+    deployer.discover_teepod().await.unwrap();
+    let vm_config = deployer
+        .create_vm_config(compose, "app", None, None, None)
+        .unwrap();
+    assert_eq!(vm_config.compose_manifest.docker_compose_file, compose);
+}
+
+#[tokio::test]
+async fn test_get_env_keys() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/cvms/app_1/compose"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "compose_file": {
+                "compose_manifest": {
+                    "docker_compose_file": "services:\n  api:\n    image: api\n    environment:\n      - DB_URL=${DB_URL}\n      - API_TOKEN\n      - LOG_LEVEL=info\n"
+                },
+                "allowed_envs": ["API_TOKEN", "SENTRY_DSN"]
+            },
+            "env_pubkey": "0x00"
+        })))
+        .mount(&mock_server)
+        .await;
+
+    let deployer = test_deployer(mock_server.uri()).build().unwrap();
+    let keys = deployer.get_env_keys("app_1").await.unwrap();
+
+    assert_eq!(keys, ["API_TOKEN", "SENTRY_DSN", "DB_URL", "LOG_LEVEL"]);
+}
+
+#[test]
+fn test_update_response_deserialization() {
+    let response: UpdateResponse = serde_json::from_value(json!({
+        "status": "success",
+        "message": "Compose configuration updated",
+        "revision": 3
+    }))
+    .unwrap();
+    assert_eq!(response.status, "success");
+    assert_eq!(
+        response.message.as_deref(),
+        Some("Compose configuration updated")
+    );
+    assert_eq!(response.raw["revision"], 3);
+
+    let minimal: UpdateResponse = serde_json::from_value(json!({ "ok": true })).unwrap();
+    assert_eq!(minimal.status, "");
+    assert_eq!(minimal.message, None);
+    assert_eq!(
+        serde_json::to_value(&minimal).unwrap(),
+        json!({ "ok": true })
+    );
+}
+
+#[tokio::test]
+async fn test_poll_strategy_applies_to_deployer_waits() {
+    let mock_server = MockServer::start().await;
+    mount_state(&mock_server, "starting", None).await;
+
+    let clock = FakeClock::new();
+    let deployer = test_deployer(mock_server.uri())
+        .with_clock(clock.clone())
+        .with_poll_strategy(PollStrategy::Exponential {
+            base: Duration::from_secs(1),
+            factor: 2.0,
+            max: Duration::from_secs(16),
+        })
+        .build()
+        .unwrap();
+    assert!(matches!(
+        deployer
+            .wait_until_running("app_1", Duration::from_secs(10))
+            .await,
+        Err(Error::Timeout { .. })
+    ));
+    let secs: Vec<u64> = clock.sleeps().iter().map(Duration::as_secs).collect();
+    assert_eq!(secs, vec![1, 2, 4, 8]);
+}
+
+#[tokio::test]
+async fn test_await_deletion_until_not_found() {
+    let mock_server = MockServer::start().await;
+    mount_state(&mock_server, "stopping", Some(2)).await;
+    Mock::given(method("GET"))
+        .and(path("/cvms/app_1/state"))
+        .respond_with(ResponseTemplate::new(404).set_body_json(json!({
+            "detail": "CVM not found"
+        })))
+        .mount(&mock_server)
+        .await;
+
+    let clock = FakeClock::new();
+    let deployer = test_deployer(mock_server.uri())
+        .with_clock(clock.clone())
+        .build()
+        .unwrap();
+    deployer
+        .await_deletion("app_1", Duration::from_secs(30))
+        .await
+        .unwrap();
+    assert_eq!(clock.sleeps().len(), 2);
+
+    let still_there = MockServer::start().await;
+    mount_state(&still_there, "stopped", None).await;
+    let deployer = test_deployer(still_there.uri())
+        .with_clock(FakeClock::new())
+        .build()
+        .unwrap();
+    match deployer
+        .await_deletion("app_1", Duration::from_secs(5))
+        .await
+    {
+        Err(Error::Timeout { operation }) => assert!(operation.contains("to be deleted")),
+        other => panic!("expected timeout, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_request_timeout_is_reported_as_timeout() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/cvms/app_1/state"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(json!({ "status": "running", "is_running": true }))
+                .set_delay(Duration::from_millis(500)),
+        )
+        .mount(&mock_server)
+        .await;
+
+    let deployer = test_deployer(mock_server.uri())
+        .with_request_timeout(Duration::from_millis(50))
+        .build()
+        .unwrap();
+    match deployer.get_status("app_1").await {
+        Err(Error::Timeout { operation }) => {
+            assert_eq!(operation, "request to /cvms/app_1/state")
+        }
+        other => panic!("expected timeout error, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_connect_timeout_fails_before_request_timeout() {
+    // Best effort: an unroutable address either times out connecting or is
+    // rejected immediately, depending on the network; neither should wait
+    // for the full request timeout.
+    let deployer = TeeDeployerBuilder::new()
+        .with_api_key("test_api_key")
+        .with_api_endpoint("http://10.255.255.1:81")
+        .with_request_timeout(Duration::from_secs(30))
+        .with_connect_timeout(Duration::from_millis(200))
+        .build()
+        .unwrap();
+
+    let started = std::time::Instant::now();
+    assert!(deployer.get_status("app_1").await.is_err());
+    assert!(started.elapsed() < Duration::from_secs(5));
+}
+
+#[tokio::test]
+async fn test_name_template() {
+    let mock_server = MockServer::start().await;
+    mount_discovery(&mock_server, vec![teepod_image("dstack-0.3.5", false)]).await;
+
+    let mut deployer = test_deployer(mock_server.uri())
+        .with_name_template("team-{name}-prod")
+        .build()
+        .unwrap();
+    deployer.discover_teepod().await.unwrap();
+
+    let vm_config = deployer
+        .create_vm_config("services: {}", "web", None, None, None)
+        .unwrap();
+    assert_eq!(vm_config.name, "team-web-prod");
+    assert_eq!(vm_config.compose_manifest.name, "team-web-prod");
+
+    for invalid in ["my app".to_string(), "x".repeat(60)] {
+        match deployer.create_vm_config("services: {}", &invalid, None, None, None) {
+            Err(Error::Configuration(message)) => assert!(message.contains("team-{name}-prod")),
+            other => panic!("expected invalid name error, got {:?}", other),
+        }
+    }
+
+    assert!(matches!(
+        test_deployer(mock_server.uri())
+            .with_name_template("team-prod")
+            .build(),
+        Err(Error::Configuration(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_large_env_encryption_keeps_runtime_responsive() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/cvms/from_cvm_configuration"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "id": 1,
+            "status": "creating"
+        })))
+        .mount(&mock_server)
+        .await;
+    let client = TeeClient::new(create_test_config(mock_server.uri())).unwrap();
+    let vm_config = json!({ "name": "vm", "teepod_id": 1 });
+
+    // A ticker on the same single-threaded runtime must keep running while encrypting
+    let max_gap = std::sync::Arc::new(std::sync::Mutex::new(Duration::ZERO));
+    let ticker = tokio::spawn({
+        let max_gap = max_gap.clone();
+        async move {
+            let mut last = std::time::Instant::now();
+            loop {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+                let gap = last.elapsed();
+                let mut max_gap = max_gap.lock().unwrap();
+                *max_gap = (*max_gap).max(gap);
+                last = std::time::Instant::now();
+            }
+        }
+    });
+    tokio::task::yield_now().await;
+
+    let env_vars = vec![("CHARACTER_DATA".to_string(), "x".repeat(4 * 1024 * 1024))];
+    client
+        .deploy_with_config_do_encrypt(vm_config.clone(), &env_vars, &test_pubkey(), &test_salt())
+        .await
+        .unwrap();
+    ticker.abort();
+    // Encrypting 4 MiB takes seconds in debug builds; serializing the body takes far less
+    assert!(*max_gap.lock().unwrap() < Duration::from_secs(1));
+
+    let oversized = vec![("CHARACTER_DATA".to_string(), "x".repeat(32 * 1024 * 1024))];
+    match client
+        .deploy_with_config_do_encrypt(vm_config, &oversized, &test_pubkey(), &test_salt())
+        .await
+    {
+        Err(Error::Configuration(message)) => assert!(message.contains("33554446 bytes")),
+        other => panic!("expected size limit error, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_get_compose_yaml() {
+    let mock_server = MockServer::start().await;
+    let yaml = "services:\n  web:\n    image: nginx:latest\n    ports:\n      - \"80:80\"\n";
+    Mock::given(method("GET"))
+        .and(path("/cvms/app_1/compose"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "compose_file": {
+                "compose_manifest": {
+                    "name": "web",
+                    "features": ["kms", "tproxy-net"],
+                    "docker_compose_file": yaml,
+                    "runner": "docker-compose"
+                },
+                "allowed_envs": ["API_KEY"]
+            },
+            "env_pubkey": format!("0x{}", hex::encode([9u8; 32]))
+        })))
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/cvms/app_2/compose"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "compose_file": { "allowed_envs": [] },
+            "env_pubkey": format!("0x{}", hex::encode([9u8; 32]))
+        })))
+        .mount(&mock_server)
+        .await;
+
+    let deployer = test_deployer(mock_server.uri()).build().unwrap();
+    assert_eq!(deployer.get_compose_yaml("app_1").await.unwrap(), yaml);
+    match deployer.get_compose_yaml("app_2").await {
+        Err(Error::Configuration(message)) => assert!(message.contains("app_2")),
+        other => panic!("expected missing compose error, got {:?}", other),
+    }
 }
@@ -1,10 +1,13 @@
+use crate::{Encryptor, Error};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::time::Duration;
 
 /// Docker registry authentication configuration.
 ///
 /// Used to access private Docker registries when deploying containers.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct DockerConfig {
     /// Docker registry username
     pub username: String,
@@ -20,6 +23,7 @@ pub struct DockerConfig {
 ///
 /// Controls security and visibility settings for deployed applications.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct AdvancedFeatures {
     /// Enable transparent proxy support
     pub tproxy: bool,
@@ -40,10 +44,73 @@ pub struct AdvancedFeatures {
     pub listed: bool,
 }
 
+/// Changes to the advanced features of an existing deployment.
+///
+/// Only settings that can change after launch are modelled; `kms`, `tproxy`
+/// and the registry credentials are fixed when the CVM is created. Unset
+/// fields are left unchanged.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct SettingsUpdate {
+    /// Make system information publicly accessible
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub public_sys_info: Option<bool>,
+
+    /// Make application logs publicly accessible
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub public_logs: Option<bool>,
+
+    /// List this deployment in public directories
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub listed: Option<bool>,
+}
+
+impl SettingsUpdate {
+    /// Creates an update that changes nothing.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets whether system information is publicly accessible.
+    ///
+    /// # Returns
+    ///
+    /// The updated `SettingsUpdate` instance for method chaining
+    pub fn with_public_sys_info(mut self, public_sys_info: bool) -> Self {
+        self.public_sys_info = Some(public_sys_info);
+        self
+    }
+
+    /// Sets whether application logs are publicly accessible.
+    ///
+    /// # Returns
+    ///
+    /// The updated `SettingsUpdate` instance for method chaining
+    pub fn with_public_logs(mut self, public_logs: bool) -> Self {
+        self.public_logs = Some(public_logs);
+        self
+    }
+
+    /// Sets whether the deployment is listed in public directories.
+    ///
+    /// # Returns
+    ///
+    /// The updated `SettingsUpdate` instance for method chaining
+    pub fn with_listed(mut self, listed: bool) -> Self {
+        self.listed = Some(listed);
+        self
+    }
+
+    /// Returns `true` if the update changes no setting.
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+}
+
 /// Docker Compose manifest configuration.
 ///
 /// Defines the application structure using Docker Compose format.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct ComposeManifest {
     /// Name of the application
     pub name: String,
@@ -53,12 +120,58 @@ pub struct ComposeManifest {
 
     /// Docker Compose file content
     pub docker_compose_file: String,
+
+    /// Runner used to launch the application inside the CVM
+    #[serde(default)]
+    #[cfg_attr(feature = "schema", schemars(with = "String"))]
+    pub runner: Runner,
+}
+
+/// Runner used by the CVM to launch the application.
+///
+/// Serialized as the plain runner string (e.g. `"docker-compose"`). Unknown
+/// runners are preserved through `Other` so newer platform runners can be
+/// targeted before this client knows about them.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub enum Runner {
+    /// Docker Compose (the platform default)
+    #[default]
+    DockerCompose,
+    /// Any other runner identifier
+    Other(String),
+}
+
+impl Runner {
+    /// Returns the runner identifier sent to the API.
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::DockerCompose => "docker-compose",
+            Self::Other(runner) => runner,
+        }
+    }
+}
+
+impl From<String> for Runner {
+    fn from(runner: String) -> Self {
+        match runner.as_str() {
+            "docker-compose" => Self::DockerCompose,
+            _ => Self::Other(runner),
+        }
+    }
+}
+
+impl From<Runner> for String {
+    fn from(runner: Runner) -> Self {
+        runner.as_str().to_string()
+    }
 }
 
 /// Virtual Machine configuration for a TEE deployment.
 ///
 /// Defines the resources and settings for the VM that will run the containerized application.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct VmConfig {
     /// Name of the deployment
     pub name: String,
@@ -83,6 +196,88 @@ pub struct VmConfig {
 
     /// Advanced features configuration
     pub advanced_features: AdvancedFeatures,
+
+    /// Arbitrary metadata labels (e.g. team, environment)
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub labels: HashMap<String, String>,
+
+    /// Extra kernel command-line arguments appended to the image's `cmdline`,
+    /// where the platform permits it (e.g. for debugging)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kernel_cmdline_append: Option<String>,
+
+    /// Additional top-level fields not yet modelled by this crate
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+impl VmConfig {
+    /// Sets a raw top-level field in the serialized VM configuration.
+    ///
+    /// Lets callers use platform fields this crate does not model yet. Keys that
+    /// match a typed field should be set through that field instead.
+    ///
+    /// # Parameters
+    ///
+    /// * `key` - Name of the top-level field
+    /// * `value` - JSON value of the field
+    ///
+    /// # Returns
+    ///
+    /// The updated `VmConfig` instance for method chaining
+    pub fn with_extra(mut self, key: impl Into<String>, value: serde_json::Value) -> Self {
+        self.extra.insert(key.into(), value);
+        self
+    }
+
+    /// Returns the JSON schema of the VM configuration as pretty-printed JSON.
+    ///
+    /// Intended for frontends that validate configurations before submitting
+    /// them. Requires the `schema` feature.
+    #[cfg(feature = "schema")]
+    pub fn json_schema_string() -> String {
+        let schema = schemars::schema_for!(VmConfig);
+        serde_json::to_string_pretty(&schema).expect("JSON schema is always serializable")
+    }
+}
+
+/// Rejects kernel command-line additions that could inject extra lines.
+pub(crate) fn check_kernel_cmdline_append(cmdline: &str) -> Result<(), Error> {
+    if cmdline.contains(['\n', '\r']) {
+        return Err(Error::Configuration(
+            "Kernel cmdline append must not contain newlines".into(),
+        ));
+    }
+    Ok(())
+}
+
+impl From<VmConfig> for serde_json::Value {
+    /// Serializes the configuration into the JSON object sent to the API.
+    fn from(config: VmConfig) -> Self {
+        serde_json::to_value(config).expect("VmConfig always serializes to JSON")
+    }
+}
+
+impl TryFrom<serde_json::Value> for VmConfig {
+    type Error = Error;
+
+    /// Parses a VM configuration from JSON, keeping unmodelled fields in `extra`.
+    fn try_from(value: serde_json::Value) -> Result<Self, Error> {
+        serde_json::from_value(value)
+            .map_err(|e| Error::Serialization(format!("Invalid VM config: {}", e)))
+    }
+}
+
+/// A fully-resolved, secret-free deployment plan.
+///
+/// Produced by `TeeDeployer::export_plan` for review (e.g. in a pull request)
+/// and applied later with `TeeDeployer::deploy_from_plan`. Environment
+/// variables and registry passwords are never part of a plan; they are supplied
+/// when the plan is applied.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeploymentPlan {
+    /// The VM configuration to deploy verbatim
+    pub vm_config: VmConfig,
 }
 
 /// Encrypted environment variable entry.
@@ -109,10 +304,54 @@ pub struct DeploymentResponse {
     /// Current status of the deployment (e.g., "pending", "running")
     pub status: String,
 
+    /// Application identifier (e.g. "app_abc123") used by the per-app endpoints
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub app_id: Option<String>,
+
     /// Additional deployment details as key-value pairs
     pub details: Option<HashMap<String, serde_json::Value>>,
 }
 
+/// Human-readable summary of the deployment, including the TEEPod and image
+/// when the deployer recorded them in `details`.
+impl std::fmt::Display for DeploymentResponse {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Deployment ID: {}\nStatus: {}", self.id, self.status)?;
+        if let Some(app_id) = &self.app_id {
+            write!(f, "\nApp ID: {}", app_id)?;
+        }
+        let detail = |key: &str| self.details.as_ref().and_then(|d| d.get(key));
+        if let Some(teepod_id) = detail("teepod_id") {
+            write!(f, "\nTEEPod ID: {}", teepod_id)?;
+        }
+        if let Some(image) = detail("image").and_then(|v| v.as_str()) {
+            write!(f, "\nImage: {}", image)?;
+        }
+        Ok(())
+    }
+}
+
+impl DeploymentResponse {
+    /// Returns the `status` parsed as a `DeploymentStatus`.
+    pub fn deployment_status(&self) -> DeploymentStatus {
+        DeploymentStatus::parse(&self.status)
+    }
+
+    /// Returns `true` if the deployment was accepted and is creating, starting
+    /// or running.
+    pub fn is_success(&self) -> bool {
+        matches!(
+            self.deployment_status(),
+            DeploymentStatus::Creating | DeploymentStatus::Starting | DeploymentStatus::Running
+        )
+    }
+
+    /// Returns `true` if the deployment failed.
+    pub fn is_failed(&self) -> bool {
+        self.deployment_status() == DeploymentStatus::Failed
+    }
+}
+
 // Implement custom deserialization to handle different API response formats
 impl<'de> Deserialize<'de> for DeploymentResponse {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
@@ -130,31 +369,27 @@ impl<'de> Deserialize<'de> for DeploymentResponse {
             None => return Err(D::Error::custom("Expected object for DeploymentResponse")),
         };
 
+        // Numeric IDs that do not fit a u64 are kept as text for `app_id`
+        let mut oversized_id = None;
+
         // Try to extract the ID field from various possible formats
-        let id = if let Some(id_value) = obj.get("id") {
-            if let Some(id_num) = id_value.as_u64() {
-                id_num
-            } else if let Some(id_str) = id_value.as_str() {
-                id_str.parse::<u64>().unwrap_or(0)
-            } else {
-                0 // Default ID if can't parse
-            }
-        } else if let Some(id_value) = obj.get("uuid") {
-            if let Some(id_num) = id_value.as_u64() {
-                id_num
-            } else if let Some(id_str) = id_value.as_str() {
-                id_str.parse::<u64>().unwrap_or(0)
-            } else {
-                0 // Default ID if can't parse
+        let id = if let Some(id_value) = obj.get("id").or_else(|| obj.get("uuid")) {
+            match numeric_id(id_value) {
+                Some(Ok(id)) => id,
+                Some(Err(original)) => {
+                    oversized_id = Some(original);
+                    0
+                }
+                None => 0, // Default ID if can't parse
             }
         } else if let Some(id_value) = obj.get("app_id") {
             if let Some(id_str) = id_value.as_str() {
                 // Extract numeric part from "app_123" format
-                if id_str.starts_with("app_") {
-                    id_str[4..].parse::<u64>().unwrap_or(0)
-                } else {
-                    id_str.parse::<u64>().unwrap_or(0)
-                }
+                id_str
+                    .strip_prefix("app_")
+                    .unwrap_or(id_str)
+                    .parse::<u64>()
+                    .unwrap_or(0)
             } else {
                 0 // Default ID if can't parse
             }
@@ -177,14 +412,53 @@ impl<'de> Deserialize<'de> for DeploymentResponse {
             details.insert(k.clone(), v.clone());
         }
 
+        let app_id = obj
+            .get("app_id")
+            .and_then(|v| v.as_str())
+            .map(str::to_string)
+            .or(oversized_id);
+
         Ok(DeploymentResponse {
             id,
             status,
+            app_id,
             details: Some(details),
         })
     }
 }
 
+/// Reads a numeric deployment ID from an integer, an integral float (e.g.
+/// `42.0`) or a numeric string.
+///
+/// Returns `None` if the value is not numeric, and `Some(Err(original))`
+/// with the original text if it is numeric but not a `u64`, e.g. too large,
+/// negative or fractional.
+fn numeric_id(value: &serde_json::Value) -> Option<Result<u64, String>> {
+    let from_f64 = |float: f64, original: String| {
+        // `u64::MAX as f64` rounds up to 2^64, which is itself out of range
+        if float.fract() == 0.0 && float >= 0.0 && float < u64::MAX as f64 {
+            Ok(float as u64)
+        } else {
+            Err(original)
+        }
+    };
+    match value {
+        serde_json::Value::Number(number) => Some(match number.as_u64() {
+            Some(id) => Ok(id),
+            None => from_f64(number.as_f64()?, number.to_string()),
+        }),
+        serde_json::Value::String(text) => {
+            let text = text.trim();
+            if let Ok(id) = text.parse::<u64>() {
+                return Some(Ok(id));
+            }
+            let float = text.parse::<f64>().ok().filter(|f| f.is_finite())?;
+            Some(from_f64(float, text.to_string()))
+        }
+        _ => None,
+    }
+}
+
 /// Response when retrieving a compose configuration.
 ///
 /// Contains both the compose configuration and the public key needed for
@@ -198,6 +472,40 @@ pub struct ComposeResponse {
     pub env_pubkey: String,
 }
 
+/// Response from a compose update (`PUT /api/v1/cvms/{app_id}/compose`).
+///
+/// The full response body is kept in `raw`, so fields not modelled here stay
+/// accessible.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(from = "serde_json::Value", into = "serde_json::Value")]
+pub struct UpdateResponse {
+    /// Status of the update (e.g. "success"), empty if not reported
+    pub status: String,
+
+    /// Human-readable message, if any
+    pub message: Option<String>,
+
+    /// The response body as returned by the API
+    pub raw: serde_json::Value,
+}
+
+impl From<serde_json::Value> for UpdateResponse {
+    fn from(raw: serde_json::Value) -> Self {
+        let field = |key: &str| raw.get(key).and_then(|v| v.as_str()).map(str::to_string);
+        Self {
+            status: field("status").unwrap_or_default(),
+            message: field("message"),
+            raw,
+        }
+    }
+}
+
+impl From<UpdateResponse> for serde_json::Value {
+    fn from(response: UpdateResponse) -> Self {
+        response.raw
+    }
+}
+
 /// Response from a pubkey request.
 ///
 /// Contains the public key and other configuration details needed for deployment.
@@ -206,10 +514,10 @@ pub struct PubkeyResponse {
     /// Public key for encrypting environment variables
     pub app_env_encrypt_pubkey: String,
 
-    /// Generated application ID
+    /// Generated application ID, derived by the platform from `app_id_salt`
     pub app_id: String,
 
-    /// Salt used in app ID generation
+    /// Salt used in app ID generation; see [`AppIdentity`]
     pub app_id_salt: String,
 
     /// Compose manifest configuration
@@ -246,6 +554,152 @@ pub struct PubkeyResponse {
     pub vcpu: u64,
 }
 
+impl PubkeyResponse {
+    /// Returns the validated environment encryption public key.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the API returned a malformed key
+    pub fn public_key(&self) -> Result<AppPublicKey, Error> {
+        AppPublicKey::try_from(self.app_env_encrypt_pubkey.as_str())
+    }
+
+    /// Returns the validated app ID salt.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the API returned an empty salt
+    pub fn salt(&self) -> Result<AppIdSalt, Error> {
+        AppIdSalt::try_from(self.app_id_salt.as_str())
+    }
+
+    /// Returns the app ID paired with the salt it was derived from.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the API returned an empty salt
+    pub fn app_identity(&self) -> Result<AppIdentity, Error> {
+        Ok(AppIdentity {
+            app_id: self.app_id.clone(),
+            app_id_salt: self.salt()?,
+        })
+    }
+}
+
+/// An app ID together with the salt it was derived from.
+///
+/// The platform derives the app ID server-side from the salt together with
+/// the VM configuration; the scheme is not published, so the app ID cannot
+/// be predicted client-side. The pairing becomes known when the pubkey step
+/// returns (see `PubkeyResponse::app_identity`). Sending the same salt for
+/// the same configuration, e.g. through the `salt_override` of
+/// `TeeDeployer::deploy_config`, keeps the app ID stable across redeploys.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AppIdentity {
+    /// Application ID as returned by the API
+    pub app_id: String,
+
+    /// Salt the app ID was derived from
+    pub app_id_salt: AppIdSalt,
+}
+
+/// Public key for encrypting a deployment's environment variables.
+///
+/// Holds the hex string returned by the API, with or without a `0x` prefix.
+/// Construction through `TryFrom` checks that it decodes to a 32-byte X25519
+/// key. Being distinct from `AppIdSalt`, it cannot be passed in the salt's
+/// position:
+///
+/// ```compile_fail
+/// use phala_tee_deploy_rs::{AppIdSalt, AppPublicKey, TeeClient};
+///
+/// async fn swapped(client: &TeeClient, pubkey: &AppPublicKey, salt: &AppIdSalt) {
+///     let vm_config = serde_json::json!({});
+///     client
+///         .deploy_with_config_encrypted_env(vm_config, String::new(), salt, pubkey)
+///         .await;
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(transparent)]
+pub struct AppPublicKey(String);
+
+impl AppPublicKey {
+    /// Returns the key as the hex string sent to the API.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Returns the raw 32-byte X25519 key.
+    pub fn to_bytes(&self) -> [u8; 32] {
+        Encryptor::decode_pubkey(&self.0).expect("validated on construction")
+    }
+}
+
+impl TryFrom<&str> for AppPublicKey {
+    type Error = Error;
+
+    fn try_from(key: &str) -> Result<Self, Error> {
+        Encryptor::decode_pubkey(key)?;
+        Ok(Self(key.to_string()))
+    }
+}
+
+impl TryFrom<String> for AppPublicKey {
+    type Error = Error;
+
+    fn try_from(key: String) -> Result<Self, Error> {
+        Self::try_from(key.as_str())
+    }
+}
+
+impl std::fmt::Display for AppPublicKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Salt the platform uses to derive a deployment's app ID.
+///
+/// See [`AppIdentity`] for how the salt relates to the app ID.
+///
+/// Construction through `TryFrom` rejects an empty salt.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(transparent)]
+pub struct AppIdSalt(String);
+
+impl AppIdSalt {
+    /// Returns the salt as sent to the API.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl TryFrom<&str> for AppIdSalt {
+    type Error = Error;
+
+    fn try_from(salt: &str) -> Result<Self, Error> {
+        if salt.is_empty() {
+            return Err(Error::Configuration("App ID salt is empty".to_string()));
+        }
+        Ok(Self(salt.to_string()))
+    }
+}
+
+impl TryFrom<String> for AppIdSalt {
+    type Error = Error;
+
+    fn try_from(salt: String) -> Result<Self, Error> {
+        Self::try_from(salt.as_str())
+    }
+}
+
+impl std::fmt::Display for AppIdSalt {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
 /// Compose manifest configuration.
 ///
 /// Contains Docker Compose and related deployment settings.
@@ -307,6 +761,115 @@ pub struct TeePodDiscoveryResponse {
     pub tier: String,
 }
 
+impl TeePodDiscoveryResponse {
+    /// Aggregates the remaining resources of every node in the response.
+    pub fn utilization(&self) -> ClusterUtilization {
+        let node_count = self.nodes.len();
+        let average_resource_score = if node_count == 0 {
+            0.0
+        } else {
+            self.nodes.iter().map(|n| n.resource_score).sum::<f64>() / node_count as f64
+        };
+        ClusterUtilization {
+            node_count,
+            remaining_vcpu: self.nodes.iter().map(|n| n.remaining_vcpu).sum(),
+            remaining_memory: self.nodes.iter().map(|n| n.remaining_memory).sum(),
+            remaining_cvm_slots: self.nodes.iter().map(|n| n.remaining_cvm_slots).sum(),
+            average_resource_score,
+            max_vcpu: self.capacity.max_vcpu,
+            max_memory: self.capacity.max_memory,
+        }
+    }
+}
+
+/// Fleet-wide view of the resources across all discovered TEEPod nodes.
+///
+/// The API reports only remaining resources per node, so the remaining
+/// totals are sums over nodes; the `max_*` fields are the cluster's limits
+/// for a single CVM, for comparison.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ClusterUtilization {
+    /// Number of nodes aggregated
+    pub node_count: usize,
+
+    /// Remaining virtual CPU capacity summed over all nodes
+    pub remaining_vcpu: f64,
+
+    /// Remaining memory in MB summed over all nodes
+    pub remaining_memory: f64,
+
+    /// Free CVM slots summed over all nodes
+    pub remaining_cvm_slots: u64,
+
+    /// Mean resource availability score (0.0-1.0), or 0.0 without nodes
+    pub average_resource_score: f64,
+
+    /// Maximum number of virtual CPUs for a single CVM
+    pub max_vcpu: u64,
+
+    /// Maximum memory in MB for a single CVM
+    pub max_memory: u64,
+}
+
+/// Resource prices for one service tier, per hour.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ResourcePrices {
+    /// Price of one vCPU per hour
+    pub vcpu_hour: f64,
+
+    /// Price of one GB of memory per hour
+    pub memory_gb_hour: f64,
+
+    /// Price of one GB of disk per hour
+    pub disk_gb_hour: f64,
+}
+
+/// Resource prices keyed by service tier (e.g. "pro"), used for cost estimates.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PriceTable {
+    tiers: HashMap<String, ResourcePrices>,
+}
+
+impl PriceTable {
+    /// Creates an empty price table.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the prices for a tier, replacing any previous prices for it.
+    ///
+    /// # Parameters
+    ///
+    /// * `tier` - The service tier as reported by TEEPod discovery
+    /// * `prices` - Hourly resource prices for the tier
+    ///
+    /// # Returns
+    ///
+    /// The updated `PriceTable` instance for method chaining
+    pub fn with_tier(mut self, tier: impl Into<String>, prices: ResourcePrices) -> Self {
+        self.tiers.insert(tier.into(), prices);
+        self
+    }
+
+    /// Returns the prices for a tier, if configured.
+    pub fn prices(&self, tier: &str) -> Option<&ResourcePrices> {
+        self.tiers.get(tier)
+    }
+}
+
+/// Estimated cost of running a VM, in the price table's currency.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CostEstimate {
+    /// Service tier the prices were taken from
+    pub tier: String,
+
+    /// Estimated cost per hour
+    pub hourly: f64,
+
+    /// Estimated cost per month (730 hours)
+    pub monthly: f64,
+}
+
 /// Capacity configuration for a TEEPod cluster.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TeePodCapacity {
@@ -349,6 +912,10 @@ pub struct TeePodNode {
 
     /// Unique identifier for the TEEPod
     pub teepod_id: u64,
+
+    /// Region or location of the node, if reported (e.g. "us-east")
+    #[serde(default, alias = "location")]
+    pub region: Option<String>,
 }
 
 /// VM image configuration for a TEEPod.
@@ -391,6 +958,22 @@ pub struct TeePodImage {
     pub version: Vec<u64>,
 }
 
+impl TeePodImage {
+    /// Parses the kernel command line into `(key, value)` pairs.
+    ///
+    /// Bare flags such as `quiet` are returned with a `None` value, while
+    /// `key=value` parameters are split on the first `=`.
+    pub fn cmdline_args(&self) -> Vec<(String, Option<String>)> {
+        self.cmdline
+            .split_whitespace()
+            .map(|arg| match arg.split_once('=') {
+                Some((key, value)) => (key.to_string(), Some(value.to_string())),
+                None => (arg.to_string(), None),
+            })
+            .collect()
+    }
+}
+
 /// Response containing network information for a deployment.
 ///
 /// Provides details about connectivity, IP addresses, and public URLs
@@ -416,6 +999,44 @@ pub struct NetworkInfoResponse {
     pub public_urls: PublicUrls,
 }
 
+impl NetworkInfoResponse {
+    /// Parses the public application URL.
+    ///
+    /// Returns `None` if the URL is empty, malformed, or not `http`/`https`.
+    pub fn app_url(&self) -> Option<url::Url> {
+        parse_public_url(&self.public_urls.app)
+    }
+
+    /// Parses the public instance URL.
+    ///
+    /// Returns `None` if the URL is empty, malformed, or not `http`/`https`.
+    pub fn instance_url(&self) -> Option<url::Url> {
+        parse_public_url(&self.public_urls.instance)
+    }
+
+    /// Returns `true` if the deployment is online and has a usable application URL.
+    pub fn is_ready(&self) -> bool {
+        self.is_online && self.app_url().is_some()
+    }
+
+    /// Parses `latest_handshake` as an RFC 3339 timestamp.
+    ///
+    /// Returns `None` if the value is empty or not a valid timestamp, which the
+    /// API reports for deployments that have not completed a handshake yet.
+    #[cfg(feature = "chrono")]
+    pub fn latest_handshake_time(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        chrono::DateTime::parse_from_rfc3339(self.latest_handshake.trim())
+            .ok()
+            .map(|time| time.with_timezone(&chrono::Utc))
+    }
+}
+
+/// Parses a public URL, accepting only non-empty `http` and `https` URLs.
+fn parse_public_url(raw: &str) -> Option<url::Url> {
+    let url = url::Url::parse(raw.trim()).ok()?;
+    matches!(url.scheme(), "http" | "https").then_some(url)
+}
+
 /// Public URLs for accessing a deployment.
 ///
 /// Contains URLs for different parts of the deployment.
@@ -446,6 +1067,16 @@ pub struct DiskInfo {
     pub free_size: u64,
 }
 
+impl DiskInfo {
+    /// Percentage of the disk in use, or 0.0 if no size is reported.
+    pub fn used_percent(&self) -> f64 {
+        if self.total_size == 0 {
+            return 0.0;
+        }
+        self.total_size.saturating_sub(self.free_size) as f64 / self.total_size as f64 * 100.0
+    }
+}
+
 /// Detailed system information for a virtual machine.
 ///
 /// Contains comprehensive details about the operating system, hardware,
@@ -504,6 +1135,42 @@ pub struct SystemInfo {
     pub disks: Vec<DiskInfo>,
 }
 
+impl SystemInfo {
+    /// Percentage of physical memory in use, or 0.0 if no memory is reported.
+    pub fn memory_used_percent(&self) -> f64 {
+        if self.total_memory == 0 {
+            return 0.0;
+        }
+        self.used_memory as f64 / self.total_memory as f64 * 100.0
+    }
+
+    /// Percentage of swap in use, or `None` if the VM has no swap.
+    pub fn swap_used_percent(&self) -> Option<f64> {
+        (self.total_swap > 0).then(|| self.used_swap as f64 / self.total_swap as f64 * 100.0)
+    }
+
+    /// 1-minute load average as a percentage of the available CPUs.
+    ///
+    /// Returns 0.0 while the VM reports no CPUs (seen during early boot) rather
+    /// than an infinite or NaN percentage.
+    pub fn cpu_load_percent(&self) -> f32 {
+        if self.num_cpus == 0 {
+            return 0.0;
+        }
+        self.loadavg_one * 100.0 / self.num_cpus as f32
+    }
+
+    /// Splits the uptime into `(days, hours, minutes, seconds)`.
+    pub fn uptime_components(&self) -> (u64, u64, u64, u64) {
+        (
+            self.uptime / (60 * 60 * 24),
+            (self.uptime / (60 * 60)) % 24,
+            (self.uptime / 60) % 60,
+            self.uptime % 60,
+        )
+    }
+}
+
 /// Response containing system statistics for a container VM.
 ///
 /// Provides details about the operational status and system resource usage
@@ -523,6 +1190,211 @@ pub struct SystemStatsResponse {
     pub sysinfo: SystemInfo,
 }
 
+/// Multi-section, human-readable summary of the VM status and resources.
+impl std::fmt::Display for SystemStatsResponse {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        const MB: u64 = 1024 * 1024;
+        const GB: f64 = 1024.0 * 1024.0 * 1024.0;
+        let yes_no = |flag: bool| if flag { "Yes" } else { "No" };
+        let info = &self.sysinfo;
+
+        writeln!(f, "=== System Status ===")?;
+        writeln!(f, "Online: {}", yes_no(self.is_online))?;
+        writeln!(f, "Publicly accessible: {}", yes_no(self.is_public))?;
+        if let Some(error) = &self.error {
+            writeln!(f, "Error: {}", error)?;
+        }
+
+        writeln!(f, "\n=== OS Information ===")?;
+        writeln!(f, "OS: {} {}", info.os_name, info.os_version)?;
+        writeln!(f, "Kernel version: {}", info.kernel_version)?;
+
+        writeln!(f, "\n=== CPU Information ===")?;
+        writeln!(f, "CPU model: {}", info.cpu_model)?;
+        writeln!(f, "Number of CPUs: {}", info.num_cpus)?;
+
+        writeln!(f, "\n=== Memory Usage ===")?;
+        writeln!(f, "Total memory: {} MB", info.total_memory / MB)?;
+        writeln!(
+            f,
+            "Used memory: {} MB ({:.1}%)",
+            info.used_memory / MB,
+            info.memory_used_percent()
+        )?;
+        writeln!(f, "Free memory: {} MB", info.free_memory / MB)?;
+
+        if let Some(swap_percent) = info.swap_used_percent() {
+            writeln!(f, "\n=== Swap Usage ===")?;
+            writeln!(f, "Total swap: {} MB", info.total_swap / MB)?;
+            writeln!(
+                f,
+                "Used swap: {} MB ({:.1}%)",
+                info.used_swap / MB,
+                swap_percent
+            )?;
+            writeln!(f, "Free swap: {} MB", info.free_swap / MB)?;
+        }
+
+        writeln!(f, "\n=== System Load ===")?;
+        writeln!(
+            f,
+            "Load averages: {:.2} (1m), {:.2} (5m), {:.2} (15m)",
+            info.loadavg_one, info.loadavg_five, info.loadavg_fifteen
+        )?;
+
+        let (days, hours, minutes, seconds) = info.uptime_components();
+        writeln!(f, "\n=== Uptime ===")?;
+        writeln!(
+            f,
+            "System uptime: {}d {}h {}m {}s",
+            days, hours, minutes, seconds
+        )?;
+
+        writeln!(f, "\n=== Disk Information ===")?;
+        if info.disks.is_empty() {
+            write!(f, "No disk information available")?;
+        }
+        for (i, disk) in info.disks.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            writeln!(f, "Disk {}: {}", i + 1, disk.name)?;
+            if let Some(mount) = &disk.mount_point {
+                writeln!(f, "  Mount point: {}", mount)?;
+            }
+            let total_gb = disk.total_size as f64 / GB;
+            let free_gb = disk.free_size as f64 / GB;
+            let used_gb = total_gb - free_gb;
+            writeln!(f, "  Total size: {:.2} GB", total_gb)?;
+            writeln!(f, "  Used: {:.2} GB ({:.1}%)", used_gb, disk.used_percent())?;
+            write!(f, "  Free: {:.2} GB", free_gb)?;
+        }
+        Ok(())
+    }
+}
+
+/// Limits checked by [`SystemStatsResponse::alerts`].
+///
+/// Each threshold is optional; unset thresholds are not checked.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StatThresholds {
+    /// Maximum percentage of physical memory in use
+    pub memory_used_percent: Option<f64>,
+
+    /// Maximum percentage of swap in use
+    pub swap_used_percent: Option<f64>,
+
+    /// Maximum 1-minute load average
+    pub load_average: Option<f64>,
+
+    /// Maximum percentage in use of any single disk
+    pub disk_used_percent: Option<f64>,
+}
+
+/// Metric that crossed its threshold in an [`Alert`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AlertMetric {
+    /// Physical memory used, in percent
+    MemoryUsed,
+
+    /// Swap used, in percent
+    SwapUsed,
+
+    /// 1-minute load average
+    LoadAverage,
+
+    /// Disk used, in percent, for the named disk
+    DiskUsed(String),
+}
+
+impl std::fmt::Display for AlertMetric {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AlertMetric::MemoryUsed => write!(f, "memory used %"),
+            AlertMetric::SwapUsed => write!(f, "swap used %"),
+            AlertMetric::LoadAverage => write!(f, "load average"),
+            AlertMetric::DiskUsed(disk) => write!(f, "disk {} used %", disk),
+        }
+    }
+}
+
+/// A metric that exceeded its configured threshold.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Alert {
+    /// The metric that crossed its threshold
+    pub metric: AlertMetric,
+
+    /// The observed value
+    pub actual: f64,
+
+    /// The configured threshold
+    pub threshold: f64,
+}
+
+impl std::fmt::Display for Alert {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} is {:.2}, above threshold {:.2}",
+            self.metric, self.actual, self.threshold
+        )
+    }
+}
+
+impl SystemStatsResponse {
+    /// Returns an alert for every metric above its threshold in `thresholds`.
+    ///
+    /// Swap is only checked when the VM has swap, and disks are checked
+    /// individually. Values equal to a threshold do not raise an alert.
+    ///
+    /// # Parameters
+    ///
+    /// * `thresholds` - The limits to check against
+    ///
+    /// # Returns
+    ///
+    /// The alerts raised, empty if every metric is within its threshold
+    pub fn alerts(&self, thresholds: &StatThresholds) -> Vec<Alert> {
+        let info = &self.sysinfo;
+        let mut observed = vec![
+            (
+                AlertMetric::MemoryUsed,
+                Some(info.memory_used_percent()),
+                thresholds.memory_used_percent,
+            ),
+            (
+                AlertMetric::SwapUsed,
+                info.swap_used_percent(),
+                thresholds.swap_used_percent,
+            ),
+            (
+                AlertMetric::LoadAverage,
+                Some(info.loadavg_one as f64),
+                thresholds.load_average,
+            ),
+        ];
+        observed.extend(info.disks.iter().map(|disk| {
+            (
+                AlertMetric::DiskUsed(disk.name.clone()),
+                Some(disk.used_percent()),
+                thresholds.disk_used_percent,
+            )
+        }));
+
+        observed
+            .into_iter()
+            .filter_map(|(metric, actual, threshold)| {
+                let (actual, threshold) = (actual?, threshold?);
+                (actual > threshold).then_some(Alert {
+                    metric,
+                    actual,
+                    threshold,
+                })
+            })
+            .collect()
+    }
+}
+
 // ─────────────────────────────────────────────────────────────────────────────
 // CVM lifecycle types
 // ─────────────────────────────────────────────────────────────────────────────
@@ -537,6 +1409,28 @@ pub struct CvmInfo {
     pub extra: HashMap<String, serde_json::Value>,
 }
 
+/// A publicly listed deployment from the directory at `GET /api/v1/cvms/listed`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeploymentSummary {
+    /// Application identifier of the deployment
+    pub app_id: String,
+
+    /// Name of the deployment
+    pub name: String,
+
+    /// Current status, if reported
+    #[serde(default)]
+    pub status: String,
+
+    /// Whether the deployment is listed publicly, if reported
+    #[serde(default)]
+    pub listed: Option<bool>,
+
+    /// Fields not modelled above
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_json::Value>,
+}
+
 /// CVM state from `GET /api/v1/cvms/{cvm_id}/state`.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CvmStateResponse {
@@ -544,6 +1438,178 @@ pub struct CvmStateResponse {
     pub is_running: bool,
 }
 
+/// A single application log line.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LogLine {
+    /// When the line was emitted, if reported
+    #[serde(default)]
+    pub timestamp: Option<String>,
+
+    /// The log message
+    pub message: String,
+}
+
+/// A page of logs from `GET /api/v1/cvms/{cvm_id}/logs`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogsResponse {
+    /// Log lines in emission order
+    #[serde(default)]
+    pub lines: Vec<LogLine>,
+
+    /// Cursor to pass as `since` to fetch only newer lines
+    #[serde(default)]
+    pub next_cursor: Option<String>,
+}
+
+/// Lifecycle status of a deployment, parsed from the CVM state `status` field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeploymentStatus {
+    /// The CVM is being created
+    Creating,
+    /// The CVM is booting
+    Starting,
+    /// The CVM is up and running
+    Running,
+    /// The CVM is shutting down
+    Stopping,
+    /// The CVM is stopped
+    Stopped,
+    /// The CVM failed to start or crashed
+    Failed,
+    /// Any status not recognised by this client
+    Unknown(String),
+}
+
+impl DeploymentStatus {
+    /// Parses a status string as returned by the API (case-insensitive).
+    pub fn parse(status: &str) -> Self {
+        match status.to_ascii_lowercase().as_str() {
+            "creating" | "pending" => Self::Creating,
+            "starting" | "booting" => Self::Starting,
+            "running" => Self::Running,
+            "stopping" | "shutting_down" => Self::Stopping,
+            "stopped" | "exited" => Self::Stopped,
+            "failed" | "error" => Self::Failed,
+            _ => Self::Unknown(status.to_string()),
+        }
+    }
+
+    /// Returns `true` if the deployment will stay in this status without
+    /// further action (running, stopped or failed).
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, Self::Running | Self::Stopped | Self::Failed)
+    }
+}
+
+impl std::fmt::Display for DeploymentStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Creating => write!(f, "creating"),
+            Self::Starting => write!(f, "starting"),
+            Self::Running => write!(f, "running"),
+            Self::Stopping => write!(f, "stopping"),
+            Self::Stopped => write!(f, "stopped"),
+            Self::Failed => write!(f, "failed"),
+            Self::Unknown(status) => write!(f, "{}", status),
+        }
+    }
+}
+
+/// Delay schedule used by the deployer's wait helpers between status checks.
+///
+/// Quick polls early in boot followed by a back-off keep latency low without
+/// hammering the API during long waits.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PollStrategy {
+    /// The same delay before every poll
+    Fixed(Duration),
+    /// Starts at `start` and grows by `step` after each poll, capped at `max`
+    Linear {
+        start: Duration,
+        step: Duration,
+        max: Duration,
+    },
+    /// Starts at `base` and is multiplied by `factor` after each poll, capped at `max`
+    Exponential {
+        base: Duration,
+        factor: f64,
+        max: Duration,
+    },
+}
+
+impl PollStrategy {
+    /// Returns the delay to wait after the poll numbered `attempt` (starting at 0).
+    pub fn delay(&self, attempt: u32) -> Duration {
+        match *self {
+            Self::Fixed(interval) => interval,
+            Self::Linear { start, step, max } => step
+                .checked_mul(attempt)
+                .and_then(|growth| start.checked_add(growth))
+                .map_or(max, |delay| delay.min(max)),
+            Self::Exponential { base, factor, max } => {
+                let secs = base.as_secs_f64() * factor.powi(attempt.min(i32::MAX as u32) as i32);
+                Duration::try_from_secs_f64(secs).map_or(max, |delay| delay.min(max))
+            }
+        }
+    }
+}
+
+impl From<Duration> for PollStrategy {
+    fn from(interval: Duration) -> Self {
+        Self::Fixed(interval)
+    }
+}
+
+/// API version and optional features from `GET /api/v1/version`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiCapabilities {
+    /// Version string reported by the API
+    pub version: String,
+
+    /// Names of the optional endpoints this API version serves (e.g. "delete")
+    #[serde(default)]
+    pub features: Vec<String>,
+}
+
+impl ApiCapabilities {
+    /// Returns `true` if the API reports `feature` (case-insensitive).
+    pub fn supports(&self, feature: &str) -> bool {
+        self.features
+            .iter()
+            .any(|f| f.eq_ignore_ascii_case(feature))
+    }
+
+    /// Returns `true` if CVMs can be deleted.
+    pub fn supports_delete(&self) -> bool {
+        self.supports("delete")
+    }
+
+    /// Returns `true` if application logs can be fetched.
+    pub fn supports_logs(&self) -> bool {
+        self.supports("logs")
+    }
+
+    /// Returns `true` if CVM resources can be resized.
+    pub fn supports_resize(&self) -> bool {
+        self.supports("resize")
+    }
+
+    /// Fails fast when `feature` is unavailable, instead of a raw 404 later.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::Unsupported` if the API does not report `feature`
+    pub fn require(&self, feature: &str) -> Result<(), Error> {
+        if self.supports(feature) {
+            return Ok(());
+        }
+        Err(Error::Unsupported {
+            feature: feature.to_string(),
+            api_version: self.version.clone(),
+        })
+    }
+}
+
 /// TEE attestation from `GET /api/v1/cvms/{cvm_id}/attestation`.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AttestationResponse {